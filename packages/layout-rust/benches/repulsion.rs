@@ -0,0 +1,154 @@
+//! Compares the scalar, SIMD and spatial hash repulsion kernels.
+//!
+//! ```bash
+//! $ cargo bench --bench repulsion
+//! $ RUSTFLAGS="-C target-feature=+avx2" cargo bench --bench repulsion
+//! ```
+
+#![feature(test)]
+
+extern crate test;
+
+use antv_layout::{Layout, LayoutType, NeighborSearch, Nodes, Settings};
+use test::Bencher;
+
+const NODES: usize = 2_000;
+
+fn build(
+    name: LayoutType,
+    dimensions: usize,
+    chunk_size: Option<usize>,
+    simd: bool,
+    neighbor_search: NeighborSearch,
+) -> Layout {
+    let positions = (0..NODES * dimensions)
+        .map(|i| ((i * 7_919) % 1_009) as f32 - 504.0)
+        .collect();
+    Layout::from_position_graph(
+        vec![],
+        Nodes::Mass(vec![1.0; NODES]),
+        positions,
+        None,
+        Settings {
+            name,
+            dimensions,
+            chunk_size,
+            simd,
+            kg: 0.0,
+            center: vec![0.0; dimensions],
+            max_distance: match neighbor_search {
+                NeighborSearch::AllPairs => f32::MAX.sqrt(),
+                NeighborSearch::Grid => 50.0,
+            },
+            neighbor_search,
+            ..Default::default()
+        },
+    )
+}
+
+macro_rules! bench_kernel {
+    ($fn:ident, $name:expr, $dimensions:expr, $chunk_size:expr, $simd:expr) => {
+        bench_kernel!(
+            $fn,
+            $name,
+            $dimensions,
+            $chunk_size,
+            $simd,
+            NeighborSearch::AllPairs
+        );
+    };
+    ($fn:ident, $name:expr, $dimensions:expr, $chunk_size:expr, $simd:expr, $search:expr) => {
+        #[bench]
+        fn $fn(b: &mut Bencher) {
+            let mut layout = build($name, $dimensions, $chunk_size, $simd, $search);
+            let mut i = 0;
+            b.iter(|| {
+                layout.iteration(i);
+                i += 1;
+            });
+        }
+    };
+}
+
+bench_kernel!(
+    forceatlas2_2d_serial,
+    LayoutType::ForceAtlas2,
+    2,
+    None,
+    false
+);
+bench_kernel!(
+    forceatlas2_2d_serial_simd,
+    LayoutType::ForceAtlas2,
+    2,
+    None,
+    true
+);
+bench_kernel!(
+    forceatlas2_2d_parallel,
+    LayoutType::ForceAtlas2,
+    2,
+    Some(256),
+    false
+);
+bench_kernel!(
+    forceatlas2_2d_parallel_simd,
+    LayoutType::ForceAtlas2,
+    2,
+    Some(256),
+    true
+);
+bench_kernel!(
+    forceatlas2_3d_serial,
+    LayoutType::ForceAtlas2,
+    3,
+    None,
+    false
+);
+bench_kernel!(
+    forceatlas2_3d_serial_simd,
+    LayoutType::ForceAtlas2,
+    3,
+    None,
+    true
+);
+bench_kernel!(force2_2d_serial, LayoutType::Force2, 2, None, false);
+bench_kernel!(force2_2d_serial_simd, LayoutType::Force2, 2, None, true);
+bench_kernel!(
+    fruchterman_2d_serial,
+    LayoutType::Fruchterman,
+    2,
+    None,
+    false
+);
+bench_kernel!(
+    fruchterman_2d_serial_simd,
+    LayoutType::Fruchterman,
+    2,
+    None,
+    true
+);
+bench_kernel!(
+    forceatlas2_2d_serial_grid,
+    LayoutType::ForceAtlas2,
+    2,
+    None,
+    false,
+    NeighborSearch::Grid
+);
+bench_kernel!(
+    forceatlas2_2d_serial_grid_simd,
+    LayoutType::ForceAtlas2,
+    2,
+    None,
+    true,
+    NeighborSearch::Grid
+);
+bench_kernel!(
+    forceatlas2_3d_serial_grid,
+    LayoutType::ForceAtlas2,
+    3,
+    None,
+    false,
+    NeighborSearch::Grid
+);
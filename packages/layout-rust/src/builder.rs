@@ -0,0 +1,270 @@
+use crate::edges::{normalize_edges, EdgeReport};
+use crate::layout::{Layout, Settings};
+use crate::util::{Edge, Nodes};
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::hash::Hash;
+
+/// Optional attributes of a node added to a `ForceGraphBuilder`
+#[derive(Clone, Debug, Default)]
+pub struct NodeOptions {
+    /// Defaults to the degree of the node, at least `1` for isolated nodes
+    pub mass: Option<f32>,
+    /// Diameter used with `prevent_overlapping`, defaults to its `node_size`
+    pub size: Option<f32>,
+    /// Initial position, defaults to a spiral around `Settings::center`
+    pub position: Option<Vec<f32>>,
+    /// Keep the node at its initial position
+    pub fixed: bool,
+}
+
+/// Builds a `Layout` from nodes identified by arbitrary ids
+///
+/// Edges may reference nodes not added yet: they are added with default options.
+/// Edges are normalized with `normalize_edges` when building.
+pub struct ForceGraphBuilder<Id> {
+    ids: Vec<Id>,
+    index: HashMap<Id, usize>,
+    nodes: Vec<NodeOptions>,
+    edges: Vec<Edge>,
+    weights: Vec<f32>,
+}
+
+/// A `Layout` along with the ids of its nodes
+pub struct ForceGraph<Id> {
+    pub layout: Layout,
+    /// What normalizing the edges changed
+    pub edge_report: EdgeReport,
+    ids: Vec<Id>,
+    index: HashMap<Id, usize>,
+}
+
+impl<Id: Clone + Eq + Hash> Default for ForceGraphBuilder<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id: Clone + Eq + Hash> ForceGraphBuilder<Id> {
+    pub fn new() -> Self {
+        Self {
+            ids: Vec::new(),
+            index: HashMap::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            weights: Vec::new(),
+        }
+    }
+
+    /// Adds a node, or replaces the options of an existing one. Returns its index.
+    pub fn add_node(&mut self, id: Id, options: NodeOptions) -> usize {
+        match self.index.get(&id) {
+            Some(&n) => {
+                self.nodes[n] = options;
+                n
+            }
+            None => self.insert(id, options),
+        }
+    }
+
+    /// Adds an edge between `source` and `target`
+    pub fn add_edge(&mut self, source: Id, target: Id, weight: f32) {
+        let n1 = self.node_index(source);
+        let n2 = self.node_index(target);
+        self.edges.push((n1, n2));
+        self.weights.push(weight);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    fn node_index(&mut self, id: Id) -> usize {
+        match self.index.get(&id) {
+            Some(&n) => n,
+            None => self.insert(id, NodeOptions::default()),
+        }
+    }
+
+    fn insert(&mut self, id: Id, options: NodeOptions) -> usize {
+        let n = self.ids.len();
+        self.index.insert(id.clone(), n);
+        self.ids.push(id);
+        self.nodes.push(options);
+        n
+    }
+
+    pub fn build(self, settings: Settings) -> ForceGraph<Id> {
+        let dimensions = settings.dimensions;
+        let (edges, weights, edge_report) = normalize_edges(&self.edges, Some(&self.weights));
+
+        let mut degrees = vec![0usize; self.nodes.len()];
+        for (n1, n2) in edges.iter() {
+            degrees[*n1] += 1;
+            degrees[*n2] += 1;
+        }
+        let masses = self
+            .nodes
+            .iter()
+            .zip(degrees)
+            .map(|(node, degree)| node.mass.unwrap_or(degree.max(1) as f32))
+            .collect();
+
+        let mut positions = Vec::with_capacity(self.nodes.len() * dimensions);
+        for (n, node) in self.nodes.iter().enumerate() {
+            match &node.position {
+                Some(position) => {
+                    assert_eq!(position.len(), dimensions);
+                    positions.extend_from_slice(position);
+                }
+                None => positions.extend(initial_position(n, dimensions, &settings.center)),
+            }
+        }
+
+        let sizes = if self.nodes.iter().any(|node| node.size.is_some()) {
+            let node_size = settings
+                .prevent_overlapping
+                .as_ref()
+                .map_or(0.0, |(node_size, _)| *node_size);
+            Some(
+                self.nodes
+                    .iter()
+                    .map(|node| node.size.unwrap_or(node_size))
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        let fixed = if self.nodes.iter().any(|node| node.fixed) {
+            Some(self.nodes.iter().map(|node| node.fixed).collect())
+        } else {
+            None
+        };
+
+        let mut layout =
+            Layout::from_position_graph(edges, Nodes::Mass(masses), positions, weights, settings);
+        layout.sizes = sizes;
+        layout.fixed = fixed;
+
+        ForceGraph {
+            layout,
+            edge_report,
+            ids: self.ids,
+            index: self.index,
+        }
+    }
+}
+
+/// Position of the `n`-th node without one, on a sunflower spiral (a ball in 3D)
+pub(crate) fn initial_position(n: usize, dimensions: usize, center: &[f32]) -> Vec<f32> {
+    let golden_angle = PI * (3.0 - 5f32.sqrt());
+    let t = n as f32 + 0.5;
+    let angle = n as f32 * golden_angle;
+    let mut position = if dimensions == 2 {
+        let r = 10.0 * t.sqrt();
+        vec![r * angle.cos(), r * angle.sin()]
+    } else {
+        let r = 10.0 * t.cbrt();
+        let z = 1.0 - 2.0 * (n as f32 * 0.618_034).fract();
+        let ring = (1.0 - z * z).sqrt();
+        let mut position = vec![r * ring * angle.cos(), r * ring * angle.sin(), r * z];
+        position.resize(dimensions, 0.0);
+        position
+    };
+    for (p, c) in position.iter_mut().zip(center.iter()) {
+        *p += c;
+    }
+    position
+}
+
+impl<Id: Eq + Hash> ForceGraph<Id> {
+    /// Index of the node `id` in the `Layout`
+    pub fn index(&self, id: &Id) -> Option<usize> {
+        self.index.get(id).cloned()
+    }
+
+    /// Ids of the nodes, by index
+    pub fn ids(&self) -> &[Id] {
+        &self.ids
+    }
+
+    pub fn position(&self, id: &Id) -> Option<&[f32]> {
+        self.index(id).map(|n| self.layout.points.get(n))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (&Id, &[f32])> {
+        self.ids.iter().zip(self.layout.points.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutType;
+
+    #[test]
+    fn test_builder() {
+        let mut builder = ForceGraphBuilder::new();
+        builder.add_node(
+            "hub",
+            NodeOptions {
+                position: Some(vec![0.0, 0.0]),
+                fixed: true,
+                ..Default::default()
+            },
+        );
+        for leaf in ["a", "b", "c"] {
+            builder.add_edge("hub", leaf, 1.0);
+        }
+        builder.add_edge("b", "hub", 2.0);
+        builder.add_edge("c", "c", 1.0);
+        assert_eq!(builder.len(), 4);
+
+        let mut graph = builder.build(Settings {
+            name: LayoutType::ForceAtlas2,
+            chunk_size: None,
+            ..Default::default()
+        });
+        assert_eq!(graph.edge_report.merged, 1);
+        assert_eq!(graph.edge_report.self_loops, 1);
+        assert_eq!(graph.layout.edges, vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(graph.layout.weights, Some(vec![1.0, 3.0, 1.0]));
+        assert_eq!(graph.layout.masses, vec![3.0, 1.0, 1.0, 1.0]);
+
+        let before = graph.position(&"a").unwrap().to_vec();
+        for i in 0..10 {
+            graph.layout.iteration(i);
+        }
+        assert_eq!(graph.position(&"hub"), Some(&[0.0, 0.0][..]));
+        assert_ne!(graph.position(&"a").unwrap(), &before[..]);
+        assert_eq!(graph.position(&"d"), None);
+
+        let ids: Vec<&str> = graph.positions().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec!["hub", "a", "b", "c"]);
+        assert_eq!(graph.index(&"c"), Some(3));
+    }
+
+    #[test]
+    fn test_builder_isolated_node() {
+        // Force2 divides by the masses, an isolated node must not have a mass of 0
+        let mut builder = ForceGraphBuilder::new();
+        builder.add_edge(0, 1, 1.0);
+        builder.add_node(2, NodeOptions::default());
+        let mut graph = builder.build(Settings {
+            name: LayoutType::Force2,
+            chunk_size: None,
+            ..Default::default()
+        });
+        assert_eq!(graph.layout.masses, vec![1.0, 1.0, 1.0]);
+
+        for i in 0..10 {
+            graph.layout.iteration(i);
+        }
+        assert!(graph.layout.points.iter().flatten().all(|x| x.is_finite()));
+    }
+}
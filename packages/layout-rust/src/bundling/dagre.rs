@@ -0,0 +1,51 @@
+use super::{bspline, hierarchical_bundling, HierarchicalOptions};
+use crate::util::{Edge, PointList};
+use crate::{GraphConfig, GraphEdge, GraphEdgePoint, GraphNode};
+
+use graphlib_rust::Graph;
+use std::collections::HashMap;
+
+/// Bundles the edges of a compound graph laid out by `layout`, along its clusters
+///
+/// The hierarchy is that of `Graph::set_parent`, clusters being at the center of their
+/// boxes. The `points` of each edge are replaced by `samples` points of its B-spline, from
+/// the center of its source to the center of its target.
+pub fn hierarchical_bundling_dagre(
+    graph: &mut Graph<GraphConfig, GraphNode, GraphEdge>,
+    options: &HierarchicalOptions,
+) {
+    let nodes = graph.nodes();
+    let index: HashMap<&String, usize> = nodes.iter().enumerate().map(|(i, v)| (v, i)).collect();
+    let mut points = PointList {
+        dimensions: 2,
+        points: Vec::with_capacity(nodes.len() * 2),
+    };
+    for v in nodes.iter() {
+        let (x, y) = graph.node(v).map_or((0.0, 0.0), |node| (node.x, node.y));
+        points.points.push(x);
+        points.points.push(y);
+    }
+    let parents: Vec<Option<usize>> = nodes
+        .iter()
+        .map(|v| graph.parent(v).and_then(|p| index.get(p).cloned()))
+        .collect();
+
+    let graph_edges = graph.edges();
+    let edges: Vec<Edge> = graph_edges
+        .iter()
+        .map(|e| (index[&e.v], index[&e.w]))
+        .collect();
+    let lines = hierarchical_bundling(&points, &parents, &edges, options);
+
+    for (e, control) in graph_edges.iter().zip(lines.iter()) {
+        if let Some(edge) = graph.edge_mut_with_obj(e) {
+            let curve = bspline(control, options.samples);
+            edge.points = Some(
+                curve
+                    .iter()
+                    .map(|p| GraphEdgePoint { x: p[0], y: p[1] })
+                    .collect(),
+            );
+        }
+    }
+}
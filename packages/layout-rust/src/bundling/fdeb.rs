@@ -0,0 +1,282 @@
+//! Force-directed edge bundling, after Holten & van Wijk, "Force-Directed Edge Bundling for
+//! Graph Visualization".
+//!
+//! Edges are subdivided into control points, joined by springs along each edge. Control
+//! points of compatible edges attract each other electrostatically. The simulation runs in
+//! cycles: each cycle doubles the number of control points, halves the step size and runs
+//! fewer iterations.
+
+use crate::util::{Edge, PointList};
+
+use rayon::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct FdebOptions {
+    /// Stiffness of the springs along an edge
+    pub stiffness: f32,
+    /// Number of subdivision cycles
+    pub cycles: usize,
+    /// Control points of each edge in the first cycle
+    pub initial_subdivisions: usize,
+    /// Growth of the number of control points from one cycle to the next
+    pub subdivision_rate: usize,
+    /// Step size of the first cycle, relative to the mean length of the edges
+    pub initial_step: f32,
+    /// Iterations of the first cycle
+    pub initial_iterations: usize,
+    /// Iterations of a cycle, relative to the previous one
+    pub iteration_rate: f32,
+    /// Edges less compatible than this do not attract each other, between `0` and `1`
+    pub compatibility_threshold: f32,
+}
+
+impl Default for FdebOptions {
+    fn default() -> Self {
+        Self {
+            stiffness: 0.1,
+            cycles: 6,
+            initial_subdivisions: 1,
+            subdivision_rate: 2,
+            initial_step: 0.001,
+            initial_iterations: 50,
+            iteration_rate: 2.0 / 3.0,
+            compatibility_threshold: 0.6,
+        }
+    }
+}
+
+fn sub(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b).map(|(x, y)| x - y).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn length(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// How well the projection of `q` on the line of `p` overlaps `p`
+fn visibility(p: (&[f32], &[f32]), q: (&[f32], &[f32])) -> f32 {
+    let axis = sub(p.1, p.0);
+    let length2 = dot(&axis, &axis);
+    let project = |x: &[f32]| -> Vec<f32> {
+        let t = dot(&sub(x, p.0), &axis) / length2;
+        p.0.iter()
+            .zip(axis.iter())
+            .map(|(o, a)| o + t * a)
+            .collect()
+    };
+    let (i0, i1) = (project(q.0), project(q.1));
+    let span = length(&sub(&i1, &i0));
+    if span == 0.0 {
+        return 0.0;
+    }
+    let mid_p: Vec<f32> = p.0.iter().zip(p.1).map(|(a, b)| (a + b) / 2.0).collect();
+    let mid_i: Vec<f32> = i0
+        .iter()
+        .zip(i1.iter())
+        .map(|(a, b)| (a + b) / 2.0)
+        .collect();
+    (1.0 - 2.0 * length(&sub(&mid_p, &mid_i)) / span).max(0.0)
+}
+
+/// Product of the angle, scale, position and visibility compatibilities of two edges,
+/// between `0` and `1`
+fn compatibility(p: (&[f32], &[f32]), q: (&[f32], &[f32])) -> f32 {
+    let (vp, vq) = (sub(p.1, p.0), sub(q.1, q.0));
+    let (lp, lq) = (length(&vp), length(&vq));
+    if lp == 0.0 || lq == 0.0 {
+        return 0.0;
+    }
+    let angle = (dot(&vp, &vq) / (lp * lq)).abs();
+    let mean = (lp + lq) / 2.0;
+    let scale = 2.0 / (mean / lp.min(lq) + lp.max(lq) / mean);
+    let midpoints = p.0.iter().zip(p.1).zip(q.0.iter().zip(q.1));
+    let gap = midpoints
+        .map(|((a, b), (c, d))| ((a + b) - (c + d)) / 2.0)
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt();
+    let position = mean / (mean + gap);
+    let visibility = visibility(p, q).min(visibility(q, p));
+    angle * scale * position * visibility
+}
+
+/// Points evenly spaced along the polyline `line`, `segments + 1` of them
+fn resample(line: &[f32], dimensions: usize, segments: usize) -> Vec<f32> {
+    let d = dimensions;
+    let vertices: Vec<&[f32]> = line.chunks(d).collect();
+    let lengths: Vec<f32> = vertices
+        .windows(2)
+        .map(|w| length(&sub(w[1], w[0])))
+        .collect();
+    let total: f32 = lengths.iter().sum();
+    let first = vertices[0];
+    let last = vertices[vertices.len() - 1];
+    if total == 0.0 {
+        return (0..=segments).flat_map(|_| first.iter().cloned()).collect();
+    }
+
+    let mut out = Vec::with_capacity((segments + 1) * d);
+    out.extend_from_slice(first);
+    let (mut v, mut walked) = (0, 0.0);
+    for s in 1..segments {
+        let target = total * s as f32 / segments as f32;
+        while v + 1 < lengths.len() && walked + lengths[v] < target {
+            walked += lengths[v];
+            v += 1;
+        }
+        let t = if lengths[v] > 0.0 {
+            ((target - walked) / lengths[v]).min(1.0)
+        } else {
+            0.0
+        };
+        let (a, b) = (vertices[v], vertices[v + 1]);
+        out.extend(a.iter().zip(b).map(|(x, y)| x + t * (y - x)));
+    }
+    out.extend_from_slice(last);
+    out
+}
+
+/// Bundles the `edges` between nodes at `points`, returning a polyline for each edge
+///
+/// Each polyline starts and ends at the nodes of its edge, and all have the same number of
+/// points. Edges of zero length, self-loops included, are not bundled. The compatibility of
+/// every pair of edges is computed once, in `O(m²)` time and up to `O(m²)` memory.
+pub fn fdeb(points: &PointList, edges: &[Edge], options: &FdebOptions) -> Vec<PointList> {
+    let d = points.dimensions;
+    let ends: Vec<(&[f32], &[f32])> = edges
+        .iter()
+        .map(|&(n1, n2)| (points.get(n1), points.get(n2)))
+        .collect();
+    let lengths: Vec<f32> = ends.iter().map(|(a, b)| length(&sub(b, a))).collect();
+    let bundled = lengths.iter().filter(|l| **l > 0.0).count();
+    let mean = lengths.iter().sum::<f32>() / bundled.max(1) as f32;
+
+    // Compatible edges, with whether they run the other way
+    let compatible: Vec<Vec<(usize, f32, bool)>> = (0..edges.len())
+        .into_par_iter()
+        .map(|e| {
+            (0..edges.len())
+                .filter(|f| *f != e)
+                .filter_map(|f| {
+                    let c = compatibility(ends[e], ends[f]);
+                    if c < options.compatibility_threshold || c == 0.0 {
+                        return None;
+                    }
+                    let reversed =
+                        dot(&sub(ends[e].1, ends[e].0), &sub(ends[f].1, ends[f].0)) < 0.0;
+                    Some((f, c, reversed))
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut lines: Vec<Vec<f32>> = ends
+        .iter()
+        .map(|(a, b)| a.iter().chain(b.iter()).cloned().collect())
+        .collect();
+    let mut subdivisions = options.initial_subdivisions.max(1);
+    let mut step = options.initial_step * mean;
+    let mut iterations = options.initial_iterations as f32;
+    // Closer control points attract as if that far apart, which bounds the steps
+    let closest = 1e-2 * mean;
+
+    for cycle in 0..options.cycles {
+        if cycle > 0 {
+            subdivisions *= options.subdivision_rate.max(1);
+            step /= 2.0;
+            iterations *= options.iteration_rate;
+        }
+        lines = lines
+            .iter()
+            .map(|line| resample(line, d, subdivisions + 1))
+            .collect();
+        let last = subdivisions + 1;
+
+        for _ in 0..iterations.round() as usize {
+            let moves: Vec<Vec<f32>> = lines
+                .par_iter()
+                .enumerate()
+                .map(|(e, line)| {
+                    let mut out = vec![0.0; line.len()];
+                    if lengths[e] == 0.0 {
+                        return out;
+                    }
+                    let k = options.stiffness / (lengths[e] * last as f32);
+                    for i in 1..last {
+                        let p = &line[i * d..(i + 1) * d];
+                        let force = &mut out[i * d..(i + 1) * d];
+                        let (prev, next) = (&line[(i - 1) * d..i * d], &line[(i + 1) * d..]);
+                        for (c, f) in force.iter_mut().enumerate() {
+                            *f = k * (prev[c] + next[c] - 2.0 * p[c]);
+                        }
+                        for &(other, weight, reversed) in compatible[e].iter() {
+                            let j = if reversed { last - i } else { i };
+                            let q = &lines[other][j * d..(j + 1) * d];
+                            let delta = sub(q, p);
+                            let dist2 = dot(&delta, &delta).max(closest * closest);
+                            for (f, x) in force.iter_mut().zip(delta) {
+                                *f += weight * x * mean / dist2;
+                            }
+                        }
+                        force.iter_mut().for_each(|f| *f *= step);
+                    }
+                    out
+                })
+                .collect();
+            for (line, delta) in lines.iter_mut().zip(moves) {
+                line.iter_mut().zip(delta).for_each(|(x, dx)| *x += dx);
+            }
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|points| PointList {
+            dimensions: d,
+            points,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fdeb() {
+        // Two close parallel edges, one running the other way, and a perpendicular one
+        let points = PointList {
+            dimensions: 2,
+            points: vec![
+                0.0, 0.0, 100.0, 0.0, // 0 -> 1
+                100.0, 10.0, 0.0, 10.0, // 3 -> 2
+                50.0, 50.0, 50.0, 150.0, // 4 -> 5
+            ],
+        };
+        let edges = vec![(0, 1), (3, 2), (4, 5)];
+        let options = FdebOptions::default();
+        let lines = fdeb(&points, &edges, &options);
+        assert_eq!(lines.len(), 3);
+
+        let size = 2 + options.initial_subdivisions * 2usize.pow(options.cycles as u32 - 1);
+        for (line, (n1, n2)) in lines.iter().zip(edges.iter()) {
+            assert_eq!(line.points.len(), size * 2);
+            assert_eq!(line.get(0), points.get(*n1));
+            assert_eq!(line.get(size - 1), points.get(*n2));
+        }
+
+        // The parallel edges meet in the middle, the perpendicular one stays straight
+        let (a, b) = (lines[0].get(size / 2), lines[1].get(size / 2));
+        assert!((a[1] - b[1]).abs() < 5.0);
+        assert!(a[1] > 0.5 && b[1] < 9.5);
+        assert!(lines[2].iter().all(|p| p[0] == 50.0));
+
+        // Resampling keeps the ends and spaces the points evenly
+        let line = resample(&[0.0, 0.0, 1.0, 0.0, 1.0, 3.0], 2, 4);
+        assert_eq!(line, vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 2.0, 1.0, 3.0]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Hierarchical edge bundling, after Holten, "Hierarchical Edge Bundles: Visualization of
+//! Adjacency Relations in Hierarchical Data".
+//!
+//! Each edge is routed along the path between its nodes in a hierarchy, through the
+//! positions of the clusters on the way. That path is the control polygon of a B-spline,
+//! straightened by the bundling strength `beta`: `1` follows the hierarchy, `0` draws the
+//! edge straight.
+
+use crate::util::{Edge, PointList};
+
+#[derive(Clone, Debug)]
+pub struct HierarchicalOptions {
+    /// Bundling strength, between `0` and `1`
+    pub beta: f32,
+    /// Points sampled along each spline by `hierarchical_bundling_dagre`
+    pub samples: usize,
+}
+
+impl Default for HierarchicalOptions {
+    fn default() -> Self {
+        Self {
+            beta: 0.85,
+            samples: 32,
+        }
+    }
+}
+
+/// Depth of every node of the forest of `parents`, roots being at depth `0`
+fn depths(parents: &[Option<usize>]) -> Vec<usize> {
+    let mut depths: Vec<Option<usize>> = vec![None; parents.len()];
+    let mut chain = Vec::new();
+    for v in 0..parents.len() {
+        let mut u = v;
+        while depths[u].is_none() {
+            chain.push(u);
+            assert!(chain.len() <= parents.len(), "the hierarchy has a cycle");
+            match parents[u] {
+                Some(p) => u = p,
+                None => break,
+            }
+        }
+        let mut depth = match depths[u] {
+            Some(depth) => depth + 1,
+            None => 0,
+        };
+        while let Some(w) = chain.pop() {
+            if depths[w].is_none() {
+                depths[w] = Some(depth);
+                depth += 1;
+            }
+        }
+    }
+    depths.into_iter().map(|depth| depth.unwrap()).collect()
+}
+
+/// Path from `a` to `b` in the forest of `parents`, with the index of their lowest common
+/// ancestor in it
+///
+/// Nodes of different trees are joined through their roots, without common ancestor.
+fn hierarchy_path(
+    parents: &[Option<usize>],
+    depths: &[usize],
+    a: usize,
+    b: usize,
+) -> (Vec<usize>, Option<usize>) {
+    let (mut up, mut down) = (vec![a], vec![b]);
+    let (mut u, mut v) = (a, b);
+    while u != v {
+        if depths[u] >= depths[v] {
+            match parents[u] {
+                Some(p) => u = p,
+                None => break,
+            }
+            up.push(u);
+        } else {
+            v = parents[v].unwrap();
+            down.push(v);
+        }
+    }
+    let lca = if u == v {
+        down.pop();
+        Some(up.len() - 1)
+    } else {
+        None
+    };
+    up.extend(down.into_iter().rev());
+    (up, lca)
+}
+
+/// Positions of the nodes of the hierarchy: those of `points` for the first nodes, the
+/// centroid of the nodes of `points` they contain for the other clusters
+fn hierarchy_positions(points: &PointList, parents: &[Option<usize>]) -> PointList {
+    let d = points.dimensions;
+    let n = points.points.len() / d;
+    let mut sums = vec![0.0; parents.len() * d];
+    let mut counts = vec![0usize; parents.len()];
+    for v in 0..n {
+        let mut u = parents[v];
+        while let Some(c) = u {
+            if c >= n {
+                counts[c] += 1;
+                for (s, x) in sums[c * d..(c + 1) * d].iter_mut().zip(points.get(v)) {
+                    *s += x;
+                }
+            }
+            u = parents[c];
+        }
+    }
+    sums[..n * d].copy_from_slice(&points.points);
+    for (c, count) in counts.iter().enumerate().skip(n) {
+        if *count > 0 {
+            sums[c * d..(c + 1) * d]
+                .iter_mut()
+                .for_each(|s| *s /= *count as f32);
+        }
+    }
+    PointList {
+        dimensions: d,
+        points: sums,
+    }
+}
+
+/// Control points of the B-spline of each edge between nodes at `points`
+///
+/// `parents` is the parent of each node of the hierarchy, `None` for roots. The first
+/// nodes of the hierarchy are those of `points`; the others are clusters, placed at the
+/// centroid of the nodes they contain. The lowest common ancestor of the nodes of an edge is
+/// left out of its path, unless it is one of them or the only node between them.
+pub fn hierarchical_bundling(
+    points: &PointList,
+    parents: &[Option<usize>],
+    edges: &[Edge],
+    options: &HierarchicalOptions,
+) -> Vec<PointList> {
+    let d = points.dimensions;
+    assert!(parents.len() * d >= points.points.len());
+    let depths = depths(parents);
+    let positions = hierarchy_positions(points, parents);
+    let beta = options.beta.clamp(0.0, 1.0);
+
+    edges
+        .iter()
+        .map(|&(a, b)| {
+            let (mut path, lca) = hierarchy_path(parents, &depths, a, b);
+            if let Some(lca) = lca {
+                if path.len() > 3 && lca > 0 && lca < path.len() - 1 {
+                    path.remove(lca);
+                }
+            }
+
+            let (start, end) = (positions.get(a), positions.get(b));
+            let last = (path.len() - 1).max(1) as f32;
+            let mut control = Vec::with_capacity(path.len() * d);
+            for (i, v) in path.iter().enumerate() {
+                let t = i as f32 / last;
+                control.extend(
+                    positions
+                        .get(*v)
+                        .iter()
+                        .zip(start.iter().zip(end))
+                        .map(|(p, (s, e))| beta * p + (1.0 - beta) * (s + t * (e - s))),
+                );
+            }
+            PointList {
+                dimensions: d,
+                points: control,
+            }
+        })
+        .collect()
+}
+
+/// Samples `samples` points, ends included, along the clamped uniform B-spline of the
+/// `control` points, of degree 3 or less when there are fewer than 4 of them
+pub fn bspline(control: &PointList, samples: usize) -> PointList {
+    let d = control.dimensions;
+    let count = control.points.len() / d;
+    if count < 2 || samples < 2 {
+        let first = &control.points[..d.min(control.points.len())];
+        return PointList {
+            dimensions: d,
+            points: (0..samples).flat_map(|_| first.iter().cloned()).collect(),
+        };
+    }
+    let degree = 3.min(count - 1);
+    let spans = count - degree;
+    // Clamped uniform knots, e.g. `0 0 0 0 1 2 2 2 2` for five control points
+    let knots: Vec<f32> = (0..count + degree + 1)
+        .map(|i| (i.max(degree) - degree).min(spans) as f32)
+        .collect();
+
+    let mut points = Vec::with_capacity(samples * d);
+    let mut work = vec![0.0; (degree + 1) * d];
+    for s in 0..samples {
+        let x = spans as f32 * s as f32 / (samples - 1) as f32;
+        // Knot span of `x`, the last one at the end
+        let k = ((x.floor() as usize).min(spans - 1)) + degree;
+        work.copy_from_slice(&control.points[(k - degree) * d..(k + 1) * d]);
+        // De Boor's algorithm
+        for r in 1..=degree {
+            for j in (r..=degree).rev() {
+                let i = j + k - degree;
+                let alpha = (x - knots[i]) / (knots[i + degree + 1 - r] - knots[i]);
+                let (lower, upper) = work.split_at_mut(j * d);
+                for (a, b) in upper[..d].iter_mut().zip(lower[(j - 1) * d..].iter()) {
+                    *a = (1.0 - alpha) * b + alpha * *a;
+                }
+            }
+        }
+        points.extend_from_slice(&work[degree * d..]);
+    }
+    PointList {
+        dimensions: d,
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hierarchical_bundling() {
+        // Leaves 0 to 3 in clusters 4 = {0, 1} and 5 = {2, 3}, under root 6
+        let points = PointList {
+            dimensions: 2,
+            points: vec![0.0, 0.0, 0.0, 2.0, 10.0, 0.0, 10.0, 2.0],
+        };
+        let parents = vec![Some(4), Some(4), Some(5), Some(5), Some(6), Some(6), None];
+        let edges = vec![(0, 2), (1, 3), (0, 1), (3, 3)];
+        let options = HierarchicalOptions {
+            beta: 1.0,
+            ..Default::default()
+        };
+        let lines = hierarchical_bundling(&points, &parents, &edges, &options);
+
+        // Through the centroids of both clusters, the root being left out
+        assert_eq!(
+            lines[0].points,
+            vec![0.0, 0.0, 0.0, 1.0, 10.0, 1.0, 10.0, 0.0]
+        );
+        // Siblings meet at their parent, kept as the only node between them
+        assert_eq!(lines[2].points, vec![0.0, 0.0, 0.0, 1.0, 0.0, 2.0]);
+        assert_eq!(lines[3].points, vec![10.0, 2.0]);
+
+        // Without bundling, control points are on the straight edge
+        let straight = HierarchicalOptions {
+            beta: 0.0,
+            ..Default::default()
+        };
+        let lines = hierarchical_bundling(&points, &parents, &edges, &straight);
+        assert!(lines[1].iter().all(|p| (p[1] - 2.0).abs() < 1e-6));
+
+        // The spline starts and ends at the nodes, and bends towards the bundle
+        let curve = bspline(
+            &hierarchical_bundling(&points, &parents, &edges, &options)[0],
+            9,
+        );
+        assert_eq!(curve.points.len(), 18);
+        assert_eq!(curve.get(0), &[0.0, 0.0]);
+        assert_eq!(curve.get(8), &[10.0, 0.0]);
+        assert!(curve.get(4)[1] > 0.5 && (curve.get(4)[0] - 5.0).abs() < 1e-4);
+    }
+}
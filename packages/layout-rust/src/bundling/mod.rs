@@ -0,0 +1,13 @@
+//! Edge bundling: routes edges as polylines that gather in bundles, to declutter dense
+//! drawings.
+//!
+//! Bundling is a post-processing step: it takes the positions of a finished layout and
+//! leaves the nodes where they are.
+
+mod dagre;
+mod fdeb;
+mod hierarchical;
+
+pub use self::dagre::hierarchical_bundling_dagre;
+pub use self::fdeb::{fdeb, FdebOptions};
+pub use self::hierarchical::{bspline, hierarchical_bundling, HierarchicalOptions};
@@ -0,0 +1,352 @@
+/*
+ * Layout of a bipartite graph on two layers, without the ranking, dummy nodes
+ * and subgraphs of the full layout.
+ *
+ * The order of each layer is improved by barycentric sweeps, alternately down
+ * and up as in `order`, keeping the order with the fewest crossings. Nodes are
+ * then placed along their layer by Brandes-Köpf, and edges are straight lines
+ * between the borders of their nodes.
+ */
+
+use crate::dagre::order::cross_count::two_layer_weighted_cross_count;
+use crate::dagre::order::sweep;
+use crate::dagre::position::bk::position_x;
+use crate::dagre::util::{intersect_rect, Rect};
+use crate::util::{Edge, PointList};
+use crate::{GraphConfig, GraphEdge, GraphEdgePoint, GraphNode};
+use graphlib_rust::{Graph, GraphOption};
+
+#[derive(Clone, Debug)]
+pub struct BipartiteOptions {
+    /// Gap between adjacent nodes of a layer
+    pub node_sep: f32,
+    /// Gap between the two layers
+    pub rank_sep: f32,
+    /// Sweeps without fewer crossings before the order is kept
+    pub sweeps: usize,
+    /// Alignment of Brandes-Köpf, one of `ul`, `ur`, `dl` and `dr`, `None` to balance them
+    pub align: Option<String>,
+}
+
+impl Default for BipartiteOptions {
+    fn default() -> Self {
+        Self {
+            node_sep: 50.0,
+            rank_sep: 50.0,
+            sweeps: 4,
+            align: None,
+        }
+    }
+}
+
+pub struct BipartiteLayout {
+    /// Nodes of the top and bottom layers, from left to right
+    pub layers: [Vec<usize>; 2],
+    /// Centers of the nodes
+    pub points: PointList,
+    /// Polyline of each edge, from the border of its source to that of its target
+    pub edges: Vec<PointList>,
+    /// Weighted crossings of the edges: each crossing counts the product of the weights
+    /// of its edges
+    pub crossings: f32,
+}
+
+fn assign_order(
+    graphs: &mut [&mut Graph<GraphConfig, GraphNode, GraphEdge>],
+    layering: &Vec<Vec<String>>,
+) {
+    for g in graphs.iter_mut() {
+        for layer in layering {
+            for (i, v) in layer.iter().enumerate() {
+                g.node_mut(v).unwrap().order = Some(i);
+            }
+        }
+    }
+}
+
+/// Lays out the bipartite graph of the nodes of `sizes`, `(width, height)`, on two layers
+///
+/// `bottom` tells the layer of each node, and each edge must join both layers, in any
+/// orientation. `weights` are those of the edges, `1` if `None`. Parallel edges add their
+/// weights. The top layer is at `y = 0`, and the leftmost node at `x = 0`.
+pub fn bipartite_layout(
+    sizes: &[(f32, f32)],
+    bottom: &[bool],
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+    options: &BipartiteOptions,
+) -> BipartiteLayout {
+    let n = sizes.len();
+    assert_eq!(bottom.len(), n);
+    let mut points = PointList {
+        dimensions: 2,
+        points: vec![0.0; n * 2],
+    };
+    if n == 0 {
+        return BipartiteLayout {
+            layers: [vec![], vec![]],
+            points,
+            edges: Vec::new(),
+            crossings: 0.0,
+        };
+    }
+
+    // Edges go down in `down` and up in `up`, barycenters being those of predecessors
+    let new_graph = || -> Graph<GraphConfig, GraphNode, GraphEdge> {
+        Graph::new(Some(GraphOption {
+            directed: Some(true),
+            multigraph: Some(false),
+            compound: Some(false),
+        }))
+    };
+    let mut down = new_graph();
+    down.set_graph(GraphConfig {
+        nodesep: Some(options.node_sep),
+        ranksep: Some(options.rank_sep),
+        align: options.align.clone(),
+        ..GraphConfig::default()
+    });
+    let mut up = new_graph();
+    let mut layering: Vec<Vec<String>> = vec![vec![], vec![]];
+    for (v, &(width, height)) in sizes.iter().enumerate() {
+        let rank = bottom[v] as usize;
+        let node = GraphNode {
+            width,
+            height,
+            rank: Some(rank as i32),
+            order: Some(layering[rank].len()),
+            ..Default::default()
+        };
+        down.set_node(v.to_string(), Some(node.clone()));
+        up.set_node(v.to_string(), Some(node));
+        layering[rank].push(v.to_string());
+    }
+    for (e, &(a, b)) in edges.iter().enumerate() {
+        assert_ne!(bottom[a], bottom[b], "edge within a layer");
+        let (t, s) = if bottom[a] { (b, a) } else { (a, b) };
+        let (t, s) = (t.to_string(), s.to_string());
+        let w = weights.map_or(1.0, |weights| weights[e]);
+        let w = w + down
+            .edge(&t, &s, None)
+            .and_then(|edge| edge.weight)
+            .unwrap_or(0.0);
+        for (g, v, u) in [(&mut down, &t, &s), (&mut up, &s, &t)] {
+            let _ = g.set_edge(
+                v,
+                u,
+                Some(GraphEdge {
+                    weight: Some(w),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+    }
+
+    let mut best = layering.clone();
+    let mut best_cc = two_layer_weighted_cross_count(&mut down, &mut layering, &0, &1);
+    let mut i = 0;
+    let mut last_best = 0;
+    while last_best < options.sweeps && best_cc > 0.0 {
+        let bias_right = i % 4 >= 2;
+        if i % 2 == 0 {
+            layering[1] = sweep(&down, &layering[1], bias_right);
+        } else {
+            layering[0] = sweep(&up, &layering[0], bias_right);
+        }
+        assign_order(&mut [&mut down, &mut up], &layering);
+
+        let cc = two_layer_weighted_cross_count(&mut down, &mut layering, &0, &1);
+        last_best += 1;
+        if cc < best_cc {
+            last_best = 0;
+            best = layering.clone();
+            best_cc = cc;
+        }
+        i += 1;
+    }
+    assign_order(&mut [&mut down], &best);
+
+    // Layers one below the other, as `position_y`
+    let heights: Vec<f32> = best
+        .iter()
+        .map(|layer| {
+            layer
+                .iter()
+                .map(|v| down.node(v).unwrap().height)
+                .fold(0.0, f32::max)
+        })
+        .collect();
+    let ys = [
+        heights[0] / 2.0,
+        heights[0] + options.rank_sep + heights[1] / 2.0,
+    ];
+    let xs = position_x(&mut down);
+    let left = xs
+        .iter()
+        .map(|(v, x)| x - down.node(v).unwrap().width / 2.0)
+        .fold(f32::INFINITY, f32::min);
+    for (v, x) in xs.iter() {
+        let i: usize = v.parse().unwrap();
+        points
+            .get_mut(i)
+            .copy_from_slice(&[x - left, ys[bottom[i] as usize]]);
+    }
+
+    let rect = |v: usize| Rect {
+        x: points.get(v)[0],
+        y: points.get(v)[1],
+        width: sizes[v].0,
+        height: sizes[v].1,
+    };
+    let center = |v: usize| GraphEdgePoint {
+        x: points.get(v)[0],
+        y: points.get(v)[1],
+    };
+    let lines = edges
+        .iter()
+        .map(|&(a, b)| {
+            let p = intersect_rect(&rect(a), &center(b));
+            let q = intersect_rect(&rect(b), &center(a));
+            PointList {
+                dimensions: 2,
+                points: vec![p.x, p.y, q.x, q.y],
+            }
+        })
+        .collect();
+
+    let index =
+        |layer: &Vec<String>| -> Vec<usize> { layer.iter().map(|v| v.parse().unwrap()).collect() };
+    BipartiteLayout {
+        layers: [index(&best[0]), index(&best[1])],
+        points,
+        edges: lines,
+        crossings: best_cc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(layer: &[usize]) -> Vec<usize> {
+        let mut layer = layer.to_vec();
+        layer.sort_unstable();
+        layer
+    }
+
+    #[test]
+    fn test_crossed_ladder() {
+        // 0 1 2 3
+        //  \ \/ /
+        // 7 6 5 4, each rung crossing all the others
+        let bottom: Vec<bool> = (0..8).map(|v| v >= 4).collect();
+        let edges = vec![(0, 7), (1, 6), (2, 5), (3, 4)];
+        let sizes = vec![(10.0, 10.0); 8];
+        let layout = bipartite_layout(&sizes, &bottom, &edges, None, &Default::default());
+        assert_eq!(layout.crossings, 0.0);
+        assert_eq!(sorted(&layout.layers[0]), [0, 1, 2, 3]);
+        assert_eq!(sorted(&layout.layers[1]), [4, 5, 6, 7]);
+        // Rungs are vertical
+        for &(a, b) in edges.iter() {
+            assert!((layout.points.get(a)[0] - layout.points.get(b)[0]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_separation() {
+        let sizes = vec![
+            (30.0, 10.0),
+            (10.0, 20.0),
+            (20.0, 10.0),
+            (40.0, 10.0),
+            (10.0, 30.0),
+        ];
+        let bottom = vec![false, false, false, true, true];
+        let edges = vec![(0, 4), (3, 1), (2, 3), (0, 3)];
+        let options = BipartiteOptions {
+            node_sep: 15.0,
+            rank_sep: 40.0,
+            ..Default::default()
+        };
+        let layout = bipartite_layout(
+            &sizes,
+            &bottom,
+            &edges,
+            Some(&[1.0, 2.0, 0.5, 1.0]),
+            &options,
+        );
+        assert_eq!(sorted(&layout.layers[0]), [0, 1, 2]);
+        assert_eq!(sorted(&layout.layers[1]), [3, 4]);
+
+        for layer in layout.layers.iter() {
+            for w in layer.windows(2) {
+                let gap = layout.points.get(w[1])[0] - layout.points.get(w[0])[0];
+                assert!(gap >= (sizes[w[0]].0 + sizes[w[1]].0) / 2.0 + 15.0 - 1e-3);
+            }
+        }
+        // Layers below one another, the leftmost node at 0
+        for (v, &side) in bottom.iter().enumerate() {
+            let y = if side { 20.0 + 40.0 + 15.0 } else { 10.0 };
+            assert_eq!(layout.points.get(v)[1], y);
+        }
+        let left = (0..5)
+            .map(|v| layout.points.get(v)[0] - sizes[v].0 / 2.0)
+            .fold(f32::INFINITY, f32::min);
+        assert!(left.abs() < 1e-3);
+
+        // Polylines go from border to border
+        let on_border = |v: usize, p: &[f32]| {
+            let c = layout.points.get(v);
+            let (dx, dy) = ((p[0] - c[0]).abs(), (p[1] - c[1]).abs());
+            let (w, h) = (sizes[v].0 / 2.0, sizes[v].1 / 2.0);
+            dx <= w + 1e-3 && dy <= h + 1e-3 && ((dx - w).abs() < 1e-3 || (dy - h).abs() < 1e-3)
+        };
+        for (&(a, b), line) in edges.iter().zip(layout.edges.iter()) {
+            assert_eq!(line.points.len(), 4);
+            assert!(on_border(a, line.get(0)));
+            assert!(on_border(b, line.get(1)));
+        }
+    }
+
+    #[test]
+    fn test_weighted_crossings() {
+        // K2,2 always has a crossing, the lightest is between the edges of weight 0.5
+        let bottom = vec![false, false, true, true];
+        let layout = bipartite_layout(
+            &[(10.0, 10.0); 4],
+            &bottom,
+            &[(0, 2), (0, 3), (1, 2), (1, 3)],
+            Some(&[1.0, 0.5, 0.5, 1.0]),
+            &Default::default(),
+        );
+        assert_eq!(layout.crossings, 0.25);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        // All nodes on a single side, without edges
+        let sizes = vec![(10.0, 10.0); 3];
+        for side in [false, true] {
+            let layout = bipartite_layout(&sizes, &[side; 3], &[], None, &Default::default());
+            assert_eq!(sorted(&layout.layers[side as usize]), [0, 1, 2]);
+            assert!(layout.layers[!side as usize].is_empty());
+            assert_eq!(layout.crossings, 0.0);
+            assert!(layout.points.iter().all(|p| p[0].is_finite()));
+        }
+
+        // Nodes with edges of weight 0 only have no barycenter, and keep their position
+        let bottom = vec![false, false, false, true, true, true];
+        let layout = bipartite_layout(
+            &[(10.0, 10.0); 6],
+            &bottom,
+            &[(0, 4), (1, 3), (2, 5)],
+            Some(&[1.0, 1.0, 0.0]),
+            &Default::default(),
+        );
+        assert_eq!(layout.crossings, 0.0);
+        assert_eq!(layout.layers[0][2], 2);
+        assert_eq!(layout.layers[1][2], 5);
+        assert!(layout.points.iter().all(|p| p[0].is_finite()));
+    }
+}
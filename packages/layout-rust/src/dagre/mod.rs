@@ -10,6 +10,7 @@ use crate::{GraphConfig, GraphEdge, GraphEdgePoint, GraphNode};
 use graphlib_rust::{Graph, GraphOption};
 
 pub mod acyclic;
+pub mod bipartite;
 pub mod greedy_fas;
 pub mod add_border_segments;
 pub mod coordinate_system;
@@ -25,20 +25,72 @@ pub fn barycenter(
             }
 
             //( sum, weight )
-            let mut result = (0, 0);
+            let mut result = (0.0, 0.0);
             in_v.iter().for_each(|e| {
                 let edge = g.edge_with_obj(&e).unwrap();
                 let node_u = g.node(&e.v).unwrap();
-                let edge_weight = edge.weight.clone().unwrap_or(0.0) as i32;
-                result.0 += edge_weight * (node_u.order.clone().unwrap_or(0) as i32);
+                let edge_weight = edge.weight.clone().unwrap_or(0.0);
+                result.0 += edge_weight * (node_u.order.clone().unwrap_or(0) as f32);
                 result.1 += edge_weight;
             });
 
+            // Without weight, e.g. edges of weight 0, there is no barycenter to sort by
+            if result.1 <= 0.0 {
+                return Barycenter {
+                    v: v.clone(),
+                    barycenter: None,
+                    weight: None,
+                };
+            }
+
             return Barycenter {
                 v: v.clone(),
-                barycenter: Some(result.0.clone() as f32 / result.1.clone() as f32),
-                weight: Some(result.1 as f32),
+                barycenter: Some(result.0 / result.1),
+                weight: Some(result.1),
             };
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_barycenter() {
+        let mut g: Graph<GraphConfig, GraphNode, GraphEdge> = Graph::new(None);
+        for (v, order) in [("a", 2), ("b", 4), ("c", 0), ("x", 0), ("y", 0), ("z", 0)] {
+            g.set_node(
+                v.to_string(),
+                Some(GraphNode {
+                    order: Some(order),
+                    ..Default::default()
+                }),
+            );
+        }
+        for (v, w, weight) in [
+            ("a", "x", 1.0),
+            ("b", "x", 0.5),
+            ("a", "y", 0.0),
+            ("c", "y", 0.0),
+        ] {
+            let _ = g.set_edge(
+                &v.to_string(),
+                &w.to_string(),
+                Some(GraphEdge {
+                    weight: Some(weight),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        let results = barycenter(&g, &vec!["x".to_string(), "y".to_string(), "z".to_string()]);
+        // Weighted mean of the orders of the predecessors, without truncating weights
+        assert_eq!(results[0].barycenter, Some((2.0 + 0.5 * 4.0) / 1.5));
+        assert_eq!(results[0].weight, Some(1.5));
+        // Edges of weight 0 and no edges give no barycenter
+        assert_eq!(results[1].barycenter, None);
+        assert_eq!(results[2].barycenter, None);
+    }
+}
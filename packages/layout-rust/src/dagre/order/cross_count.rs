@@ -33,12 +33,23 @@ pub fn cross_count(
     return cc;
 }
 
+/// `two_layer_weighted_cross_count` rounded to the nearest integer
 pub fn two_layer_cross_count(
     g: &mut Graph<GraphConfig, GraphNode, GraphEdge>,
     layering: &mut Vec<Vec<String>>,
     north_idx: &usize,
     south_idx: &usize,
 ) -> usize {
+    two_layer_weighted_cross_count(g, layering, north_idx, south_idx).round() as usize
+}
+
+/// Sum over the pairs of crossing edges between two layers of the product of their weights
+pub fn two_layer_weighted_cross_count(
+    g: &mut Graph<GraphConfig, GraphNode, GraphEdge>,
+    layering: &mut Vec<Vec<String>>,
+    north_idx: &usize,
+    south_idx: &usize,
+) -> f32 {
     // Sort all of the edges between the north and south layers by their position
     // in the north layer and then the south. Map these edges to the position of
     // their head in the south layer.
@@ -81,24 +92,127 @@ pub fn two_layer_cross_count(
     let tree_size = 2 * first_index - 1;
     first_index -= 1;
 
-    let mut tree: Vec<usize> = vec![0; tree_size];
+    let mut tree: Vec<f32> = vec![0.0; tree_size];
 
     // Calculate the weighted crossings
-    let mut cc = 0;
+    let mut cc = 0.0;
     south_entries.iter().for_each(|entry| {
         let mut idx = entry.0 + first_index;
-        tree.insert(idx, tree[idx] + (entry.1 as usize));
+        tree[idx] += entry.1;
 
-        let mut weight_sum: usize = 0;
+        let mut weight_sum = 0.0;
         while idx > 0 {
             if idx % 2 != 0 {
                 weight_sum += tree[idx + 1];
             }
             idx = (idx - 1) >> 1;
-            tree[idx] += entry.1 as usize;
+            tree[idx] += entry.1;
         }
-        cc += entry.1 as usize * weight_sum;
+        cc += entry.1 * weight_sum;
     });
 
     cc
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str, f32)]) -> Graph<GraphConfig, GraphNode, GraphEdge> {
+        let mut g: Graph<GraphConfig, GraphNode, GraphEdge> = Graph::new(None);
+        for &(v, w, weight) in edges {
+            for u in [v, w] {
+                g.set_node(u.to_string(), Some(GraphNode::default()));
+            }
+            let _ = g.set_edge(
+                &v.to_string(),
+                &w.to_string(),
+                Some(GraphEdge {
+                    weight: Some(weight),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+        g
+    }
+
+    fn layering(layers: &[&[&str]]) -> Vec<Vec<String>> {
+        layers
+            .iter()
+            .map(|layer| layer.iter().map(|v| v.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_two_layer_cross_count() {
+        let mut g = graph(&[("a1", "b1", 1.0), ("a2", "b2", 1.0)]);
+        assert_eq!(cross_count(&mut g, &mut vec![]), 0);
+        assert_eq!(
+            cross_count(&mut g, &mut layering(&[&["a1", "a2"], &["b1", "b2"]])),
+            0
+        );
+        assert_eq!(
+            two_layer_cross_count(
+                &mut g,
+                &mut layering(&[&["a1", "a2"], &["b2", "b1"]]),
+                &0,
+                &1
+            ),
+            1
+        );
+
+        // Crossings are the products of the weights of the edges
+        let mut g = graph(&[("a1", "b1", 2.0), ("a2", "b2", 3.0)]);
+        assert_eq!(
+            cross_count(&mut g, &mut layering(&[&["a1", "a2"], &["b2", "b1"]])),
+            6
+        );
+        let mut g = graph(&[("a1", "b1", 0.5), ("a2", "b2", 3.0)]);
+        assert_eq!(
+            cross_count(&mut g, &mut layering(&[&["a1", "a2"], &["b2", "b1"]])),
+            2
+        );
+    }
+
+    #[test]
+    fn test_cross_count_across_layers() {
+        let mut g = graph(&[
+            ("a1", "b1", 1.0),
+            ("a2", "b2", 1.0),
+            ("b1", "c1", 1.0),
+            ("b2", "c2", 1.0),
+        ]);
+        assert_eq!(
+            cross_count(
+                &mut g,
+                &mut layering(&[&["a1", "a2"], &["b2", "b1"], &["c1", "c2"]])
+            ),
+            2
+        );
+
+        let mut g = graph(&[
+            ("a", "b", 1.0),
+            ("b", "c", 1.0),
+            ("d", "e", 1.0),
+            ("e", "c", 1.0),
+            ("a", "f", 1.0),
+            ("f", "i", 1.0),
+            ("a", "e", 1.0),
+        ]);
+        assert_eq!(
+            cross_count(
+                &mut g,
+                &mut layering(&[&["a", "d"], &["b", "e", "f"], &["c", "i"]])
+            ),
+            1
+        );
+        assert_eq!(
+            cross_count(
+                &mut g,
+                &mut layering(&[&["d", "a"], &["e", "b", "f"], &["c", "i"]])
+            ),
+            0
+        );
+    }
+}
@@ -8,9 +8,12 @@ pub mod sort;
 pub mod sort_subgraph;
 
 use crate::dagre::order::add_subgraph_constraints::add_subgraph_constraints;
+use crate::dagre::order::barycenter::barycenter;
 use crate::dagre::order::build_layer_graph::{build_layer_graph, GraphRelationship};
 use crate::dagre::order::cross_count::cross_count;
 use crate::dagre::order::init_order::init_order;
+use crate::dagre::order::resolve_conflicts::ResolvedBaryEntry;
+use crate::dagre::order::sort::sort;
 use crate::dagre::order::sort_subgraph::sort_subgraph;
 use crate::dagre::util;
 use crate::{GraphConfig, GraphEdge, GraphNode};
@@ -104,6 +107,31 @@ fn sweep_layer_graphs(
     })
 }
 
+/// Orders `layer` by the barycenters of its predecessors in `g`, nodes without any keeping
+/// their position
+pub(crate) fn sweep(
+    g: &Graph<GraphConfig, GraphNode, GraphEdge>,
+    layer: &Vec<String>,
+    bias_right: bool,
+) -> Vec<String> {
+    // No constraints within a layer, each node is an entry of its own
+    let entries: Vec<ResolvedBaryEntry> = barycenter(g, layer)
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| ResolvedBaryEntry {
+            indegree: 0,
+            _in: vec![],
+            _out: vec![],
+            vs: vec![entry.v],
+            i,
+            barycenter: entry.barycenter,
+            weight: entry.weight,
+            merged: None,
+        })
+        .collect();
+    sort(&entries, &bias_right).vs
+}
+
 fn assign_order(g: &mut Graph<GraphConfig, GraphNode, GraphEdge>, layering: &Vec<Vec<String>>) {
     for layer in layering {
         for (i, v) in layer.iter().enumerate() {
@@ -20,7 +20,7 @@ pub fn sort(entries: &Vec<ResolvedBaryEntry>, bias_right: &bool) -> SubgraphResu
     let mut weight = 0.0;
     let mut vs_index: usize = 0;
 
-    vs_index = consume_unsortable(&mut vs, &mut sortable, &mut vs_index);
+    vs_index = consume_unsortable(&mut vs, &mut unsortable, &mut vs_index);
     sortable.iter().for_each(|entry| {
         vs_index += entry.vs.len();
         vs.append(entry.vs.clone().as_mut());
@@ -48,10 +48,9 @@ fn consume_unsortable(
     if unsortable.len() == 0 {
         return index.clone();
     }
-    let mut last: ResolvedBaryEntry = unsortable.get(unsortable.len() - 1).cloned().unwrap();
-    while unsortable.len() > 0 && &last.i <= index {
+    while unsortable.len() > 0 && &unsortable[unsortable.len() - 1].i <= index {
+        let last = unsortable.pop().unwrap();
         vs.append(last.vs.clone().as_mut());
-        last = unsortable.pop().unwrap();
         *index += 1;
     }
     return index.clone();
@@ -62,12 +61,12 @@ fn compare_with_bias(
     entry_w: &ResolvedBaryEntry,
     bias: &bool,
 ) -> Ordering {
-    let barycenter_v = entry_v.barycenter.clone().unwrap_or(0.0) as i32;
-    let barycenter_w = entry_w.barycenter.clone().unwrap_or(0.0) as i32;
+    let barycenter_v = entry_v.barycenter.clone().unwrap_or(0.0);
+    let barycenter_w = entry_w.barycenter.clone().unwrap_or(0.0);
     if barycenter_v < barycenter_w {
-        return Ordering::Greater;
-    } else if barycenter_v > barycenter_w {
         return Ordering::Less;
+    } else if barycenter_v > barycenter_w {
+        return Ordering::Greater;
     }
 
     return if !bias {
@@ -76,3 +75,99 @@ fn compare_with_bias(
         entry_w.i.cmp(&entry_v.i)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(vs: &[&str], i: usize, barycenter: Option<(f32, f32)>) -> ResolvedBaryEntry {
+        ResolvedBaryEntry {
+            indegree: 0,
+            _in: vec![],
+            _out: vec![],
+            vs: vs.iter().map(|v| v.to_string()).collect(),
+            i,
+            barycenter: barycenter.map(|b| b.0),
+            weight: barycenter.map(|b| b.1),
+            merged: None,
+        }
+    }
+
+    fn vs(result: &SubgraphResult) -> Vec<&str> {
+        result.vs.iter().map(|v| v.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_by_barycenter() {
+        let input = vec![
+            entry(&["a"], 0, Some((2.0, 3.0))),
+            entry(&["b"], 1, Some((1.0, 2.0))),
+        ];
+        let result = sort(&input, &false);
+        assert_eq!(vs(&result), ["b", "a"]);
+        assert_eq!(result.barycenter, (2.0 * 3.0 + 1.0 * 2.0) / (3.0 + 2.0));
+        assert_eq!(result.weight, 5.0);
+
+        // Super-nodes keep their nodes together
+        let input = vec![
+            entry(&["a", "c", "d"], 0, Some((2.0, 3.0))),
+            entry(&["b"], 1, Some((1.0, 2.0))),
+        ];
+        assert_eq!(vs(&sort(&input, &false)), ["b", "a", "c", "d"]);
+
+        // Fractional barycenters are not truncated
+        let input = vec![
+            entry(&["a"], 0, Some((1.5, 1.0))),
+            entry(&["b"], 1, Some((1.25, 1.0))),
+        ];
+        assert_eq!(vs(&sort(&input, &false)), ["b", "a"]);
+    }
+
+    #[test]
+    fn test_sort_bias() {
+        let input = vec![
+            entry(&["a"], 0, Some((1.0, 1.0))),
+            entry(&["b"], 1, Some((1.0, 1.0))),
+        ];
+        assert_eq!(vs(&sort(&input, &false)), ["a", "b"]);
+        let result = sort(&input, &true);
+        assert_eq!(vs(&result), ["b", "a"]);
+        assert_eq!(result.barycenter, 1.0);
+        assert_eq!(result.weight, 2.0);
+    }
+
+    #[test]
+    fn test_sort_without_barycenter() {
+        let input = vec![
+            entry(&["a"], 0, Some((2.0, 1.0))),
+            entry(&["b"], 1, Some((6.0, 1.0))),
+            entry(&["c"], 2, None),
+            entry(&["d"], 3, Some((3.0, 1.0))),
+        ];
+        let result = sort(&input, &false);
+        assert_eq!(vs(&result), ["a", "d", "c", "b"]);
+        assert_eq!(result.barycenter, (2.0 + 6.0 + 3.0) / 3.0);
+        assert_eq!(result.weight, 3.0);
+
+        let input = vec![
+            entry(&["a"], 0, None),
+            entry(&["b"], 3, None),
+            entry(&["c"], 2, None),
+            entry(&["d"], 1, None),
+        ];
+        let result = sort(&input, &false);
+        assert_eq!(vs(&result), ["a", "d", "c", "b"]);
+        assert_eq!(result.weight, 0.0);
+
+        let input = vec![
+            entry(&["a"], 0, Some((0.0, 1.0))),
+            entry(&["b"], 3, None),
+            entry(&["c"], 2, None),
+            entry(&["d"], 1, None),
+        ];
+        let result = sort(&input, &false);
+        assert_eq!(vs(&result), ["a", "d", "c", "b"]);
+        assert_eq!(result.barycenter, 0.0);
+        assert_eq!(result.weight, 1.0);
+    }
+}
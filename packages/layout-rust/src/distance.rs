@@ -0,0 +1,181 @@
+//! Shortest path distances, for the layouts reproducing them in the plane
+
+use crate::util::{bfs_distances, Edge};
+
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Neighbours of each node and the length of the edge to them, `1` without `lengths`
+pub fn weighted_adjacency(
+    n: usize,
+    edges: &[Edge],
+    lengths: Option<&[f32]>,
+) -> Vec<Vec<(usize, f32)>> {
+    if let Some(lengths) = lengths {
+        assert_eq!(lengths.len(), edges.len());
+    }
+    let mut adjacency = vec![Vec::new(); n];
+    for (e, &(n1, n2)) in edges.iter().enumerate() {
+        let length = lengths.map_or(1.0, |lengths| lengths[e]);
+        adjacency[n1].push((n2, length));
+        adjacency[n2].push((n1, length));
+    }
+    adjacency
+}
+
+/// Node of the Dijkstra queue, closest first
+struct Candidate(f32, usize);
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+/// Length of the shortest path from `source` to each node, infinite for unreachable nodes
+pub fn shortest_paths(adjacency: &[Vec<(usize, f32)>], source: usize) -> Vec<f32> {
+    let mut distances = vec![f32::INFINITY; adjacency.len()];
+    let mut queue = BinaryHeap::new();
+    distances[source] = 0.0;
+    queue.push(Candidate(0.0, source));
+    while let Some(Candidate(d, n1)) = queue.pop() {
+        if d > distances[n1] {
+            continue;
+        }
+        for &(n2, length) in adjacency[n1].iter() {
+            if d + length < distances[n2] {
+                distances[n2] = d + length;
+                queue.push(Candidate(d + length, n2));
+            }
+        }
+    }
+    distances
+}
+
+/// Shortest paths from every node, computed in parallel
+pub fn all_shortest_paths(adjacency: &[Vec<(usize, f32)>]) -> Vec<Vec<f32>> {
+    (0..adjacency.len())
+        .into_par_iter()
+        .map(|source| shortest_paths(adjacency, source))
+        .collect()
+}
+
+/// `k` pivots spread over the graph, each the node furthest from the previous ones, starting
+/// from `first`, with the shortest paths from them
+///
+/// Nodes unreachable from the pivots so far are furthest, so that every connected component
+/// gets a pivot when `k` allows it. When all the edges are `1` long, paths are found by
+/// breadth-first search instead of Dijkstra's algorithm.
+pub fn max_min_pivots(
+    adjacency: &[Vec<(usize, f32)>],
+    k: usize,
+    first: usize,
+) -> (Vec<usize>, Vec<Vec<f32>>) {
+    let n = adjacency.len();
+    let k = k.min(n);
+    let mut pivots = Vec::with_capacity(k);
+    let mut rows = Vec::with_capacity(k);
+    let mut closest = vec![f32::INFINITY; n];
+    let unit = adjacency.iter().flatten().all(|(_, length)| *length == 1.0);
+    let hops: Vec<Vec<usize>> = if unit {
+        adjacency
+            .iter()
+            .map(|neighbors| neighbors.iter().map(|(n2, _)| *n2).collect())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut pivot = first;
+    while pivots.len() < k {
+        let row: Vec<f32> = if unit {
+            bfs_distances(&hops, pivot)
+                .into_iter()
+                .map(|d| {
+                    if d == usize::MAX {
+                        f32::INFINITY
+                    } else {
+                        d as f32
+                    }
+                })
+                .collect()
+        } else {
+            shortest_paths(adjacency, pivot)
+        };
+        for (c, d) in closest.iter_mut().zip(row.iter()) {
+            *c = c.min(*d);
+        }
+        pivots.push(pivot);
+        rows.push(row);
+        pivot = (0..n)
+            .max_by(|a, b| closest[*a].total_cmp(&closest[*b]).then(b.cmp(a)))
+            .unwrap_or(0);
+    }
+    (pivots, rows)
+}
+
+/// Replaces infinite distances by one more than the longest finite one, so that separate
+/// connected components are laid out side by side
+pub(crate) fn fill_unreachable(rows: &mut [Vec<f32>]) {
+    let longest = rows
+        .iter()
+        .flatten()
+        .filter(|d| d.is_finite())
+        .fold(0.0f32, |a, b| a.max(*b));
+    for d in rows.iter_mut().flatten() {
+        if !d.is_finite() {
+            *d = longest + 1.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_paths() {
+        // A square with a long diagonal, and an isolated node
+        let edges = vec![(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)];
+        let adjacency = weighted_adjacency(5, &edges, Some(&[1.0, 2.0, 1.0, 1.0, 5.0]));
+        assert_eq!(
+            shortest_paths(&adjacency, 0),
+            vec![0.0, 1.0, 2.0, 1.0, f32::INFINITY]
+        );
+
+        let mut rows = all_shortest_paths(&adjacency);
+        assert_eq!(rows[1][3], 2.0);
+        fill_unreachable(&mut rows);
+        assert_eq!(rows[4], vec![3.0, 3.0, 3.0, 3.0, 0.0]);
+
+        let (pivots, rows) = max_min_pivots(&adjacency, 3, 0);
+        assert_eq!(pivots, vec![0, 4, 2]);
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn test_max_min_pivots_unit_lengths() {
+        // Breadth-first search gives the same rows as Dijkstra's algorithm
+        let edges = vec![(0, 1), (1, 2), (2, 3), (3, 0), (0, 2), (4, 5)];
+        let adjacency = weighted_adjacency(7, &edges, None);
+        let (pivots, rows) = max_min_pivots(&adjacency, 4, 0);
+        assert_eq!(pivots, vec![0, 4, 6, 1]);
+        for (p, row) in pivots.iter().zip(rows.iter()) {
+            assert_eq!(row, &shortest_paths(&adjacency, *p));
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::util::Edge;
+
+use std::collections::HashMap;
+
+/// What `normalize_edges` changed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EdgeReport {
+    /// Edges given as `(n1, n2)` with `n1 > n2`, swapped
+    pub reversed: usize,
+    /// Edges merged into a previous edge between the same nodes
+    pub merged: usize,
+    /// Self-loops dropped
+    pub self_loops: usize,
+}
+
+impl EdgeReport {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Canonicalises an edge list for the force layouts.
+///
+/// Edges are oriented as `(n1, n2)` with `n1 < n2`, duplicates are merged by summing their
+/// weights, and self-loops are dropped. Edges keep the order of their first occurrence.
+///
+/// Without `weights`, each edge weighs `1.0`: weights are only returned if merging made
+/// them differ.
+pub fn normalize_edges(
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+) -> (Vec<Edge>, Option<Vec<f32>>, EdgeReport) {
+    canonicalize_edges(edges, weights, true)
+}
+
+/// Orients the edges as `(n1, n2)` with `n1 < n2` and drops self-loops, like
+/// `normalize_edges` but keeping duplicates as is.
+///
+/// `weights` are returned as given, minus the ones of self-loops.
+pub fn orient_edges(
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+) -> (Vec<Edge>, Option<Vec<f32>>, EdgeReport) {
+    canonicalize_edges(edges, weights, false)
+}
+
+fn canonicalize_edges(
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+    merge: bool,
+) -> (Vec<Edge>, Option<Vec<f32>>, EdgeReport) {
+    if let Some(weights) = weights {
+        assert_eq!(weights.len(), edges.len());
+    }
+
+    let mut report = EdgeReport::default();
+    let mut index: HashMap<Edge, usize> =
+        HashMap::with_capacity(if merge { edges.len() } else { 0 });
+    let mut out_edges = Vec::with_capacity(edges.len());
+    let mut out_weights = Vec::with_capacity(edges.len());
+
+    for (i, &(n1, n2)) in edges.iter().enumerate() {
+        let weight = weights.map_or(1.0, |weights| weights[i]);
+        if n1 == n2 {
+            report.self_loops += 1;
+            continue;
+        }
+        let edge = if n1 > n2 {
+            report.reversed += 1;
+            (n2, n1)
+        } else {
+            (n1, n2)
+        };
+        if merge {
+            if let Some(&j) = index.get(&edge) {
+                report.merged += 1;
+                out_weights[j] += weight;
+                continue;
+            }
+            index.insert(edge, out_edges.len());
+        }
+        out_edges.push(edge);
+        out_weights.push(weight);
+    }
+
+    let out_weights = if weights.is_some() || report.merged > 0 {
+        Some(out_weights)
+    } else {
+        None
+    };
+    (out_edges, out_weights, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_edges() {
+        let (edges, weights, report) =
+            normalize_edges(&[(5, 2), (0, 1), (2, 5), (3, 3), (1, 0)], None);
+        assert_eq!(edges, vec![(2, 5), (0, 1)]);
+        assert_eq!(weights, Some(vec![2.0, 2.0]));
+        assert_eq!(
+            report,
+            EdgeReport {
+                reversed: 2,
+                merged: 2,
+                self_loops: 1,
+            }
+        );
+
+        let (edges, weights, report) =
+            normalize_edges(&[(0, 1), (1, 2), (1, 1)], Some(&[0.5, 2.0, 3.0]));
+        assert_eq!(edges, vec![(0, 1), (1, 2)]);
+        assert_eq!(weights, Some(vec![0.5, 2.0]));
+        assert_eq!(report.self_loops, 1);
+
+        let (_, weights, report) = normalize_edges(&[(0, 1), (1, 2)], None);
+        assert_eq!(weights, None);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn test_orient_edges() {
+        let (edges, weights, report) = orient_edges(
+            &[(5, 2), (0, 1), (2, 5), (3, 3), (1, 0)],
+            Some(&[1.0, 2.0, 3.0, 4.0, 5.0]),
+        );
+        assert_eq!(edges, vec![(2, 5), (0, 1), (2, 5), (0, 1)]);
+        assert_eq!(weights, Some(vec![1.0, 2.0, 3.0, 5.0]));
+        assert_eq!(
+            report,
+            EdgeReport {
+                reversed: 2,
+                merged: 0,
+                self_loops: 1,
+            }
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::layout::Layout;
+use crate::layout::{Layout, LayoutType};
 
 use itertools::izip;
 
@@ -16,6 +16,9 @@ pub fn apply_attraction_force2_2d(layout: &mut Layout) {
         let dy = unsafe { *n1_pos.get_unchecked(1) - *n2_pos.get_unchecked(1) };
 
         let dist = (dx * dx + dy * dy).sqrt();
+        if dist == 0.0 {
+            continue;
+        }
         let dire_x = dx / dist;
         let dire_y = dy / dist;
 
@@ -50,6 +53,9 @@ pub fn apply_attraction_force2_3d(layout: &mut Layout) {
         let dz = unsafe { *n1_pos.get_unchecked(2) - *n2_pos.get_unchecked(2) };
 
         let dist = (dx * dx + dy * dy + dz * dz).sqrt();
+        if dist == 0.0 {
+            continue;
+        }
         let dire_x = dx / dist;
         let dire_y = dy / dist;
         let dire_z = dz / dist;
@@ -254,7 +260,6 @@ pub fn apply_attraction_forceatlas2_dh_log(layout: &mut Layout) {
 }
 
 pub fn apply_attraction_forceatlas2_po(layout: &mut Layout) {
-    let node_size = &layout.settings.prevent_overlapping.as_ref().unwrap().0;
     for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
         let mut d = 0.0;
         let n1_pos = layout.points.get(*n1);
@@ -266,7 +271,7 @@ pub fn apply_attraction_forceatlas2_po(layout: &mut Layout) {
         }
         d = d.sqrt();
 
-        let dprime = d.clone() - node_size.clone();
+        let dprime = d.clone() - layout.overlap_distance(*n1, *n2);
         if dprime <= 0.0 {
             continue;
         }
@@ -288,7 +293,6 @@ pub fn apply_attraction_forceatlas2_po(layout: &mut Layout) {
 }
 
 pub fn apply_attraction_forceatlas2_dh_po(layout: &mut Layout) {
-    let node_size = &layout.settings.prevent_overlapping.as_ref().unwrap().0;
     for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
         let mut d = 0.0;
         let n1_pos = layout.points.get(*n1);
@@ -300,7 +304,7 @@ pub fn apply_attraction_forceatlas2_dh_po(layout: &mut Layout) {
         }
         d = d.sqrt();
 
-        let dprime = d.clone() - node_size.clone();
+        let dprime = d.clone() - layout.overlap_distance(*n1, *n2);
         if dprime < 0.0 {
             dbg!(dprime);
             continue;
@@ -324,7 +328,6 @@ pub fn apply_attraction_forceatlas2_dh_po(layout: &mut Layout) {
 }
 
 pub fn apply_attraction_forceatlas2_log_po(layout: &mut Layout) {
-    let node_size = &layout.settings.prevent_overlapping.as_ref().unwrap().0;
     for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
         let mut d = 0.0;
         let n1_pos = layout.points.get(*n1);
@@ -336,7 +339,7 @@ pub fn apply_attraction_forceatlas2_log_po(layout: &mut Layout) {
         }
         d = d.sqrt();
 
-        let dprime = d - node_size.clone();
+        let dprime = d - layout.overlap_distance(*n1, *n2);
         if dprime < 0.0 {
             continue;
         }
@@ -358,7 +361,6 @@ pub fn apply_attraction_forceatlas2_log_po(layout: &mut Layout) {
 }
 
 pub fn apply_attraction_forceatlas2_dh_log_po(layout: &mut Layout) {
-    let node_size = &layout.settings.prevent_overlapping.as_ref().unwrap().0;
     for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
         let mut d = 0.0;
         let n1_pos = layout.points.get(*n1);
@@ -370,7 +372,7 @@ pub fn apply_attraction_forceatlas2_dh_log_po(layout: &mut Layout) {
         }
         d = d.sqrt();
 
-        let dprime = d - node_size.clone();
+        let dprime = d - layout.overlap_distance(*n1, *n2);
         if dprime < 0.0 {
             continue;
         }
@@ -391,3 +393,40 @@ pub fn apply_attraction_forceatlas2_dh_log_po(layout: &mut Layout) {
         }
     }
 }
+
+/// Potential energy of the attraction, whose gradient is the attraction force
+///
+/// Overlap prevention is not accounted for, nor are the masses dividing the Force2 springs.
+pub(crate) fn attraction_potential(layout: &Layout) -> f32 {
+    let settings = &layout.settings;
+    let mut energy = 0.0;
+    for (edge, (n1, n2)) in layout.edges.iter().enumerate() {
+        let d = layout
+            .points
+            .get(*n1)
+            .iter()
+            .zip(layout.points.get(*n2))
+            .map(|(p1, p2)| (p2 - p1).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        energy += match settings.name {
+            LayoutType::Fruchterman => d * d * (d / 3.0 + settings.kr / 2.0) / settings.ka,
+            LayoutType::Force2 => {
+                settings.edge_strength * (d - settings.link_distance).powi(2) / 2.0
+            }
+            LayoutType::ForceAtlas2 => {
+                let mut weight =
+                    layout.weights.as_ref().map_or(1.0, |weights| weights[edge]) * settings.ka;
+                if settings.dissuade_hubs {
+                    weight /= layout.masses[*n1];
+                }
+                if settings.lin_log {
+                    weight * ((1.0 + d) * d.ln_1p() - d)
+                } else {
+                    weight * d * d / 2.0
+                }
+            }
+        };
+    }
+    energy
+}
@@ -1,4 +1,7 @@
-use crate::{layout::Layout, util::*};
+use crate::{
+    layout::{Layout, LayoutType},
+    util::*,
+};
 
 use itertools::izip;
 
@@ -60,3 +63,27 @@ pub fn apply_gravity_forceatlas2_sg(layout: &mut Layout) {
         }
     }
 }
+
+/// Potential energy of the gravity, the masses dividing the Force2 pull not accounted for
+pub(crate) fn gravity_potential(layout: &Layout) -> f32 {
+    let settings = &layout.settings;
+    let mut energy = 0.0;
+    for (n, mass) in layout.masses.iter().enumerate() {
+        let r2: f32 = layout
+            .points
+            .get(n)
+            .iter()
+            .zip(settings.center.iter())
+            .map(|(pos, center)| (pos - center).powi(2))
+            .sum();
+        energy += match settings.name {
+            LayoutType::Fruchterman => 0.01 * settings.ka * settings.kg * r2 / 2.0,
+            LayoutType::Force2 => settings.kg * r2 / 2.0,
+            LayoutType::ForceAtlas2 if settings.strong_gravity => {
+                (mass + 1.0) * settings.kg * r2 / 2.0
+            }
+            LayoutType::ForceAtlas2 => (mass + 1.0) * settings.kg * r2.sqrt(),
+        };
+    }
+    energy
+}
@@ -2,7 +2,7 @@ pub mod attraction;
 pub mod gravity;
 pub mod repulsion;
 
-use crate::layout::{Layout, LayoutType, Settings};
+use crate::layout::{Layout, LayoutType, NeighborSearch, Settings};
 
 #[doc(hidden)]
 pub trait Attraction {
@@ -89,34 +89,70 @@ impl Gravity for Layout {
 
 impl Repulsion for Layout {
     fn choose_repulsion(settings: &Settings) -> fn(&mut Layout) {
-        match settings.name {
-            LayoutType::Fruchterman => match settings.dimensions {
-                3 => {
-                    return repulsion::apply_repulsion_fruchterman_3d_parallel;
+        let parallel = settings.chunk_size.is_some();
+        let grid = matches!(settings.neighbor_search, NeighborSearch::Grid)
+            && settings.max_distance.is_finite()
+            && settings.max_distance > 0.0;
+        if grid && settings.prevent_overlapping.is_none() {
+            return match (&settings.name, settings.dimensions) {
+                (LayoutType::Fruchterman, 3) => repulsion::apply_repulsion_fruchterman_3d_grid,
+                (LayoutType::Fruchterman, _) => repulsion::apply_repulsion_fruchterman_2d_grid,
+                (LayoutType::Force2, 3) => repulsion::apply_repulsion_force2_3d_grid,
+                (LayoutType::Force2, _) => repulsion::apply_repulsion_force2_2d_grid,
+                (LayoutType::ForceAtlas2, 3) => repulsion::apply_repulsion_forceatlas2_3d_grid,
+                (LayoutType::ForceAtlas2, _) => repulsion::apply_repulsion_forceatlas2_2d_grid,
+            };
+        }
+        if settings.simd && settings.prevent_overlapping.is_none() {
+            return match (&settings.name, settings.dimensions) {
+                (LayoutType::Fruchterman, 3) => repulsion::apply_repulsion_fruchterman_3d_simd,
+                (LayoutType::Fruchterman, _) => repulsion::apply_repulsion_fruchterman_2d_simd,
+                (LayoutType::Force2, 3) => repulsion::apply_repulsion_force2_3d_simd,
+                (LayoutType::Force2, _) => repulsion::apply_repulsion_force2_2d_simd,
+                (LayoutType::ForceAtlas2, 3) => repulsion::apply_repulsion_forceatlas2_3d_simd,
+                (LayoutType::ForceAtlas2, _) => repulsion::apply_repulsion_forceatlas2_2d_simd,
+            };
+        }
+        if parallel && settings.deterministic && settings.prevent_overlapping.is_none() {
+            return match (&settings.name, settings.dimensions) {
+                (LayoutType::Fruchterman, 3) => {
+                    repulsion::apply_repulsion_fruchterman_3d_deterministic
                 }
-                _ => {
-                    return repulsion::apply_repulsion_fruchterman_2d_parallel;
+                (LayoutType::Fruchterman, _) => {
+                    repulsion::apply_repulsion_fruchterman_2d_deterministic
                 }
-            },
-            LayoutType::Force2 => match settings.dimensions {
-                3 => {
-                    return repulsion::apply_repulsion_force2_3d_parallel;
+                (LayoutType::Force2, 3) => repulsion::apply_repulsion_force2_3d_deterministic,
+                (LayoutType::Force2, _) => repulsion::apply_repulsion_force2_2d_deterministic,
+                (LayoutType::ForceAtlas2, 3) => {
+                    repulsion::apply_repulsion_forceatlas2_3d_deterministic
                 }
-                _ => {
-                    return repulsion::apply_repulsion_force2_2d_parallel;
+                (LayoutType::ForceAtlas2, _) => {
+                    repulsion::apply_repulsion_forceatlas2_2d_deterministic
                 }
+            };
+        }
+        match settings.name {
+            LayoutType::Fruchterman => match (settings.dimensions, parallel) {
+                (3, true) => repulsion::apply_repulsion_fruchterman_3d_parallel,
+                (3, false) => repulsion::apply_repulsion_fruchterman_3d,
+                (_, true) => repulsion::apply_repulsion_fruchterman_2d_parallel,
+                (_, false) => repulsion::apply_repulsion_fruchterman_2d,
+            },
+            LayoutType::Force2 => match (settings.dimensions, parallel) {
+                (3, true) => repulsion::apply_repulsion_force2_3d_parallel,
+                (3, false) => repulsion::apply_repulsion_force2_3d,
+                (_, true) => repulsion::apply_repulsion_force2_2d_parallel,
+                (_, false) => repulsion::apply_repulsion_force2_2d,
             },
             LayoutType::ForceAtlas2 => {
                 if settings.prevent_overlapping.is_some() {
                     repulsion::apply_repulsion_forceatlas2_po
                 } else {
-                    match settings.dimensions {
-                        3 => {
-                            return repulsion::apply_repulsion_forceatlas2_3d_parallel;
-                        }
-                        _ => {
-                            return repulsion::apply_repulsion_forceatlas2_2d_parallel;
-                        }
+                    match (settings.dimensions, parallel) {
+                        (3, true) => repulsion::apply_repulsion_forceatlas2_3d_parallel,
+                        (3, false) => repulsion::apply_repulsion_forceatlas2_3d,
+                        (_, true) => repulsion::apply_repulsion_forceatlas2_2d_parallel,
+                        (_, false) => repulsion::apply_repulsion_forceatlas2_2d,
                     }
                 }
             }
@@ -1,74 +1,143 @@
-use crate::{layout::*, util::*};
+use crate::{
+    grid::SpatialHash,
+    layout::*,
+    simd::{Lanes, Native},
+    util::*,
+};
 
 use itertools::izip;
 use rayon::prelude::*;
+use std::ops::Range;
 
 pub fn apply_repulsion_forceatlas2_2d_parallel(layout: &mut Layout) {
     let kr = layout.settings.kr;
     let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
-    for chunk_iter in layout.iter_par_nodes(layout.settings.chunk_size.unwrap()) {
-        chunk_iter.for_each(|n1_iter| {
-            for n1 in n1_iter {
-                let n1_mass = *n1.mass + 1.0;
-                for n2 in n1.n2_iter {
-                    let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
-                    let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
-
-                    let d2 = dx * dx + dy * dy;
-                    if d2 < max_distance2 {
-                        let f = n1_mass * (*n2.mass + 1.0) / d2 * kr;
-
-                        let vx = f * dx;
-                        let vy = f * dy;
-
-                        unsafe { *n1.speed.get_unchecked_mut(0) -= vx };
-                        unsafe { *n1.speed.get_unchecked_mut(1) -= vy };
-                        unsafe { *n2.speed.get_unchecked_mut(0) += vx };
-                        unsafe { *n2.speed.get_unchecked_mut(1) += vy };
-                    }
-                }
-            }
-        });
-    }
-}
-
-pub fn apply_repulsion_forceatlas2_3d_parallel(layout: &mut Layout) {
-    let kr = layout.settings.kr;
-    for chunk_iter in layout.iter_par_nodes(layout.settings.chunk_size.unwrap()) {
-        chunk_iter.for_each(|n1_iter| {
-            for n1 in n1_iter {
-                let n1_mass = *n1.mass + 1.0;
-                for n2 in n1.n2_iter {
-                    let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
-                    let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
-                    let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
-
-                    let d2 = dx * dx + dy * dy + dz * dz;
-                    if d2 == 0.0 {
-                        continue;
-                    }
+    let chunk_size = layout.settings.chunk_size.unwrap();
+    layout.par_for_each_block(chunk_size, |mut n1_iter| {
+        while let Some(n1) = n1_iter.next() {
+            let n1_mass = *n1.mass + 1.0;
+            for n2 in n1.n2_iter {
+                let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+                let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
 
+                let d2 = dx * dx + dy * dy;
+                if d2 == 0.0 {
+                    continue;
+                }
+                if d2 < max_distance2 {
                     let f = n1_mass * (*n2.mass + 1.0) / d2 * kr;
 
                     let vx = f * dx;
                     let vy = f * dy;
-                    let vz = f * dz;
 
                     unsafe { *n1.speed.get_unchecked_mut(0) -= vx };
                     unsafe { *n1.speed.get_unchecked_mut(1) -= vy };
-                    unsafe { *n1.speed.get_unchecked_mut(2) -= vz };
                     unsafe { *n2.speed.get_unchecked_mut(0) += vx };
                     unsafe { *n2.speed.get_unchecked_mut(1) += vy };
-                    unsafe { *n2.speed.get_unchecked_mut(2) += vz };
                 }
             }
-        });
+        }
+    });
+}
+
+pub fn apply_repulsion_forceatlas2_2d(layout: &mut Layout) {
+    let kr = layout.settings.kr;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let mut n1_iter = layout.iter_nodes();
+    while let Some(n1) = n1_iter.next() {
+        let n1_mass = *n1.mass + 1.0;
+        for n2 in n1.n2_iter {
+            let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+            let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+
+            let d2 = dx * dx + dy * dy;
+            if d2 == 0.0 {
+                continue;
+            }
+            if d2 < max_distance2 {
+                let f = n1_mass * (*n2.mass + 1.0) / d2 * kr;
+
+                let vx = f * dx;
+                let vy = f * dy;
+
+                unsafe { *n1.speed.get_unchecked_mut(0) -= vx };
+                unsafe { *n1.speed.get_unchecked_mut(1) -= vy };
+                unsafe { *n2.speed.get_unchecked_mut(0) += vx };
+                unsafe { *n2.speed.get_unchecked_mut(1) += vy };
+            }
+        }
+    }
+}
+
+pub fn apply_repulsion_forceatlas2_3d_parallel(layout: &mut Layout) {
+    let kr = layout.settings.kr;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let chunk_size = layout.settings.chunk_size.unwrap();
+    layout.par_for_each_block(chunk_size, |mut n1_iter| {
+        while let Some(n1) = n1_iter.next() {
+            let n1_mass = *n1.mass + 1.0;
+            for n2 in n1.n2_iter {
+                let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+                let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+                let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
+
+                let d2 = dx * dx + dy * dy + dz * dz;
+                if d2 == 0.0 || d2 >= max_distance2 {
+                    continue;
+                }
+
+                let f = n1_mass * (*n2.mass + 1.0) / d2 * kr;
+
+                let vx = f * dx;
+                let vy = f * dy;
+                let vz = f * dz;
+
+                unsafe { *n1.speed.get_unchecked_mut(0) -= vx };
+                unsafe { *n1.speed.get_unchecked_mut(1) -= vy };
+                unsafe { *n1.speed.get_unchecked_mut(2) -= vz };
+                unsafe { *n2.speed.get_unchecked_mut(0) += vx };
+                unsafe { *n2.speed.get_unchecked_mut(1) += vy };
+                unsafe { *n2.speed.get_unchecked_mut(2) += vz };
+            }
+        }
+    });
+}
+
+pub fn apply_repulsion_forceatlas2_3d(layout: &mut Layout) {
+    let kr = layout.settings.kr;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let mut n1_iter = layout.iter_nodes();
+    while let Some(n1) = n1_iter.next() {
+        let n1_mass = *n1.mass + 1.0;
+        for n2 in n1.n2_iter {
+            let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+            let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+            let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
+
+            let d2 = dx * dx + dy * dy + dz * dz;
+            if d2 == 0.0 || d2 >= max_distance2 {
+                continue;
+            }
+
+            let f = n1_mass * (*n2.mass + 1.0) / d2 * kr;
+
+            let vx = f * dx;
+            let vy = f * dy;
+            let vz = f * dz;
+
+            unsafe { *n1.speed.get_unchecked_mut(0) -= vx };
+            unsafe { *n1.speed.get_unchecked_mut(1) -= vy };
+            unsafe { *n1.speed.get_unchecked_mut(2) -= vz };
+            unsafe { *n2.speed.get_unchecked_mut(0) += vx };
+            unsafe { *n2.speed.get_unchecked_mut(1) += vy };
+            unsafe { *n2.speed.get_unchecked_mut(2) += vz };
+        }
     }
 }
 
 pub fn apply_repulsion_forceatlas2_po(layout: &mut Layout) {
-    let mut di = valloc(layout.settings.dimensions);
-    let (node_size, krprime) = unsafe {
+    let mut di = vec![0.0; layout.settings.dimensions];
+    let (_, krprime) = unsafe {
         layout
             .settings
             .prevent_overlapping
@@ -79,7 +148,7 @@ pub fn apply_repulsion_forceatlas2_po(layout: &mut Layout) {
         let mut n2_iter = layout.points.iter();
         let n1_mass = n1_mass.clone() + 1.0;
         n2_iter.offset = (n1 + 1) * layout.settings.dimensions;
-        for (n2, n2_pos) in (0..n1).zip(&mut n2_iter) {
+        for (n2, n2_pos) in (n1 + 1..).zip(&mut n2_iter) {
             di.clone_from_slice(n2_pos);
 
             let d2 = di
@@ -95,7 +164,7 @@ pub fn apply_repulsion_forceatlas2_po(layout: &mut Layout) {
             }
 
             let d = d2.clone().sqrt();
-            let dprime = d.clone() - node_size.clone();
+            let dprime = d.clone() - layout.overlap_distance(n1, n2);
 
             let f = n1_mass.clone() * (unsafe { layout.masses.get_unchecked(n2) }.clone() + 1.0)
                 / d2
@@ -123,29 +192,65 @@ pub fn apply_repulsion_force2_2d_parallel(layout: &mut Layout) {
     let node_strength = layout.settings.node_strength;
     let weight = node_strength * factor / coulomb_dis_scale / coulomb_dis_scale;
     let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
-    for chunk_iter in layout.iter_par_nodes(layout.settings.chunk_size.unwrap()) {
-        chunk_iter.for_each(|n1_iter| {
-            for n1 in n1_iter {
-                let n1_mass = *n1.mass;
-                for n2 in n1.n2_iter {
-                    let n2_mass = *n2.mass;
-                    let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
-                    let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
-
-                    let d2 = dx * dx + dy * dy;
-
-                    if d2 < max_distance2 {
-                        let d3 = d2.sqrt() * d2;
-                        let param = weight / d3;
-
-                        unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param / n1_mass };
-                        unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param / n1_mass };
-                        unsafe { *n2.speed.get_unchecked_mut(0) += dx * param / n2_mass };
-                        unsafe { *n2.speed.get_unchecked_mut(1) += dy * param / n2_mass };
-                    }
+    let chunk_size = layout.settings.chunk_size.unwrap();
+    layout.par_for_each_block(chunk_size, |mut n1_iter| {
+        while let Some(n1) = n1_iter.next() {
+            let n1_mass = *n1.mass;
+            for n2 in n1.n2_iter {
+                let n2_mass = *n2.mass;
+                let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+                let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+
+                let d2 = dx * dx + dy * dy;
+
+                if d2 == 0.0 {
+                    continue;
                 }
+
+                if d2 < max_distance2 {
+                    let d3 = d2.sqrt() * d2;
+                    let param = weight / d3;
+
+                    unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param / n1_mass };
+                    unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param / n1_mass };
+                    unsafe { *n2.speed.get_unchecked_mut(0) += dx * param / n2_mass };
+                    unsafe { *n2.speed.get_unchecked_mut(1) += dy * param / n2_mass };
+                }
+            }
+        }
+    });
+}
+
+pub fn apply_repulsion_force2_2d(layout: &mut Layout) {
+    let factor = layout.settings.factor;
+    let coulomb_dis_scale = layout.settings.coulomb_dis_scale;
+    let node_strength = layout.settings.node_strength;
+    let weight = node_strength * factor / coulomb_dis_scale / coulomb_dis_scale;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let mut n1_iter = layout.iter_nodes();
+    while let Some(n1) = n1_iter.next() {
+        let n1_mass = *n1.mass;
+        for n2 in n1.n2_iter {
+            let n2_mass = *n2.mass;
+            let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+            let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+
+            let d2 = dx * dx + dy * dy;
+
+            if d2 == 0.0 {
+                continue;
+            }
+
+            if d2 < max_distance2 {
+                let d3 = d2.sqrt() * d2;
+                let param = weight / d3;
+
+                unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param / n1_mass };
+                unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param / n1_mass };
+                unsafe { *n2.speed.get_unchecked_mut(0) += dx * param / n2_mass };
+                unsafe { *n2.speed.get_unchecked_mut(1) += dy * param / n2_mass };
             }
-        });
+        }
     }
 }
 
@@ -155,32 +260,71 @@ pub fn apply_repulsion_force2_3d_parallel(layout: &mut Layout) {
     let node_strength = layout.settings.node_strength;
     let weight = node_strength * factor / coulomb_dis_scale / coulomb_dis_scale;
     let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
-    for chunk_iter in layout.iter_par_nodes(layout.settings.chunk_size.unwrap()) {
-        chunk_iter.for_each(|n1_iter| {
-            for n1 in n1_iter {
-                let n1_mass = *n1.mass;
-                for n2 in n1.n2_iter {
-                    let n2_mass = *n2.mass;
-                    let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
-                    let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
-                    let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
-
-                    let d2 = dx * dx + dy * dy + dz * dz;
-
-                    if d2 < max_distance2 {
-                        let d3 = d2.sqrt() * d2;
-                        let param = weight / d3;
-
-                        unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param / n1_mass };
-                        unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param / n1_mass };
-                        unsafe { *n1.speed.get_unchecked_mut(2) -= dz * param / n1_mass };
-                        unsafe { *n2.speed.get_unchecked_mut(0) += dx * param / n2_mass };
-                        unsafe { *n2.speed.get_unchecked_mut(1) += dy * param / n2_mass };
-                        unsafe { *n2.speed.get_unchecked_mut(2) += dz * param / n2_mass };
-                    }
+    let chunk_size = layout.settings.chunk_size.unwrap();
+    layout.par_for_each_block(chunk_size, |mut n1_iter| {
+        while let Some(n1) = n1_iter.next() {
+            let n1_mass = *n1.mass;
+            for n2 in n1.n2_iter {
+                let n2_mass = *n2.mass;
+                let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+                let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+                let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
+
+                let d2 = dx * dx + dy * dy + dz * dz;
+
+                if d2 == 0.0 {
+                    continue;
                 }
+
+                if d2 < max_distance2 {
+                    let d3 = d2.sqrt() * d2;
+                    let param = weight / d3;
+
+                    unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param / n1_mass };
+                    unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param / n1_mass };
+                    unsafe { *n1.speed.get_unchecked_mut(2) -= dz * param / n1_mass };
+                    unsafe { *n2.speed.get_unchecked_mut(0) += dx * param / n2_mass };
+                    unsafe { *n2.speed.get_unchecked_mut(1) += dy * param / n2_mass };
+                    unsafe { *n2.speed.get_unchecked_mut(2) += dz * param / n2_mass };
+                }
+            }
+        }
+    });
+}
+
+pub fn apply_repulsion_force2_3d(layout: &mut Layout) {
+    let factor = layout.settings.factor;
+    let coulomb_dis_scale = layout.settings.coulomb_dis_scale;
+    let node_strength = layout.settings.node_strength;
+    let weight = node_strength * factor / coulomb_dis_scale / coulomb_dis_scale;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let mut n1_iter = layout.iter_nodes();
+    while let Some(n1) = n1_iter.next() {
+        let n1_mass = *n1.mass;
+        for n2 in n1.n2_iter {
+            let n2_mass = *n2.mass;
+            let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+            let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+            let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
+
+            let d2 = dx * dx + dy * dy + dz * dz;
+
+            if d2 == 0.0 {
+                continue;
+            }
+
+            if d2 < max_distance2 {
+                let d3 = d2.sqrt() * d2;
+                let param = weight / d3;
+
+                unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param / n1_mass };
+                unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param / n1_mass };
+                unsafe { *n1.speed.get_unchecked_mut(2) -= dz * param / n1_mass };
+                unsafe { *n2.speed.get_unchecked_mut(0) += dx * param / n2_mass };
+                unsafe { *n2.speed.get_unchecked_mut(1) += dy * param / n2_mass };
+                unsafe { *n2.speed.get_unchecked_mut(2) += dz * param / n2_mass };
             }
-        });
+        }
     }
 }
 
@@ -188,26 +332,49 @@ pub fn apply_repulsion_fruchterman_2d_parallel(layout: &mut Layout) {
     let k = layout.settings.ka;
     let k2 = k * k;
     let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
-    for chunk_iter in layout.iter_par_nodes(layout.settings.chunk_size.unwrap()) {
-        chunk_iter.for_each(|n1_iter| {
-            for n1 in n1_iter {
-                for n2 in n1.n2_iter {
-                    let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
-                    let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+    let chunk_size = layout.settings.chunk_size.unwrap();
+    layout.par_for_each_block(chunk_size, |mut n1_iter| {
+        while let Some(n1) = n1_iter.next() {
+            for n2 in n1.n2_iter {
+                let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+                let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
 
-                    let d2 = dx * dx + dy * dy + 0.01;
+                let d2 = dx * dx + dy * dy + 0.01;
 
-                    if d2 < max_distance2 {
-                        let param = k2 / d2;
+                if d2 < max_distance2 {
+                    let param = k2 / d2;
 
-                        unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param };
-                        unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param };
-                        unsafe { *n2.speed.get_unchecked_mut(0) += dx * param };
-                        unsafe { *n2.speed.get_unchecked_mut(1) += dy * param };
-                    }
+                    unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param };
+                    unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param };
+                    unsafe { *n2.speed.get_unchecked_mut(0) += dx * param };
+                    unsafe { *n2.speed.get_unchecked_mut(1) += dy * param };
                 }
             }
-        });
+        }
+    });
+}
+
+pub fn apply_repulsion_fruchterman_2d(layout: &mut Layout) {
+    let k = layout.settings.ka;
+    let k2 = k * k;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let mut n1_iter = layout.iter_nodes();
+    while let Some(n1) = n1_iter.next() {
+        for n2 in n1.n2_iter {
+            let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+            let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+
+            let d2 = dx * dx + dy * dy + 0.01;
+
+            if d2 < max_distance2 {
+                let param = k2 / d2;
+
+                unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param };
+                unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param };
+                unsafe { *n2.speed.get_unchecked_mut(0) += dx * param };
+                unsafe { *n2.speed.get_unchecked_mut(1) += dy * param };
+            }
+        }
     }
 }
 
@@ -215,28 +382,723 @@ pub fn apply_repulsion_fruchterman_3d_parallel(layout: &mut Layout) {
     let k = layout.settings.ka;
     let k2 = k * k;
     let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
-    for chunk_iter in layout.iter_par_nodes(layout.settings.chunk_size.unwrap()) {
-        chunk_iter.for_each(|n1_iter| {
-            for n1 in n1_iter {
-                for n2 in n1.n2_iter {
-                    let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
-                    let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
-                    let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
-
-                    let d2 = dx * dx + dy * dy + dz * dz + 0.01;
-
-                    if d2 < max_distance2 {
-                        let param = k2 / d2;
-
-                        unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param };
-                        unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param };
-                        unsafe { *n1.speed.get_unchecked_mut(2) -= dz * param };
-                        unsafe { *n2.speed.get_unchecked_mut(0) += dx * param };
-                        unsafe { *n2.speed.get_unchecked_mut(1) += dy * param };
-                        unsafe { *n2.speed.get_unchecked_mut(2) += dz * param };
-                    }
+    let chunk_size = layout.settings.chunk_size.unwrap();
+    layout.par_for_each_block(chunk_size, |mut n1_iter| {
+        while let Some(n1) = n1_iter.next() {
+            for n2 in n1.n2_iter {
+                let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+                let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+                let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
+
+                let d2 = dx * dx + dy * dy + dz * dz + 0.01;
+
+                if d2 < max_distance2 {
+                    let param = k2 / d2;
+
+                    unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param };
+                    unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param };
+                    unsafe { *n1.speed.get_unchecked_mut(2) -= dz * param };
+                    unsafe { *n2.speed.get_unchecked_mut(0) += dx * param };
+                    unsafe { *n2.speed.get_unchecked_mut(1) += dy * param };
+                    unsafe { *n2.speed.get_unchecked_mut(2) += dz * param };
                 }
             }
-        });
+        }
+    });
+}
+
+pub fn apply_repulsion_fruchterman_3d(layout: &mut Layout) {
+    let k = layout.settings.ka;
+    let k2 = k * k;
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let mut n1_iter = layout.iter_nodes();
+    while let Some(n1) = n1_iter.next() {
+        for n2 in n1.n2_iter {
+            let dx = unsafe { *n2.pos.get_unchecked(0) - *n1.pos.get_unchecked(0) };
+            let dy = unsafe { *n2.pos.get_unchecked(1) - *n1.pos.get_unchecked(1) };
+            let dz = unsafe { *n2.pos.get_unchecked(2) - *n1.pos.get_unchecked(2) };
+
+            let d2 = dx * dx + dy * dy + dz * dz + 0.01;
+
+            if d2 < max_distance2 {
+                let param = k2 / d2;
+
+                unsafe { *n1.speed.get_unchecked_mut(0) -= dx * param };
+                unsafe { *n1.speed.get_unchecked_mut(1) -= dy * param };
+                unsafe { *n1.speed.get_unchecked_mut(2) -= dz * param };
+                unsafe { *n2.speed.get_unchecked_mut(0) += dx * param };
+                unsafe { *n2.speed.get_unchecked_mut(1) += dy * param };
+                unsafe { *n2.speed.get_unchecked_mut(2) += dz * param };
+            }
+        }
+    }
+}
+
+/// Pairwise repulsion law evaluated by the SIMD kernels
+///
+/// The displacement of `n1` is `-(n2 - n1) * coefficient * scale(n1_mass)`.
+trait PairLaw: Sync {
+    /// Added to the squared distance before evaluating the law
+    const SOFTENING: f32 = 0.0;
+
+    fn coefficient<V: Lanes>(&self, d2: V, n1_mass: V, n2_mass: V) -> V;
+
+    /// Potential energy of a pair, whose derivative is the magnitude of the force
+    fn potential(&self, d2: f32, n1_mass: f32, n2_mass: f32) -> f32;
+
+    fn scale(&self, _n1_mass: f32) -> f32 {
+        1.0
+    }
+}
+
+struct ForceAtlas2Law {
+    kr: f32,
+}
+
+impl ForceAtlas2Law {
+    fn new(settings: &Settings) -> Self {
+        Self { kr: settings.kr }
+    }
+}
+
+impl PairLaw for ForceAtlas2Law {
+    #[inline(always)]
+    fn coefficient<V: Lanes>(&self, d2: V, n1_mass: V, n2_mass: V) -> V {
+        let one = V::splat(1.0);
+        (n1_mass + one) * (n2_mass + one) / d2 * V::splat(self.kr)
+    }
+
+    fn potential(&self, d2: f32, n1_mass: f32, n2_mass: f32) -> f32 {
+        -self.kr * (n1_mass + 1.0) * (n2_mass + 1.0) * d2.ln() / 2.0
+    }
+}
+
+struct Force2Law {
+    weight: f32,
+}
+
+impl Force2Law {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            weight: settings.node_strength * settings.factor
+                / settings.coulomb_dis_scale
+                / settings.coulomb_dis_scale,
+        }
+    }
+}
+
+impl PairLaw for Force2Law {
+    #[inline(always)]
+    fn coefficient<V: Lanes>(&self, d2: V, _n1_mass: V, _n2_mass: V) -> V {
+        V::splat(self.weight) / (d2.sqrt() * d2)
+    }
+
+    fn potential(&self, d2: f32, _n1_mass: f32, _n2_mass: f32) -> f32 {
+        self.weight / d2.sqrt()
+    }
+
+    fn scale(&self, n1_mass: f32) -> f32 {
+        1.0 / n1_mass
+    }
+}
+
+struct FruchtermanLaw {
+    k2: f32,
+}
+
+impl FruchtermanLaw {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            k2: settings.ka * settings.ka,
+        }
+    }
+}
+
+impl PairLaw for FruchtermanLaw {
+    const SOFTENING: f32 = 0.01;
+
+    #[inline(always)]
+    fn coefficient<V: Lanes>(&self, d2: V, _n1_mass: V, _n2_mass: V) -> V {
+        V::splat(self.k2) / d2
+    }
+
+    fn potential(&self, d2: f32, _n1_mass: f32, _n2_mass: f32) -> f32 {
+        -self.k2 * d2.ln() / 2.0
+    }
+}
+
+fn pair_potential<L: PairLaw>(layout: &Layout, law: L) -> f32 {
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    let masses = &layout.masses;
+    let mut energy = 0.0;
+    for n1 in 0..masses.len() {
+        let n1_pos = layout.points.get(n1);
+        for n2 in n1 + 1..masses.len() {
+            let d2: f32 = n1_pos
+                .iter()
+                .zip(layout.points.get(n2))
+                .map(|(p1, p2)| (p2 - p1).powi(2))
+                .sum();
+            if d2 == 0.0 || d2 >= max_distance2 {
+                continue;
+            }
+            energy += law.potential(d2 + L::SOFTENING, masses[n1], masses[n2]);
+        }
+    }
+    energy
+}
+
+/// Potential energy of the repulsion, over the pairs the kernels consider
+///
+/// Overlap prevention is not accounted for.
+pub(crate) fn repulsion_potential(layout: &Layout) -> f32 {
+    let settings = &layout.settings;
+    match settings.name {
+        LayoutType::ForceAtlas2 => pair_potential(layout, ForceAtlas2Law::new(settings)),
+        LayoutType::Force2 => pair_potential(layout, Force2Law::new(settings)),
+        LayoutType::Fruchterman => pair_potential(layout, FruchtermanLaw::new(settings)),
+    }
+}
+
+/// Sums the law over `n2` in `from..to`, `V::LANES` nodes at a time.
+///
+/// Returns the accumulated `(n2 - n1) * coefficient` and the first `n2` left unprocessed,
+/// to be finished with a narrower `V`. Pairs at distance `0` or beyond `max_distance2` are skipped.
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn repulsion_row<V: Lanes, L: PairLaw, const D: usize>(
+    soa: &PointListSoa,
+    masses: &[f32],
+    law: &L,
+    n1: usize,
+    from: usize,
+    to: usize,
+    max_distance2: f32,
+) -> ([f32; D], usize) {
+    let zero = V::splat(0.0);
+    let hi = V::splat(max_distance2);
+    let n1_mass = V::splat(masses[n1]);
+    let mut n1_pos = [zero; D];
+    for (d, p) in n1_pos.iter_mut().enumerate() {
+        *p = V::splat(soa.axis(d)[n1]);
+    }
+
+    let mut acc = [zero; D];
+    let mut n2 = from;
+    while n2 + V::LANES <= to {
+        let mut di = [zero; D];
+        let mut d2 = V::splat(L::SOFTENING);
+        for d in 0..D {
+            di[d] = unsafe { V::load(soa.axis(d), n2) } - n1_pos[d];
+            d2 = d2 + di[d] * di[d];
+        }
+        let f = law
+            .coefficient(d2, n1_mass, unsafe { V::load(masses, n2) })
+            .keep_between(d2, zero, hi);
+        for d in 0..D {
+            acc[d] = acc[d] + f * di[d];
+        }
+        n2 += V::LANES;
+    }
+
+    let mut ret = [0.0; D];
+    for d in 0..D {
+        ret[d] = acc[d].sum();
+    }
+    (ret, n2)
+}
+
+/// All-pairs repulsion over a structure-of-arrays copy of the positions.
+///
+/// Every node accumulates the force from all the others, which computes each pair twice
+/// but keeps rows independent: no write conflicts, and runs are reproducible.
+fn apply_repulsion_simd<L: PairLaw, const D: usize>(
+    layout: &mut Layout,
+    law: L,
+    max_distance2: f32,
+) {
+    layout.soa.update(&layout.points);
+    let soa = &layout.soa;
+    let masses = &layout.masses;
+    let n = masses.len();
+    let row = |(n1, speed): (usize, &mut [f32])| {
+        let (acc, n2) = repulsion_row::<Native, L, D>(soa, masses, &law, n1, 0, n, max_distance2);
+        let (tail, _) = repulsion_row::<f32, L, D>(soa, masses, &law, n1, n2, n, max_distance2);
+        let scale = law.scale(masses[n1]);
+        for (d, speed) in speed.iter_mut().enumerate() {
+            *speed -= (acc[d] + tail[d]) * scale;
+        }
+    };
+    match layout.settings.chunk_size {
+        Some(chunk_size) => layout
+            .speeds
+            .points
+            .par_chunks_mut(D)
+            .enumerate()
+            .with_min_len(chunk_size)
+            .for_each(row),
+        None => layout.speeds.points.chunks_mut(D).enumerate().for_each(row),
+    }
+}
+
+pub fn apply_repulsion_forceatlas2_2d_simd(layout: &mut Layout) {
+    let law = ForceAtlas2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_simd::<_, 2>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_forceatlas2_3d_simd(layout: &mut Layout) {
+    let law = ForceAtlas2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_simd::<_, 3>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_force2_2d_simd(layout: &mut Layout) {
+    let law = Force2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_simd::<_, 2>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_force2_3d_simd(layout: &mut Layout) {
+    let law = Force2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_simd::<_, 3>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_fruchterman_2d_simd(layout: &mut Layout) {
+    let law = FruchtermanLaw::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_simd::<_, 2>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_fruchterman_3d_simd(layout: &mut Layout) {
+    let law = FruchtermanLaw::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_simd::<_, 3>(layout, law, max_distance2);
+}
+
+/// Repulsion restricted to pairs closer than `max_distance`, found through a spatial hash
+/// with cells of side `max_distance`.
+///
+/// Each node only visits the `3^D` cells around it, so the cost depends on the local density
+/// instead of the number of nodes. Like `apply_repulsion_simd`, rows are independent.
+fn apply_repulsion_grid<L: PairLaw, const D: usize>(layout: &mut Layout, law: L) {
+    let max_distance = layout.settings.max_distance;
+    let hash = SpatialHash::new(&layout.points, max_distance);
+    let points = &layout.points;
+    layout.soa.axes.resize_with(D, Vec::new);
+    for (d, axis) in layout.soa.axes.iter_mut().enumerate() {
+        axis.clear();
+        axis.extend(hash.order.iter().map(|n| points.get(*n)[d]));
+    }
+    let soa = &layout.soa;
+    let sorted_masses: Vec<f32> = hash.order.iter().map(|n| layout.masses[*n]).collect();
+    let masses = &layout.masses;
+    let simd = layout.settings.simd;
+    let max_distance2 = max_distance * max_distance;
+
+    let row = |ranges: &mut Vec<(usize, usize)>, (n1, speed): (usize, &mut [f32])| {
+        hash.neighbor_ranges(points.get(n1), ranges);
+        let sorted_n1 = hash.rank[n1];
+        let mut acc = [0.0; D];
+        for (from, to) in ranges.iter() {
+            let (part, from) = if simd {
+                repulsion_row::<Native, L, D>(
+                    soa,
+                    &sorted_masses,
+                    &law,
+                    sorted_n1,
+                    *from,
+                    *to,
+                    max_distance2,
+                )
+            } else {
+                ([0.0; D], *from)
+            };
+            let (tail, _) = repulsion_row::<f32, L, D>(
+                soa,
+                &sorted_masses,
+                &law,
+                sorted_n1,
+                from,
+                *to,
+                max_distance2,
+            );
+            for d in 0..D {
+                acc[d] += part[d] + tail[d];
+            }
+        }
+        let scale = law.scale(masses[n1]);
+        for (d, speed) in speed.iter_mut().enumerate() {
+            *speed -= acc[d] * scale;
+        }
+    };
+    match layout.settings.chunk_size {
+        Some(chunk_size) => layout
+            .speeds
+            .points
+            .par_chunks_mut(D)
+            .enumerate()
+            .with_min_len(chunk_size)
+            .for_each_init(|| Vec::with_capacity(27), row),
+        None => {
+            let mut ranges = Vec::with_capacity(27);
+            layout
+                .speeds
+                .points
+                .chunks_mut(D)
+                .enumerate()
+                .for_each(|item| row(&mut ranges, item))
+        }
+    }
+}
+
+pub fn apply_repulsion_forceatlas2_2d_grid(layout: &mut Layout) {
+    let law = ForceAtlas2Law::new(&layout.settings);
+    apply_repulsion_grid::<_, 2>(layout, law);
+}
+
+pub fn apply_repulsion_forceatlas2_3d_grid(layout: &mut Layout) {
+    let law = ForceAtlas2Law::new(&layout.settings);
+    apply_repulsion_grid::<_, 3>(layout, law);
+}
+
+pub fn apply_repulsion_force2_2d_grid(layout: &mut Layout) {
+    let law = Force2Law::new(&layout.settings);
+    apply_repulsion_grid::<_, 2>(layout, law);
+}
+
+pub fn apply_repulsion_force2_3d_grid(layout: &mut Layout) {
+    let law = Force2Law::new(&layout.settings);
+    apply_repulsion_grid::<_, 3>(layout, law);
+}
+
+pub fn apply_repulsion_fruchterman_2d_grid(layout: &mut Layout) {
+    let law = FruchtermanLaw::new(&layout.settings);
+    apply_repulsion_grid::<_, 2>(layout, law);
+}
+
+pub fn apply_repulsion_fruchterman_3d_grid(layout: &mut Layout) {
+    let law = FruchtermanLaw::new(&layout.settings);
+    apply_repulsion_grid::<_, 3>(layout, law);
+}
+
+/// Repulsion between the nodes of `block1` and `block2`, or within `block1` if they are equal,
+/// written to `partial`: the displacements of `block1`, then those of `block2` if distinct.
+#[allow(clippy::too_many_arguments)]
+fn repulsion_block<L: PairLaw, const D: usize>(
+    points: &PointList,
+    masses: &[f32],
+    law: &L,
+    max_distance2: f32,
+    block1: Range<usize>,
+    block2: Range<usize>,
+    partial: &mut [f32],
+) {
+    let same = block1 == block2;
+    let offset2 = if same {
+        block1.start
+    } else {
+        block2.start - block1.len()
+    };
+    for n1 in block1.clone() {
+        let n1_pos = points.get(n1);
+        let n1_mass = masses[n1];
+        let n1_scale = law.scale(n1_mass);
+        let from = if same { n1 + 1 } else { block2.start };
+        for n2 in from..block2.end {
+            let n2_pos = points.get(n2);
+            let mut di = [0.0; D];
+            let mut d2 = L::SOFTENING;
+            for d in 0..D {
+                di[d] = n2_pos[d] - n1_pos[d];
+                d2 += di[d] * di[d];
+            }
+            let f = law
+                .coefficient(d2, n1_mass, masses[n2])
+                .keep_between(d2, 0.0, max_distance2);
+            let n2_scale = law.scale(masses[n2]);
+            for d in 0..D {
+                partial[(n1 - block1.start) * D + d] -= f * di[d] * n1_scale;
+                partial[(n2 - offset2) * D + d] += f * di[d] * n2_scale;
+            }
+        }
+    }
+}
+
+/// Parallel repulsion whose result does not depend on thread count or scheduling.
+///
+/// Pairs are grouped in blocks of `chunk_size` by `chunk_size` nodes, computed in waves of
+/// the blocks `(c, c + offset)` for each `offset`. Blocks of a wave share nodes, so each one
+/// writes to its own buffer, and buffers are added to the speeds in a fixed order once the
+/// wave is done. Buffers are kept on the layout to reuse them across iterations.
+fn apply_repulsion_deterministic<L: PairLaw, const D: usize>(
+    layout: &mut Layout,
+    law: L,
+    max_distance2: f32,
+) {
+    let n = layout.masses.len();
+    let chunk_size = layout.settings.chunk_size.unwrap_or(n).max(1);
+    let chunks = n.div_ceil(chunk_size);
+    let block = |c: usize| c * chunk_size..((c + 1) * chunk_size).min(n);
+    let mut partials = std::mem::take(&mut layout.partials);
+    partials.resize_with(chunks, Vec::new);
+    let (points, masses) = (&layout.points, &layout.masses);
+    for offset in 0..chunks {
+        partials[..chunks - offset]
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(c, partial)| {
+                let (block1, block2) = (block(c), block(c + offset));
+                let len = if offset == 0 {
+                    block1.len()
+                } else {
+                    block1.len() + block2.len()
+                };
+                partial.clear();
+                partial.resize(len * D, 0.0);
+                repulsion_block::<L, D>(
+                    points,
+                    masses,
+                    &law,
+                    max_distance2,
+                    block1,
+                    block2,
+                    partial,
+                );
+            });
+        let speeds = &mut layout.speeds.points;
+        for (c, partial) in partials[..chunks - offset].iter().enumerate() {
+            let mut partial = partial.iter();
+            let blocks = std::iter::once(block(c)).chain((offset > 0).then(|| block(c + offset)));
+            for b in blocks {
+                for (speed, p) in speeds[b.start * D..b.end * D].iter_mut().zip(&mut partial) {
+                    *speed += *p;
+                }
+            }
+        }
+    }
+    layout.partials = partials;
+}
+
+pub fn apply_repulsion_forceatlas2_2d_deterministic(layout: &mut Layout) {
+    let law = ForceAtlas2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_deterministic::<_, 2>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_forceatlas2_3d_deterministic(layout: &mut Layout) {
+    let law = ForceAtlas2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_deterministic::<_, 3>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_force2_2d_deterministic(layout: &mut Layout) {
+    let law = Force2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_deterministic::<_, 2>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_force2_3d_deterministic(layout: &mut Layout) {
+    let law = Force2Law::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_deterministic::<_, 3>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_fruchterman_2d_deterministic(layout: &mut Layout) {
+    let law = FruchtermanLaw::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_deterministic::<_, 2>(layout, law, max_distance2);
+}
+
+pub fn apply_repulsion_fruchterman_3d_deterministic(layout: &mut Layout) {
+    let law = FruchtermanLaw::new(&layout.settings);
+    let max_distance2 = layout.settings.max_distance * layout.settings.max_distance;
+    apply_repulsion_deterministic::<_, 3>(layout, law, max_distance2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout_2d(chunk_size: Option<usize>) -> Layout {
+        let n = 40;
+        Layout::from_position_graph(
+            vec![],
+            Nodes::Mass(vec![1.0; n]),
+            (0..n)
+                .flat_map(|i| [i as f32, ((i * 37) % 23) as f32])
+                .collect(),
+            None,
+            Settings {
+                chunk_size,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_serial_matches_parallel() {
+        let mut serial = layout_2d(None);
+        let mut parallel = layout_2d(Some(7));
+        apply_repulsion_forceatlas2_2d(&mut serial);
+        apply_repulsion_forceatlas2_2d_parallel(&mut parallel);
+        for (s, p) in serial
+            .speeds
+            .points
+            .iter()
+            .zip(parallel.speeds.points.iter())
+        {
+            assert!((s - p).abs() <= 1e-4 * s.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        for chunk_size in [None, Some(7)] {
+            let mut scalar = layout_2d(None);
+            let mut simd = layout_2d(chunk_size);
+            apply_repulsion_forceatlas2_2d(&mut scalar);
+            apply_repulsion_forceatlas2_2d_simd(&mut simd);
+            for (s, v) in scalar.speeds.points.iter().zip(simd.speeds.points.iter()) {
+                assert!((s - v).abs() <= 1e-4 * s.abs().max(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_matches_all_pairs() {
+        for simd in [false, true] {
+            let mut all_pairs = layout_2d(None);
+            let mut grid = layout_2d(Some(7));
+            for layout in [&mut all_pairs, &mut grid] {
+                layout.settings.max_distance = 5.0;
+                layout.settings.simd = simd;
+            }
+            apply_repulsion_forceatlas2_2d(&mut all_pairs);
+            apply_repulsion_forceatlas2_2d_grid(&mut grid);
+            for (a, g) in all_pairs
+                .speeds
+                .points
+                .iter()
+                .zip(grid.speeds.points.iter())
+            {
+                assert!((a - g).abs() <= 1e-4 * a.abs().max(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_coincident_nodes() {
+        for chunk_size in [None, Some(1)] {
+            let mut layout = Layout::from_position_graph(
+                vec![],
+                Nodes::Mass(vec![1.0; 3]),
+                vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0],
+                None,
+                Settings {
+                    chunk_size,
+                    ..Default::default()
+                },
+            );
+            let kernels: [fn(&mut Layout); 2] = match chunk_size {
+                Some(_) => [
+                    apply_repulsion_forceatlas2_2d_parallel,
+                    apply_repulsion_force2_2d_parallel,
+                ],
+                None => [apply_repulsion_forceatlas2_2d, apply_repulsion_force2_2d],
+            };
+            for kernel in kernels {
+                kernel(&mut layout);
+                assert!(layout.speeds.points.iter().all(|s| s.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_coincident_nodes_3d() {
+        for chunk_size in [None, Some(1)] {
+            let mut layout = Layout::from_position_graph(
+                vec![],
+                Nodes::Mass(vec![1.0; 3]),
+                vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+                None,
+                Settings {
+                    chunk_size,
+                    dimensions: 3,
+                    ..Default::default()
+                },
+            );
+            let kernels: [fn(&mut Layout); 2] = match chunk_size {
+                Some(_) => [
+                    apply_repulsion_forceatlas2_3d_parallel,
+                    apply_repulsion_force2_3d_parallel,
+                ],
+                None => [apply_repulsion_forceatlas2_3d, apply_repulsion_force2_3d],
+            };
+            for kernel in kernels {
+                kernel(&mut layout);
+                assert!(layout.speeds.points.iter().all(|s| s.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_3d_all_pairs_max_distance() {
+        let n = 40;
+        let layout_3d = |chunk_size: Option<usize>| {
+            Layout::from_position_graph(
+                vec![],
+                Nodes::Mass(vec![1.0; n]),
+                (0..n)
+                    .flat_map(|i| [i as f32, ((i * 37) % 23) as f32, ((i * 11) % 7) as f32])
+                    .collect(),
+                None,
+                Settings {
+                    chunk_size,
+                    dimensions: 3,
+                    max_distance: 5.0,
+                    ..Default::default()
+                },
+            )
+        };
+        let mut grid = layout_3d(None);
+        apply_repulsion_forceatlas2_3d_grid(&mut grid);
+        let kernels: [fn(&mut Layout); 4] = [
+            apply_repulsion_forceatlas2_3d,
+            apply_repulsion_forceatlas2_3d_parallel,
+            apply_repulsion_forceatlas2_3d_simd,
+            apply_repulsion_forceatlas2_3d_deterministic,
+        ];
+        for kernel in kernels {
+            let mut layout = layout_3d(Some(7));
+            kernel(&mut layout);
+            for (g, a) in grid.speeds.points.iter().zip(layout.speeds.points.iter()) {
+                assert!((g - a).abs() <= 1e-4 * g.abs().max(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_deterministic_across_thread_counts() {
+        let run = |threads: usize| {
+            let mut layout = layout_2d(Some(3));
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| apply_repulsion_forceatlas2_2d_deterministic(&mut layout));
+            layout.speeds.points
+        };
+        let reference = run(1);
+        for threads in 2..5 {
+            assert_eq!(reference, run(threads));
+        }
+
+        let mut serial = layout_2d(None);
+        apply_repulsion_forceatlas2_2d(&mut serial);
+        for (s, d) in serial.speeds.points.iter().zip(reference.iter()) {
+            assert!((s - d).abs() <= 1e-4 * s.abs().max(1.0));
+        }
     }
 }
@@ -0,0 +1,145 @@
+use crate::util::PointList;
+
+/// Uniform spatial hash bucketing nodes by cells of side `cell_size`
+///
+/// Cells are hashed into a table of about as many buckets as nodes, so memory stays
+/// `O(n)` however spread out the layout is. A bucket may hold nodes of unrelated cells:
+/// callers still have to check distances.
+pub struct SpatialHash {
+    pub cell_size: f32,
+    dimensions: usize,
+    /// Nodes sorted by bucket
+    pub order: Vec<usize>,
+    /// Index of each node in `order`
+    pub rank: Vec<usize>,
+    /// Nodes of bucket `b` are `order[bucket_start[b]..bucket_start[b + 1]]`
+    bucket_start: Vec<usize>,
+}
+
+impl SpatialHash {
+    pub fn new(points: &PointList, cell_size: f32) -> Self {
+        let dimensions = points.dimensions;
+        let n = points.points.len() / dimensions;
+        let mut hash = Self {
+            cell_size,
+            dimensions,
+            order: vec![0; n],
+            rank: vec![0; n],
+            bucket_start: vec![0; n.next_power_of_two() + 1],
+        };
+
+        // Counting sort by bucket
+        let buckets: Vec<usize> = points
+            .iter()
+            .map(|pos| hash.bucket(hash.cell(pos)))
+            .collect();
+        for &b in buckets.iter() {
+            hash.bucket_start[b + 1] += 1;
+        }
+        for b in 1..hash.bucket_start.len() {
+            hash.bucket_start[b] += hash.bucket_start[b - 1];
+        }
+        let mut next = hash.bucket_start.clone();
+        for (node, &b) in buckets.iter().enumerate() {
+            hash.order[next[b]] = node;
+            hash.rank[node] = next[b];
+            next[b] += 1;
+        }
+        hash
+    }
+
+    fn cell(&self, pos: &[f32]) -> [i64; 3] {
+        let mut cell = [0; 3];
+        for (c, p) in cell.iter_mut().zip(pos.iter()) {
+            *c = (*p / self.cell_size).floor() as i64;
+        }
+        cell
+    }
+
+    fn bucket(&self, cell: [i64; 3]) -> usize {
+        let h = (cell[0].wrapping_mul(73_856_093))
+            ^ (cell[1].wrapping_mul(19_349_663))
+            ^ (cell[2].wrapping_mul(83_492_791));
+        (h as u64 as usize) & (self.bucket_start.len() - 2)
+    }
+
+    /// Collects into `ranges` the `order` ranges of the buckets that may hold nodes
+    /// within `cell_size` of `pos`. Each bucket appears once.
+    pub fn neighbor_ranges(&self, pos: &[f32], ranges: &mut Vec<(usize, usize)>) {
+        ranges.clear();
+        let center = self.cell(pos);
+        let mut buckets = [0usize; 27];
+        let count = 3usize.pow(self.dimensions as u32);
+        for (i, b) in buckets.iter_mut().take(count).enumerate() {
+            let mut cell = center;
+            let mut i = i;
+            for c in cell.iter_mut().take(self.dimensions) {
+                *c += (i % 3) as i64 - 1;
+                i /= 3;
+            }
+            *b = self.bucket(cell);
+        }
+        let buckets = &mut buckets[..count];
+        buckets.sort_unstable();
+        for (i, &b) in buckets.iter().enumerate() {
+            let (start, end) = (self.bucket_start[b], self.bucket_start[b + 1]);
+            if start < end && (i == 0 || buckets[i - 1] != b) {
+                ranges.push((start, end));
+            }
+        }
+    }
+
+    /// Nodes that may lie within `cell_size` of `pos`, including any node at `pos`
+    pub fn neighbors<'a>(&'a self, pos: &[f32]) -> impl Iterator<Item = usize> + 'a {
+        let mut ranges = Vec::with_capacity(27);
+        self.neighbor_ranges(pos, &mut ranges);
+        ranges
+            .into_iter()
+            .flat_map(move |(start, end)| self.order[start..end].iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::random_points;
+
+    fn points(dimensions: usize) -> PointList {
+        let mut points = random_points(200, dimensions, 78.0, dimensions as u64);
+        // Negative coordinates too
+        points.points.iter_mut().for_each(|x| *x -= 30.0);
+        points
+    }
+
+    #[test]
+    fn test_neighbors_are_unique() {
+        for dimensions in [2, 3] {
+            let points = points(dimensions);
+            let hash = SpatialHash::new(&points, 4.0);
+            for p in points.iter() {
+                let found: Vec<usize> = hash.neighbors(p).collect();
+                let mut dedup = found.clone();
+                dedup.sort_unstable();
+                dedup.dedup();
+                assert_eq!(dedup.len(), found.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_neighbors_cover_radius() {
+        for dimensions in [2, 3] {
+            let points = points(dimensions);
+            let hash = SpatialHash::new(&points, 4.0);
+            for p in points.iter() {
+                let found: Vec<usize> = hash.neighbors(p).collect();
+                for (n2, q) in points.iter().enumerate() {
+                    let d2: f32 = p.iter().zip(q.iter()).map(|(a, b)| (a - b) * (a - b)).sum();
+                    if d2 < 16.0 {
+                        assert!(found.contains(&n2));
+                    }
+                }
+            }
+        }
+    }
+}
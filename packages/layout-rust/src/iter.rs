@@ -1,8 +1,7 @@
-use crate::{layout::Layout, util::*};
+use crate::layout::Layout;
 
-use std::marker::PhantomData;
-
-pub use parallel::*;
+use rayon::prelude::*;
+use std::slice::{Chunks, ChunksMut, Iter};
 
 pub struct Node<'a> {
     #[cfg(test)]
@@ -13,11 +12,29 @@ pub struct Node<'a> {
     pub speed: &'a mut [f32],
 }
 
+/// Nodes of a block, each paired with the nodes of a second block
+///
+/// Not an `Iterator`: a `Node` borrows the iterator, so that its `speed` and the speeds
+/// yielded by its `n2_iter` are never aliased by another `Node`. Use
+/// `while let Some(n1) = iter.next()`.
 pub struct NodeIter<'a> {
-    pub ind: usize,
-    pub(crate) layout: SendPtr<Layout>,
-    pub offset: usize,
-    pub(crate) _phantom: PhantomData<&'a mut Layout>,
+    #[cfg(test)]
+    start: usize,
+    dimensions: usize,
+    ind: usize,
+    masses: &'a [f32],
+    points: &'a [f32],
+    speeds: &'a mut [f32],
+    /// Second block, `None` to pair each node with the following nodes of the first block
+    other: Option<Block<'a>>,
+}
+
+struct Block<'a> {
+    #[cfg(test)]
+    start: usize,
+    masses: &'a [f32],
+    points: &'a [f32],
+    speeds: &'a mut [f32],
 }
 
 pub struct Node2<'a> {
@@ -29,48 +46,49 @@ pub struct Node2<'a> {
 }
 
 pub struct NodeIter2<'a> {
-    pub ind: usize,
-    pub(crate) layout: SendPtr<Layout>,
-    pub offset: usize,
-    pub(crate) _phantom: PhantomData<&'a mut Layout>,
+    #[cfg(test)]
+    ind: usize,
+    masses: Iter<'a, f32>,
+    points: Chunks<'a, f32>,
+    speeds: ChunksMut<'a, f32>,
 }
 
-impl<'a> Iterator for NodeIter<'a> {
-    type Item = Node<'a>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let layout = unsafe { self.layout.0.as_mut() };
-        if let Some(mass) = layout.masses.get(self.ind) {
-            Some({
-                let next_offset = self.offset + layout.settings.dimensions;
-                let next_ind = self.ind + 1;
-                let ret = Node {
-                    #[cfg(test)]
-                    ind: self.ind,
-                    mass,
-                    n2_iter: NodeIter2 {
-                        ind: next_ind,
-                        layout: self.layout,
-                        offset: next_offset,
-                        _phantom: PhantomData::default(),
-                    },
-                    pos: unsafe { layout.points.points.get_unchecked(self.offset..next_offset) },
-                    speed: unsafe {
-                        self.layout
-                            .0
-                            .as_mut()
-                            .speeds
-                            .points
-                            .get_unchecked_mut(self.offset..next_offset)
-                    },
-                };
-                self.offset = next_offset;
-                self.ind = next_ind;
-                ret
-            })
-        } else {
-            None
-        }
+impl<'a> NodeIter<'a> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Node<'_>> {
+        let d = self.dimensions;
+        let i = self.ind;
+        let (masses, points, speeds) = (self.masses, self.points, &mut *self.speeds);
+        let mass = masses.get(i)?;
+        let pos = &points[i * d..(i + 1) * d];
+        let (speed, rest) = speeds[i * d..].split_at_mut(d);
+        self.ind += 1;
+
+        let n2_iter = match &mut self.other {
+            None => NodeIter2 {
+                #[cfg(test)]
+                ind: self.start + i + 1,
+                masses: masses[i + 1..].iter(),
+                points: points[(i + 1) * d..].chunks(d),
+                speeds: rest.chunks_mut(d),
+            },
+            Some(other) => NodeIter2 {
+                #[cfg(test)]
+                ind: other.start,
+                masses: other.masses.iter(),
+                points: other.points.chunks(d),
+                speeds: other.speeds.chunks_mut(d),
+            },
+        };
+
+        Some(Node {
+            #[cfg(test)]
+            ind: self.start + i,
+            mass,
+            n2_iter,
+            pos,
+            speed,
+        })
     }
 }
 
@@ -78,146 +96,109 @@ impl<'a> Iterator for NodeIter2<'a> {
     type Item = Node2<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let layout = unsafe { self.layout.0.as_mut() };
-        if let Some(mass) = layout.masses.get(self.ind) {
-            Some({
-                let next_offset = self.offset + layout.settings.dimensions;
-                let ret = Node2 {
-                    #[cfg(test)]
-                    ind: self.offset / layout.settings.dimensions,
-                    mass,
-                    pos: unsafe { layout.points.points.get_unchecked(self.offset..next_offset) },
-                    speed: unsafe {
-                        self.layout
-                            .0
-                            .as_mut()
-                            .speeds
-                            .points
-                            .get_unchecked_mut(self.offset..next_offset)
-                    },
-                };
-                self.offset = next_offset;
-                self.ind += 1;
-                ret
-            })
-        } else {
-            None
+        let ret = Node2 {
+            #[cfg(test)]
+            ind: self.ind,
+            mass: self.masses.next()?,
+            pos: self.points.next()?,
+            speed: self.speeds.next()?,
+        };
+        #[cfg(test)]
+        {
+            self.ind += 1;
         }
+        Some(ret)
     }
 }
 
-mod parallel {
-    use super::*;
-
-    pub struct NodePar<'a> {
-        #[cfg(test)]
-        pub ind: usize,
-        pub mass: &'a f32,
-        pub n2_iter: NodeParIter2<'a>,
-        pub pos: &'a [f32],
-        pub speed: &'a mut [f32],
-    }
-
-    pub struct NodeParIter<'a> {
-        pub end: usize,
-        pub ind: usize,
-        pub(crate) layout: SendPtr<Layout>,
-        pub n2_start: usize,
-        pub n2_start_ind: usize,
-        pub n2_end: usize,
-        pub offset: usize,
-        pub(crate) _phantom: PhantomData<&'a mut Layout>,
-    }
-
-    pub struct NodePar2<'a> {
-        #[cfg(test)]
-        pub ind: usize,
-        pub mass: &'a f32,
-        pub pos: &'a [f32],
-        pub speed: &'a mut [f32],
-    }
-
-    pub struct NodeParIter2<'a> {
-        pub end: usize,
-        pub ind: usize,
-        pub(crate) layout: SendPtr<Layout>,
-        pub offset: usize,
-        pub(crate) _phantom: PhantomData<&'a mut Layout>,
-    }
-
-    impl<'a> Iterator for NodeParIter<'a> {
-        type Item = NodePar<'a>;
-
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.offset < self.end {
-                Some({
-                    let layout = unsafe { self.layout.0.as_mut() };
-                    let next_offset = self.offset + layout.settings.dimensions;
-                    let next_ind = self.ind + 1;
-                    let ret = NodePar {
-                        #[cfg(test)]
-                        ind: self.ind,
-                        mass: unsafe { layout.masses.get_unchecked(self.ind) },
-                        n2_iter: NodeParIter2 {
-                            end: self.n2_end,
-                            ind: self.n2_start_ind.max(next_ind),
-                            layout: self.layout,
-                            offset: self.n2_start.max(next_offset),
-                            _phantom: PhantomData::default(),
-                        },
-                        pos: unsafe {
-                            layout.points.points.get_unchecked(self.offset..next_offset)
-                        },
-                        speed: unsafe {
-                            self.layout
-                                .0
-                                .as_mut()
-                                .speeds
-                                .points
-                                .get_unchecked_mut(self.offset..next_offset)
-                        },
-                    };
-                    self.offset = next_offset;
-                    self.ind = next_ind;
-                    ret
-                })
-            } else {
-                None
-            }
+impl Layout {
+    /// Iterates over all the pairs `(n1, n2)` with `n1 < n2`
+    pub fn iter_nodes(&mut self) -> NodeIter<'_> {
+        NodeIter {
+            #[cfg(test)]
+            start: 0,
+            dimensions: self.settings.dimensions,
+            ind: 0,
+            masses: &self.masses,
+            points: &self.points.points,
+            speeds: &mut self.speeds.points,
+            other: None,
         }
     }
 
-    impl<'a> Iterator for NodeParIter2<'a> {
-        type Item = NodePar2<'a>;
-
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.offset < self.end {
-                Some({
-                    let layout = unsafe { self.layout.0.as_mut() };
-                    let next_offset = self.offset + layout.settings.dimensions;
-                    let ret = NodePar2 {
+    /// Calls `f` in parallel on blocks of `chunk_size` nodes, covering every pair `(n1, n2)`
+    /// with `n1 < n2` once.
+    ///
+    /// Blocks run in rounds where no two blocks share a node, so each block has exclusive
+    /// access to its speeds. Rounds follow a fixed schedule (the circle method of round-robin
+    /// tournaments), so a given `chunk_size` always sums forces in the same order.
+    pub fn par_for_each_block<F>(&mut self, chunk_size: usize, f: F)
+    where
+        F: Fn(NodeIter) + Sync,
+    {
+        let d = self.settings.dimensions;
+        let chunk_size = chunk_size.max(1);
+        let masses: Vec<&[f32]> = self.masses.chunks(chunk_size).collect();
+        let points: Vec<&[f32]> = self.points.points.chunks(chunk_size * d).collect();
+        let chunks = masses.len();
+
+        // A first round for the blocks within each chunk, then one per round of the tournament
+        // between chunks. With an odd number of chunks, a phantom chunk sits out each round.
+        let players = chunks + chunks % 2;
+        for round in 0..players.max(1) {
+            let mut speeds: Vec<Option<&mut [f32]>> = self
+                .speeds
+                .points
+                .chunks_mut(chunk_size * d)
+                .map(Some)
+                .collect();
+            let blocks: Vec<NodeIter> = if round == 0 {
+                speeds
+                    .into_iter()
+                    .enumerate()
+                    .map(|(c, speeds)| NodeIter {
                         #[cfg(test)]
-                        ind: self.ind,
-                        mass: unsafe { layout.masses.get_unchecked(self.ind) },
-                        pos: unsafe {
-                            layout.points.points.get_unchecked(self.offset..next_offset)
-                        },
-                        speed: unsafe {
-                            self.layout
-                                .0
-                                .as_mut()
-                                .speeds
-                                .points
-                                .get_unchecked_mut(self.offset..next_offset)
-                        },
-                    };
-                    self.offset = next_offset;
-                    self.ind += 1;
-                    ret
-                })
+                        start: c * chunk_size,
+                        dimensions: d,
+                        ind: 0,
+                        masses: masses[c],
+                        points: points[c],
+                        speeds: speeds.unwrap(),
+                        other: None,
+                    })
+                    .collect()
             } else {
-                None
-            }
+                (0..players / 2)
+                    .map(|i| {
+                        let r = round - 1;
+                        let a = if i == 0 {
+                            players - 1
+                        } else {
+                            (r + i) % (players - 1)
+                        };
+                        let b = (r + players - 1 - i) % (players - 1);
+                        (a.min(b), a.max(b))
+                    })
+                    .filter(|(_, b)| *b < chunks)
+                    .map(|(a, b)| NodeIter {
+                        #[cfg(test)]
+                        start: a * chunk_size,
+                        dimensions: d,
+                        ind: 0,
+                        masses: masses[a],
+                        points: points[a],
+                        speeds: speeds[a].take().unwrap(),
+                        other: Some(Block {
+                            #[cfg(test)]
+                            start: b * chunk_size,
+                            masses: masses[b],
+                            points: points[b],
+                            speeds: speeds[b].take().unwrap(),
+                        }),
+                    })
+                    .collect()
+            };
+            blocks.into_par_iter().for_each(&f);
         }
     }
 }
@@ -0,0 +1,286 @@
+//! Kamada & Kawai, "An Algorithm for Drawing General Undirected Graphs".
+//!
+//! Every pair of nodes is joined by a spring whose rest length is the length of the shortest
+//! path between them, and whose strength is inversely proportional to its square. Energy is
+//! minimized one node at a time: the node with the largest gradient is moved by
+//! Newton-Raphson steps until its gradient vanishes.
+
+use crate::builder::initial_position;
+use crate::distance::{all_shortest_paths, fill_unreachable, weighted_adjacency};
+use crate::stress::StressReport;
+use crate::util::{Edge, PointList};
+
+#[derive(Clone, Debug)]
+pub struct KamadaKawaiOptions {
+    pub dimensions: usize,
+    /// Maximum number of nodes moved
+    pub max_iterations: usize,
+    /// Newton-Raphson steps for each node moved
+    pub inner_iterations: usize,
+    /// Stop once the gradient of every node is shorter than this
+    pub epsilon: f32,
+}
+
+impl Default for KamadaKawaiOptions {
+    fn default() -> Self {
+        Self {
+            dimensions: 2,
+            max_iterations: 10_000,
+            inner_iterations: 20,
+            epsilon: 1e-3,
+        }
+    }
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting, `None` if `a` is singular
+fn solve(mut a: Vec<Vec<f32>>, mut b: Vec<f32>) -> Option<Vec<f32>> {
+    let d = b.len();
+    for c in 0..d {
+        let pivot = (c..d).max_by(|i, j| a[*i][c].abs().total_cmp(&a[*j][c].abs()))?;
+        if a[pivot][c].abs() < 1e-9 {
+            return None;
+        }
+        a.swap(c, pivot);
+        b.swap(c, pivot);
+        let (top, bottom) = a.split_at_mut(c + 1);
+        let (above, below) = b.split_at_mut(c + 1);
+        for (row, y) in bottom.iter_mut().zip(below.iter_mut()) {
+            let f = row[c] / top[c][c];
+            for (x, pivot) in row[c..].iter_mut().zip(top[c][c..].iter()) {
+                *x -= f * pivot;
+            }
+            *y -= f * above[c];
+        }
+    }
+    let mut x = vec![0.0; d];
+    for r in (0..d).rev() {
+        let s: f32 = (r + 1..d).map(|k| a[r][k] * x[k]).sum();
+        x[r] = (b[r] - s) / a[r][r];
+    }
+    Some(x)
+}
+
+struct Springs {
+    /// Rest lengths
+    lengths: Vec<Vec<f32>>,
+    dimensions: usize,
+}
+
+impl Springs {
+    /// Gradient of the energy of the spring between `p` and `q`, with respect to `p`
+    fn gradient(&self, i: usize, j: usize, p: &[f32], q: &[f32], out: &mut [f32]) {
+        let l = self.lengths[i][j];
+        let dist = p
+            .iter()
+            .zip(q)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt();
+        if l <= 0.0 || dist == 0.0 {
+            return;
+        }
+        let k = 1.0 / (l * l);
+        for ((o, a), b) in out.iter_mut().zip(p).zip(q) {
+            *o += k * (a - b) * (1.0 - l / dist);
+        }
+    }
+
+    /// Gradient of the energy of node `m`
+    fn node_gradient(&self, points: &PointList, m: usize) -> Vec<f32> {
+        let mut g = vec![0.0; self.dimensions];
+        let p = points.get(m);
+        for i in 0..self.lengths.len() {
+            if i != m {
+                self.gradient(m, i, p, points.get(i), &mut g);
+            }
+        }
+        g
+    }
+
+    /// Hessian of the energy of node `m`
+    fn hessian(&self, points: &PointList, m: usize) -> Vec<Vec<f32>> {
+        let d = self.dimensions;
+        let mut h = vec![vec![0.0; d]; d];
+        let p = points.get(m);
+        for (i, l) in self.lengths[m].iter().enumerate() {
+            let q = points.get(i);
+            let delta: Vec<f32> = p.iter().zip(q).map(|(a, b)| a - b).collect();
+            let dist2: f32 = delta.iter().map(|x| x * x).sum();
+            if i == m || *l <= 0.0 || dist2 == 0.0 {
+                continue;
+            }
+            let k = 1.0 / (l * l);
+            let dist3 = dist2 * dist2.sqrt();
+            for a in 0..d {
+                for b in 0..d {
+                    let identity = if a == b { 1.0 } else { 0.0 };
+                    h[a][b] +=
+                        k * (identity - l * (dist2 * identity - delta[a] * delta[b]) / dist3);
+                }
+            }
+        }
+        h
+    }
+
+    fn energy(&self, points: &PointList) -> f32 {
+        let mut energy = 0.0;
+        for (i, row) in self.lengths.iter().enumerate() {
+            for (j, l) in row.iter().enumerate().skip(i + 1) {
+                if *l <= 0.0 {
+                    continue;
+                }
+                let dist = points
+                    .get(i)
+                    .iter()
+                    .zip(points.get(j))
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f32>()
+                    .sqrt();
+                energy += (dist - l) * (dist - l) / (l * l);
+            }
+        }
+        energy
+    }
+}
+
+/// Lays out the graph of `n` nodes by Kamada-Kawai, in 2D or 3D
+///
+/// `lengths` are the lengths of the edges, `1` if `None`. Starts from `initial` positions,
+/// e.g. those of a `Layout`, or else from a spiral. The result is deterministic. The report
+/// gives the energy of the springs as stress.
+///
+/// Takes `O(n²)` memory, and time for each node moved: meant for graphs under a thousand
+/// nodes.
+pub fn kamada_kawai(
+    n: usize,
+    edges: &[Edge],
+    lengths: Option<&[f32]>,
+    initial: Option<&PointList>,
+    options: &KamadaKawaiOptions,
+) -> (PointList, StressReport) {
+    let d = options.dimensions;
+    let mut distances = all_shortest_paths(&weighted_adjacency(n, edges, lengths));
+    fill_unreachable(&mut distances);
+    let springs = Springs {
+        lengths: distances,
+        dimensions: d,
+    };
+
+    let mut points = match initial {
+        Some(initial) => {
+            assert_eq!(initial.dimensions, d);
+            assert_eq!(initial.points.len(), n * d);
+            initial.clone()
+        }
+        None => PointList {
+            dimensions: d,
+            points: (0..n)
+                .flat_map(|i| {
+                    // The spiral is spaced by 10 units, edges by about one
+                    initial_position(i, d, &[]).into_iter().map(|x| x / 10.0)
+                })
+                .collect(),
+        },
+    };
+
+    let mut gradients: Vec<Vec<f32>> = (0..n).map(|m| springs.node_gradient(&points, m)).collect();
+    let norm = |g: &[f32]| g.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let mut report = StressReport {
+        iterations: 0,
+        stress: 0.0,
+        converged: false,
+    };
+
+    for iteration in 0..options.max_iterations {
+        let (m, largest) = gradients
+            .iter()
+            .map(|g| norm(g))
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            .unwrap_or((0, 0.0));
+        if largest < options.epsilon {
+            report.converged = true;
+            break;
+        }
+        report.iterations = iteration + 1;
+
+        let old = points.get(m).to_vec();
+        for _ in 0..options.inner_iterations {
+            let g = springs.node_gradient(&points, m);
+            if norm(&g) < options.epsilon {
+                break;
+            }
+            let step = match solve(springs.hessian(&points, m), g.iter().map(|x| -x).collect()) {
+                Some(step) => step,
+                None => break,
+            };
+            for (p, s) in points.get_mut(m).iter_mut().zip(step) {
+                *p += s;
+            }
+        }
+
+        // Update the gradients of the other nodes for the move of `m`
+        let new = points.get(m).to_vec();
+        for (i, g) in gradients.iter_mut().enumerate() {
+            if i == m {
+                continue;
+            }
+            let p = points.get(i);
+            let mut before = vec![0.0; d];
+            springs.gradient(i, m, p, &old, &mut before);
+            let mut after = vec![0.0; d];
+            springs.gradient(i, m, p, &new, &mut after);
+            for ((x, a), b) in g.iter_mut().zip(before).zip(after) {
+                *x += b - a;
+            }
+        }
+        gradients[m] = springs.node_gradient(&points, m);
+    }
+
+    report.stress = springs.energy(&points);
+    (points, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kamada_kawai() {
+        let distance = |points: &PointList, i: usize, j: usize| {
+            points
+                .get(i)
+                .iter()
+                .zip(points.get(j))
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f32>()
+                .sqrt()
+        };
+
+        // A cycle of four nodes is drawn as a square
+        let cycle = vec![(0, 1), (1, 2), (2, 3), (3, 0)];
+        let (points, report) = kamada_kawai(4, &cycle, None, None, &Default::default());
+        assert!(report.converged);
+        for i in 0..4 {
+            let side = distance(&points, i, (i + 1) % 4);
+            assert!((side - distance(&points, 0, 1)).abs() < 1e-2);
+        }
+        assert!((distance(&points, 0, 2) - distance(&points, 1, 3)).abs() < 1e-2);
+
+        // In 3D, a complete graph of four nodes is a regular tetrahedron
+        let complete = vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        let options = KamadaKawaiOptions {
+            dimensions: 3,
+            ..Default::default()
+        };
+        let (points, report) = kamada_kawai(4, &complete, Some(&[2.0; 6]), None, &options);
+        assert!(report.converged);
+        assert!(report.stress < 1e-4);
+        assert!((distance(&points, 0, 3) - 2.0).abs() < 1e-2);
+
+        // Starting from a solution, nothing moves
+        let (again, report) = kamada_kawai(4, &complete, Some(&[2.0; 6]), Some(&points), &options);
+        assert_eq!(report.iterations, 0);
+        assert_eq!(again.points, points.points);
+    }
+}
@@ -1,7 +1,7 @@
-use crate::{iter::*, util::*};
+use crate::stats::IterationStats;
+use crate::util::*;
 
-use rayon::prelude::*;
-use std::marker::PhantomData;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub enum LayoutType {
@@ -20,6 +20,16 @@ pub enum DistanceThresholdMode {
     Min,
 }
 
+#[derive(Clone)]
+pub enum NeighborSearch {
+    /// Visit every pair of nodes
+    AllPairs,
+    /// Only visit pairs closer than `max_distance`, using a spatial hash
+    ///
+    /// Falls back to `AllPairs` when `max_distance` is not a positive finite number.
+    Grid,
+}
+
 #[derive(Clone)]
 pub struct Settings {
     pub name: LayoutType,
@@ -31,6 +41,24 @@ pub struct Settings {
     ///
     /// Requires `T: Send + Sync`
     pub chunk_size: Option<usize>,
+    /// Thread pool running the parallel repulsion
+    ///
+    /// Defaults to rayon's global pool. Ignored when `chunk_size` is `None`.
+    pub thread_pool: Option<Arc<rayon::ThreadPool>>,
+    /// Use the vectorized repulsion kernels
+    ///
+    /// Positions are copied to a structure-of-arrays layout and each node sums the forces
+    /// of all the others, several at a time. Not used with `prevent_overlapping`.
+    pub simd: bool,
+    /// How repulsion finds the pairs of nodes to compute. Not used with `prevent_overlapping`.
+    pub neighbor_search: NeighborSearch,
+    /// Make the parallel repulsion bit-reproducible, whatever the number of threads
+    ///
+    /// Each block of pairs writes to its own buffer, and buffers are added to the speeds in a
+    /// fixed order. The default parallel kernels give the same guarantee through their fixed
+    /// round-robin schedule; this mode runs more blocks at once, at the cost of the buffers.
+    /// The serial, `simd` and `Grid` kernels always are reproducible.
+    pub deterministic: bool,
     /// Number of spatial dimensions
     pub dimensions: usize,
     /// Move hubs (high degree nodes) to the center
@@ -68,6 +96,7 @@ pub struct Settings {
     pub max_speed: f32,
     pub min_movement: f32,
     pub distance_threshold_mode: DistanceThresholdMode,
+    /// Pairs of nodes further apart do not repel each other
     pub max_distance: f32,
 
     /// Used in Fruchterman layout.
@@ -78,6 +107,10 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             chunk_size: Some(256),
+            thread_pool: None,
+            simd: false,
+            neighbor_search: NeighborSearch::AllPairs,
+            deterministic: false,
             dimensions: 2,
             dissuade_hubs: false,
             ka: 1.0,
@@ -113,6 +146,20 @@ pub struct Layout {
     pub speeds: PointList,
     pub old_speeds: PointList,
     pub weights: Option<Vec<f32>>,
+    /// Per-node sizes, used instead of the `node_size` of `prevent_overlapping`
+    ///
+    /// Two nodes overlap when closer than the mean of their sizes.
+    pub sizes: Option<Vec<f32>>,
+    /// Nodes kept at their current position
+    pub fixed: Option<Vec<bool>>,
+    /// Statistics of the last iteration
+    pub stats: IterationStats,
+    /// Statistics of every iteration, collected when `Some`
+    pub history: Option<Vec<IterationStats>>,
+    /// Positions copied for the `simd` repulsion, kept to reuse the allocation
+    pub(crate) soa: PointListSoa,
+    /// Per-block buffers of the `deterministic` repulsion, kept to reuse the allocations
+    pub(crate) partials: Vec<Vec<f32>>,
 
     pub(crate) fn_attraction: fn(&mut Self),
     pub(crate) fn_gravity: fn(&mut Self),
@@ -120,44 +167,26 @@ pub struct Layout {
 }
 
 impl Layout {
-    pub fn iter_nodes(&mut self) -> NodeIter {
-        NodeIter {
-            ind: 0,
-            layout: SendPtr(self.into()),
-            offset: 0,
-            _phantom: PhantomData::default(),
+    /// Distance under which `n1` and `n2` overlap, with `prevent_overlapping`
+    pub(crate) fn overlap_distance(&self, n1: usize, n2: usize) -> f32 {
+        match &self.sizes {
+            Some(sizes) => (sizes[n1] + sizes[n2]) / 2.0,
+            None => self
+                .settings
+                .prevent_overlapping
+                .as_ref()
+                .map_or(0.0, |(node_size, _)| *node_size),
         }
     }
-}
 
-impl Layout {
-    pub fn iter_par_nodes(
-        &mut self,
-        chunk_size: usize,
-    ) -> impl Iterator<Item = impl ParallelIterator<Item = NodeParIter>> {
-        let ptr = SendPtr(self.into());
-        let dimensions = self.settings.dimensions;
-        let chunk_size_d = chunk_size * dimensions;
-        let n = self.masses.len() * dimensions;
-        (0..self.masses.len()).step_by(chunk_size).map(move |y0| {
-            let y0_d = y0 * dimensions;
-            (0..self.masses.len() - y0)
-                .into_par_iter()
-                .step_by(chunk_size)
-                .map(move |x0| {
-                    let x0_d = x0 * dimensions;
-                    NodeParIter {
-                        end: (x0_d + chunk_size_d).min(n),
-                        ind: x0,
-                        layout: ptr,
-                        n2_start: x0_d + y0_d,
-                        n2_start_ind: x0 + y0,
-                        n2_end: (x0_d + y0_d + chunk_size_d).min(n),
-                        offset: x0_d,
-                        _phantom: PhantomData::default(),
-                    }
-                })
-        })
+    /// Moves the nodes that are not fixed to `points`, e.g. to seed a layout
+    pub(crate) fn seed(&mut self, points: &PointList) {
+        let fixed = self.fixed.as_deref();
+        for (n, (p, q)) in self.points.iter_mut().zip(points.iter()).enumerate() {
+            if !fixed.is_some_and(|fixed| fixed[n]) {
+                p.copy_from_slice(q);
+            }
+        }
     }
 }
 
@@ -166,14 +195,15 @@ mod test {
     use super::*;
     use itertools::iproduct;
     use std::collections::BTreeSet;
-    use std::sync::{Arc, RwLock};
+    use std::sync::Mutex;
 
     #[test]
     fn test_iter_nodes() {
         for n_nodes in 1usize..16 {
-            let mut layout = Layout::<f32>::from_graph(
+            let mut layout = Layout::from_position_graph(
                 vec![],
                 Nodes::Degree(n_nodes),
+                (0..n_nodes * 2).map(|i| i as f32).collect(),
                 None,
                 Settings::default(),
             );
@@ -181,7 +211,8 @@ mod test {
                 .filter(|(n1, n2)| n1 < n2)
                 .collect::<BTreeSet<(usize, usize)>>();
             let points = layout.points.clone();
-            for n1 in layout.iter_nodes() {
+            let mut n1_iter = layout.iter_nodes();
+            while let Some(n1) = n1_iter.next() {
                 for n2 in n1.n2_iter {
                     assert!(hits.remove(&(n1.ind, n2.ind)));
                     assert_eq!(n1.pos, points.get(n1.ind));
@@ -191,4 +222,36 @@ mod test {
             assert!(hits.is_empty());
         }
     }
+
+    #[test]
+    fn test_par_for_each_block() {
+        for n_nodes in 1usize..16 {
+            for chunk_size in 1..6 {
+                let mut layout = Layout::from_position_graph(
+                    vec![],
+                    Nodes::Degree(n_nodes),
+                    (0..n_nodes * 2).map(|i| i as f32).collect(),
+                    None,
+                    Settings::default(),
+                );
+                let hits = Mutex::new(
+                    iproduct!(0..n_nodes, 0..n_nodes)
+                        .filter(|(n1, n2)| n1 < n2)
+                        .collect::<BTreeSet<(usize, usize)>>(),
+                );
+                let points = layout.points.clone();
+                layout.par_for_each_block(chunk_size, |mut n1_iter| {
+                    while let Some(n1) = n1_iter.next() {
+                        assert_eq!(n1.pos, points.get(n1.ind));
+                        for n2 in n1.n2_iter {
+                            let (lo, hi) = (n1.ind.min(n2.ind), n1.ind.max(n2.ind));
+                            assert!(hits.lock().unwrap().remove(&(lo, hi)));
+                            assert_eq!(n2.pos, points.get(n2.ind));
+                        }
+                    }
+                });
+                assert!(hits.lock().unwrap().is_empty());
+            }
+        }
+    }
 }
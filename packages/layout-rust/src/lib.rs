@@ -5,16 +5,47 @@
 #![allow(incomplete_features)]
 #![feature(core_intrinsics)]
 
+mod builder;
+pub mod bundling;
 mod dagre;
+pub mod distance;
+mod edges;
 mod forces;
+mod grid;
 mod iter;
+mod kamada_kawai;
 mod layout;
+pub mod linear;
+pub mod mds;
+pub mod metrics;
+pub mod multilevel;
+mod overlap;
+pub mod pack;
+mod run;
+mod sgd;
+mod simd;
+mod spectral;
+mod stats;
+mod stress;
+pub mod transform;
+pub mod tree;
 mod util;
 
 use forces::{Attraction, Gravity, Repulsion};
 
-pub use layout::{DistanceThresholdMode, Layout, LayoutType, Settings};
-pub use util::{Edge, Nodes, PointIter, PointIterMut, PointList, Position};
+pub use builder::{ForceGraph, ForceGraphBuilder, NodeOptions};
+pub use edges::{normalize_edges, orient_edges, EdgeReport};
+pub use grid::SpatialHash;
+pub use kamada_kawai::{kamada_kawai, KamadaKawaiOptions};
+pub use layout::{DistanceThresholdMode, Layout, LayoutType, NeighborSearch, Settings};
+pub use overlap::remove_overlaps;
+pub use run::{RunOptions, RunReport, StopReason};
+pub use sgd::{sgd_layout, SgdOptions};
+pub use spectral::{spectral_layout, SpectralOptions};
+pub use stats::{is_oscillating, IterationStats};
+pub use stress::{stress_majorization, StressOptions, StressReport};
+pub use util::{Edge, Nodes, PointIter, PointIterMut, PointList, PointListSoa, Position};
+pub use dagre::bipartite::{bipartite_layout, BipartiteLayout, BipartiteOptions};
 pub use dagre::layout;
 pub use dagre::util::unique_id;
 pub use graphlib_rust::{Graph, GraphOption};
@@ -27,20 +58,29 @@ where
 {
     /// Instanciates layout from an undirected graph, using initial positions
     ///
-    /// Assumes edges `(n1, n2)` respect `n1 < n2`
+    /// Edges `(n1, n2)` may be in any orientation. Self-loops exert no force and are dropped;
+    /// duplicate edges are kept as is, see `normalize_edges` to merge them.
     ///
     /// `nodes` is a list of coordinates, e.g. `[x1, y1, x2, y2, ...]`.
     pub fn from_position_graph(
-        edges: Vec<Edge>,
+        mut edges: Vec<Edge>,
         nodes: Nodes,
         positions: Vec<f32>,
-        weights: Option<Vec<f32>>,
+        mut weights: Option<Vec<f32>>,
         settings: Settings,
     ) -> Self {
         if let Some(weights) = &weights {
             assert_eq!(weights.len(), edges.len());
         }
 
+        if edges.iter().any(|(n1, n2)| n1 == n2) {
+            if let Some(weights) = &mut weights {
+                let mut kept = edges.iter().map(|(n1, n2)| n1 != n2);
+                weights.retain(|_| kept.next().unwrap());
+            }
+            edges.retain(|(n1, n2)| n1 != n2);
+        }
+
         let nodes = match nodes {
             Nodes::Degree(nb_nodes) => {
                 let mut degrees: Vec<usize> = vec![0; nb_nodes];
@@ -71,6 +111,12 @@ where
                 points: (0..nb).map(|_| 0.0).collect(),
             },
             weights,
+            sizes: None,
+            fixed: None,
+            stats: IterationStats::default(),
+            history: None,
+            soa: PointListSoa::default(),
+            partials: Vec::new(),
             fn_attraction: Self::choose_attraction(&settings),
             fn_gravity: Self::choose_gravity(&settings),
             fn_repulsion: Self::choose_repulsion(&settings),
@@ -83,12 +129,26 @@ where
     }
 
     /// Computes an iteration
+    ///
+    /// Returns whether the layout converged, i.e. nodes moved less than `min_movement`.
     pub fn iteration(&mut self, i: usize) -> bool {
+        self.step(i) < self.settings.min_movement
+    }
+
+    /// Computes an iteration, returning how far nodes moved, according to
+    /// `distance_threshold_mode`
+    pub(crate) fn step(&mut self, i: usize) -> f32 {
         self.init_iteration(i);
         self.apply_attraction();
         self.apply_repulsion();
         self.apply_gravity();
 
+        let mut stats = IterationStats {
+            iteration: i,
+            ..Default::default()
+        };
+        self.force_stats(&mut stats);
+
         let mut judging_distance = match self.settings.distance_threshold_mode {
             DistanceThresholdMode::Average => 0.0,
             DistanceThresholdMode::Max => f32::MIN,
@@ -96,18 +156,21 @@ where
         };
         let distance_threshold_mode = self.settings.distance_threshold_mode.clone();
 
-        let mut update_judging_distance = |distance| match distance_threshold_mode {
-            DistanceThresholdMode::Average => {
-                judging_distance += distance;
-            }
-            DistanceThresholdMode::Max => {
-                if distance > judging_distance {
-                    judging_distance = distance;
+        let mut update_judging_distance = |distance: f32| {
+            stats.record_displacement(distance);
+            match distance_threshold_mode {
+                DistanceThresholdMode::Average => {
+                    judging_distance += distance;
                 }
-            }
-            DistanceThresholdMode::Min => {
-                if distance < judging_distance {
-                    judging_distance = distance;
+                DistanceThresholdMode::Max => {
+                    if distance > judging_distance {
+                        judging_distance = distance;
+                    }
+                }
+                DistanceThresholdMode::Min => {
+                    if distance < judging_distance {
+                        judging_distance = distance;
+                    }
                 }
             }
         };
@@ -124,12 +187,22 @@ where
             LayoutType::ForceAtlas2 => self.apply_forces_forceatlas2(&mut update_judging_distance),
         };
 
+        let nb_fixed = self
+            .fixed
+            .as_ref()
+            .map_or(0, |fixed| fixed.iter().filter(|fixed| **fixed).count());
+        let nb_moving = (self.points.points.len() / self.points.dimensions - nb_fixed).max(1);
         if let DistanceThresholdMode::Average = self.settings.distance_threshold_mode {
-            judging_distance =
-                judging_distance / (self.points.points.len() / self.points.dimensions) as f32;
+            judging_distance /= nb_moving as f32;
+        }
+        stats.avg_displacement /= nb_moving as f32;
+
+        if let Some(history) = &mut self.history {
+            history.push(stats.clone());
         }
+        self.stats = stats;
 
-        judging_distance < self.settings.min_movement
+        judging_distance
     }
 
     fn init_iteration(&mut self, i: usize) {
@@ -173,7 +246,10 @@ where
     }
 
     fn apply_repulsion(&mut self) {
-        (self.fn_repulsion)(self)
+        match (&self.settings.chunk_size, self.settings.thread_pool.clone()) {
+            (Some(_), Some(pool)) => pool.install(|| (self.fn_repulsion)(self)),
+            _ => (self.fn_repulsion)(self),
+        }
     }
 
     fn apply_forces_force2(&mut self, update_judging_distance: &mut impl FnMut(f32)) {
@@ -199,7 +275,13 @@ where
             }
         }
 
-        for (pos, old_speed) in izip!(self.points.iter_mut(), self.old_speeds.iter_mut(),) {
+        let fixed = self.fixed.as_deref();
+        for (n, (pos, old_speed)) in
+            izip!(self.points.iter_mut(), self.old_speeds.iter_mut(),).enumerate()
+        {
+            if fixed.is_some_and(|fixed| fixed[n]) {
+                continue;
+            }
             let mut distance = 0.0;
             pos.iter_mut()
                 .zip(old_speed.iter())
@@ -218,7 +300,11 @@ where
         let u_speed = self.settings.speed;
         let max_displace = u_speed * self.settings.damping * i;
 
-        for (pos, speed) in izip!(self.points.iter_mut(), self.speeds.iter(),) {
+        let fixed = self.fixed.as_deref();
+        for (n, (pos, speed)) in izip!(self.points.iter_mut(), self.speeds.iter(),).enumerate() {
+            if fixed.is_some_and(|fixed| fixed[n]) {
+                continue;
+            }
             let dist_length = speed
                 .iter()
                 .map(|s| (*s * u_speed).powi(2))
@@ -243,11 +329,17 @@ where
     }
 
     fn apply_forces_forceatlas2(&mut self, update_judging_distance: &mut impl FnMut(f32)) {
-        for (pos, speed, old_speed) in izip!(
+        let fixed = self.fixed.as_deref();
+        for (n, (pos, speed, old_speed)) in izip!(
             self.points.iter_mut(),
             self.speeds.iter_mut(),
             self.old_speeds.iter()
-        ) {
+        )
+        .enumerate()
+        {
+            if fixed.is_some_and(|fixed| fixed[n]) {
+                continue;
+            }
             let swinging = speed
                 .iter()
                 .zip(old_speed.iter())
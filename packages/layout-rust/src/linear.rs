@@ -0,0 +1,355 @@
+//! Linear layout: nodes on a line, edges as arcs above or below it, e.g. arc diagrams.
+//!
+//! The order of the nodes starts from a given order or from the Fiedler vector of the
+//! graph, then barycentric sweeps, those of `dagre`, move each node to the mean rank of its
+//! neighbours. The best order seen, for arc length or crossings, is kept. Arcs are then
+//! assigned a side, longest first, where they cross fewer of the arcs already placed.
+
+use crate::dagre::order::sweep;
+use crate::spectral::spectral_order;
+use crate::util::{Edge, PointList};
+use crate::{GraphConfig, GraphEdge, GraphNode};
+use graphlib_rust::{Graph, GraphOption};
+
+#[derive(Clone, Debug)]
+pub enum LinearOrder {
+    /// Nodes by index
+    Index,
+    /// Nodes in the given order
+    Given(Vec<usize>),
+    /// Nodes by their coordinate in the Fiedler vector of their connected component
+    Spectral,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinearObjective {
+    /// Sum of the weighted lengths of the arcs
+    Length,
+    /// Number of crossings of the arcs, as if they all were on the same side
+    Crossings,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinearOptions {
+    pub initial: LinearOrder,
+    /// Barycentric sweeps improving the initial order, `0` to keep it
+    pub iterations: usize,
+    pub objective: LinearObjective,
+    /// Distance between two consecutive nodes
+    pub spacing: f32,
+    /// Put arcs below the line when they cross fewer arcs there, else all arcs are above
+    pub both_sides: bool,
+}
+
+impl Default for LinearOptions {
+    fn default() -> Self {
+        Self {
+            initial: LinearOrder::Spectral,
+            iterations: 20,
+            objective: LinearObjective::Crossings,
+            spacing: 20.0,
+            both_sides: true,
+        }
+    }
+}
+
+/// Half circle of an edge, in the coordinates of the line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arc {
+    pub center: f32,
+    pub radius: f32,
+    pub above: bool,
+}
+
+pub struct LinearLayout {
+    /// Nodes from the start of the line
+    pub order: Vec<usize>,
+    /// Positions of the nodes, on the x axis
+    pub points: PointList,
+    /// Arc of each edge
+    pub arcs: Vec<Arc>,
+    /// Crossings of arcs on the same side
+    pub crossings: usize,
+}
+
+/// Whether the arcs between ranks `a` and `b` cross, on the same side
+fn interleave(a: (usize, usize), b: (usize, usize)) -> bool {
+    (a.0 < b.0 && b.0 < a.1 && a.1 < b.1) || (b.0 < a.0 && a.0 < b.1 && b.1 < a.1)
+}
+
+/// Crossings of arcs `(l, r)` between ranks `l < r` below `n`, on the same side, in
+/// `O(m log m)` time
+fn count_crossings(arcs: &[(usize, usize)], n: usize) -> usize {
+    // Arc `i` crosses the arcs starting within it and ending past it: those starting after
+    // `l_i` and ending after `r_i`, but for those starting at or after `r_i`
+    let mut by_start: Vec<(usize, usize)> = arcs.iter().cloned().filter(|a| a.0 < a.1).collect();
+    by_start.sort_unstable();
+    let starts: Vec<usize> = by_start.iter().map(|a| a.0).collect();
+
+    let mut tree = vec![0usize; n + 1];
+    let add = |tree: &mut Vec<usize>, r: usize| {
+        let mut i = r + 1;
+        while i <= n {
+            tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    };
+    // Number of ends up to `r` included
+    let prefix = |tree: &Vec<usize>, r: usize| {
+        let (mut i, mut sum) = (r + 1, 0);
+        while i > 0 {
+            sum += tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    };
+
+    let mut crossings = 0;
+    let mut inserted = 0;
+    let mut end = by_start.len();
+    while end > 0 {
+        let l = by_start[end - 1].0;
+        let begin = starts.partition_point(|s| *s < l);
+        for &(_, r) in by_start[begin..end].iter() {
+            let after = inserted - prefix(&tree, r);
+            let outside = by_start.len() - starts.partition_point(|s| *s < r);
+            crossings += after - outside;
+        }
+        for &(_, r) in by_start[begin..end].iter() {
+            add(&mut tree, r);
+            inserted += 1;
+        }
+        end = begin;
+    }
+    crossings
+}
+
+fn score(
+    rank: &[usize],
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+    objective: LinearObjective,
+) -> f32 {
+    match objective {
+        LinearObjective::Length => edges
+            .iter()
+            .enumerate()
+            .map(|(e, &(a, b))| {
+                let w = weights.map_or(1.0, |weights| weights[e]);
+                w * (rank[a] as f32 - rank[b] as f32).abs()
+            })
+            .sum(),
+        LinearObjective::Crossings => {
+            let arcs: Vec<(usize, usize)> = edges
+                .iter()
+                .map(|&(a, b)| (rank[a].min(rank[b]), rank[a].max(rank[b])))
+                .collect();
+            count_crossings(&arcs, rank.len()) as f32
+        }
+    }
+}
+
+fn ranks(order: &[usize]) -> Vec<usize> {
+    let mut rank = vec![0; order.len()];
+    for (i, &v) in order.iter().enumerate() {
+        rank[v] = i;
+    }
+    rank
+}
+
+/// Lays out the graph of `n` nodes on a line, with its edges as arcs
+///
+/// `weights` are those of the edges, `1` if `None`, used by barycenters and arc length.
+/// Assigning sides takes `O(m²)` time.
+pub fn linear_layout(
+    n: usize,
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+    options: &LinearOptions,
+) -> LinearLayout {
+    let mut order = match &options.initial {
+        LinearOrder::Index => (0..n).collect(),
+        LinearOrder::Given(order) => {
+            let mut seen = vec![false; n];
+            assert_eq!(order.len(), n);
+            for &v in order.iter() {
+                assert!(!std::mem::replace(&mut seen[v], true), "not a permutation");
+            }
+            order.clone()
+        }
+        LinearOrder::Spectral => spectral_order(n, edges, weights),
+    };
+
+    // Edges both ways, so that the predecessors of a node are all its neighbours
+    let mut g: Graph<GraphConfig, GraphNode, GraphEdge> = Graph::new(Some(GraphOption {
+        directed: Some(true),
+        multigraph: Some(false),
+        compound: Some(false),
+    }));
+    for v in 0..n {
+        g.set_node(v.to_string(), Some(GraphNode::default()));
+    }
+    for (e, &(a, b)) in edges.iter().enumerate() {
+        if a == b {
+            continue;
+        }
+        let (a, b) = (a.to_string(), b.to_string());
+        let w = weights.map_or(1.0, |weights| weights[e]);
+        let w = w + g
+            .edge(&a, &b, None)
+            .and_then(|edge| edge.weight)
+            .unwrap_or(0.0);
+        for (v, u) in [(&a, &b), (&b, &a)] {
+            let _ = g.set_edge(
+                v,
+                u,
+                Some(GraphEdge {
+                    weight: Some(w),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+    }
+
+    let mut best = order.clone();
+    let mut best_score = score(&ranks(&order), edges, weights, options.objective);
+    for _ in 0..options.iterations {
+        let layer: Vec<String> = order.iter().map(|v| v.to_string()).collect();
+        for (i, v) in layer.iter().enumerate() {
+            g.node_mut(v).unwrap().order = Some(i);
+        }
+        order = sweep(&g, &layer, false)
+            .iter()
+            .map(|v| v.parse().unwrap())
+            .collect();
+        let s = score(&ranks(&order), edges, weights, options.objective);
+        if s < best_score {
+            best_score = s;
+            best.clone_from(&order);
+        }
+    }
+
+    let rank = ranks(&best);
+    let mut points = PointList {
+        dimensions: 2,
+        points: vec![0.0; n * 2],
+    };
+    for (v, r) in rank.iter().enumerate() {
+        points.get_mut(v)[0] = *r as f32 * options.spacing;
+    }
+
+    // Longest arcs first, on the side where they cross fewer arcs
+    let spans: Vec<(usize, usize)> = edges
+        .iter()
+        .map(|&(a, b)| (rank[a].min(rank[b]), rank[a].max(rank[b])))
+        .collect();
+    let mut by_length: Vec<usize> = (0..edges.len()).collect();
+    by_length.sort_by_key(|e| std::cmp::Reverse(spans[*e].1 - spans[*e].0));
+    let mut above = vec![true; edges.len()];
+    if options.both_sides {
+        let (mut top, mut bottom): (Vec<usize>, Vec<usize>) = (Vec::new(), Vec::new());
+        for e in by_length {
+            let crossed = |side: &[usize]| {
+                side.iter()
+                    .filter(|f| interleave(spans[e], spans[**f]))
+                    .count()
+            };
+            if crossed(&bottom) < crossed(&top) {
+                above[e] = false;
+                bottom.push(e);
+            } else {
+                top.push(e);
+            }
+        }
+    }
+
+    let side = |up: bool| -> Vec<(usize, usize)> {
+        spans
+            .iter()
+            .zip(above.iter())
+            .filter(|(_, a)| **a == up)
+            .map(|(s, _)| *s)
+            .collect()
+    };
+    let crossings = count_crossings(&side(true), n) + count_crossings(&side(false), n);
+    let arcs = spans
+        .iter()
+        .zip(above)
+        .map(|(&(l, r), above)| Arc {
+            center: (l + r) as f32 * options.spacing / 2.0,
+            radius: (r - l) as f32 * options.spacing / 2.0,
+            above,
+        })
+        .collect();
+
+    LinearLayout {
+        order: best,
+        points,
+        arcs,
+        crossings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_layout() {
+        // Crossings against all pairs
+        let arcs = vec![(0, 3), (1, 4), (2, 5), (1, 2), (0, 5), (3, 4), (2, 4)];
+        let mut expected = 0;
+        for (i, a) in arcs.iter().enumerate() {
+            expected += arcs[i + 1..].iter().filter(|b| interleave(*a, **b)).count();
+        }
+        assert_eq!(count_crossings(&arcs, 6), expected);
+        assert_eq!(expected, 4);
+
+        // A shuffled path is straightened by its Fiedler vector, its arcs joining
+        // consecutive nodes
+        let path = vec![(3, 0), (0, 4), (4, 1), (1, 5), (5, 2)];
+        let layout = linear_layout(6, &path, None, &Default::default());
+        assert!(layout.arcs.iter().all(|arc| arc.radius == 10.0));
+        assert_eq!(layout.crossings, 0);
+        // Sweeps never make the initial order worse
+        let options = LinearOptions {
+            initial: LinearOrder::Index,
+            objective: LinearObjective::Length,
+            ..Default::default()
+        };
+        let layout = linear_layout(6, &path, None, &options);
+        let length = |order: &[usize]| score(&ranks(order), &path, None, LinearObjective::Length);
+        assert!(length(&layout.order) <= length(&(0..6).collect::<Vec<_>>()));
+
+        // Two crossing arcs are put on both sides
+        let options = LinearOptions {
+            initial: LinearOrder::Index,
+            iterations: 0,
+            ..Default::default()
+        };
+        let layout = linear_layout(4, &[(0, 2), (1, 3)], None, &options);
+        assert_eq!(layout.order, vec![0, 1, 2, 3]);
+        assert_eq!(layout.crossings, 0);
+        assert_ne!(layout.arcs[0].above, layout.arcs[1].above);
+        assert_eq!(layout.points.get(3), &[60.0, 0.0]);
+        assert_eq!(
+            layout.arcs[1],
+            Arc {
+                center: 40.0,
+                radius: 20.0,
+                above: layout.arcs[1].above
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not a permutation")]
+    fn test_given_order_not_a_permutation() {
+        let options = LinearOptions {
+            initial: LinearOrder::Given(vec![0, 2, 2]),
+            ..Default::default()
+        };
+        linear_layout(3, &[(0, 1)], None, &options);
+    }
+}
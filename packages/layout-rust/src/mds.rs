@@ -0,0 +1,353 @@
+//! Multidimensional scaling: positions whose distances approximate given ones.
+
+use crate::distance::{fill_unreachable, max_min_pivots, weighted_adjacency};
+use crate::layout::Layout;
+use crate::util::{PointList, Rng};
+
+use rayon::prelude::*;
+
+/// Eigenvectors of the `k` largest eigenvalues of the symmetric `n` x `n` matrix applied by
+/// `mul`, with their eigenvalues, by power iteration with deflation
+pub(crate) fn top_eigenvectors<F>(n: usize, k: usize, mul: F) -> Vec<(f32, Vec<f32>)>
+where
+    F: Fn(&[f32], &mut [f32]),
+{
+    let mut found: Vec<(f32, Vec<f32>)> = Vec::with_capacity(k);
+    let orthogonalize = |v: &mut [f32], found: &[(f32, Vec<f32>)]| {
+        for (_, u) in found.iter() {
+            let dot: f32 = v.iter().zip(u.iter()).map(|(a, b)| a * b).sum();
+            v.iter_mut().zip(u.iter()).for_each(|(a, b)| *a -= dot * b);
+        }
+        let norm = v.iter().map(|a| a * a).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            v.iter_mut().for_each(|a| *a /= norm);
+        }
+        norm
+    };
+
+    for e in 0..k.min(n) {
+        // Seeded random start, away from the constant vector
+        let mut rng = Rng::new(e as u64);
+        let mut v: Vec<f32> = (0..n).map(|_| rng.next_f32() - 0.5).collect();
+        orthogonalize(&mut v, &found);
+        let mut w = vec![0.0; n];
+        let mut value = 0.0;
+        for _ in 0..300 {
+            mul(&v, &mut w);
+            value = orthogonalize(&mut w, &found);
+            let delta: f32 = v.iter().zip(w.iter()).map(|(a, b)| (a - b).abs()).sum();
+            std::mem::swap(&mut v, &mut w);
+            if value == 0.0 || delta < 1e-6 * n as f32 {
+                break;
+            }
+        }
+        // The sign of the eigenvalue, lost by normalizing
+        mul(&v, &mut w);
+        let rayleigh: f32 = v.iter().zip(w.iter()).map(|(a, b)| a * b).sum();
+        found.push((value.copysign(rayleigh), v));
+    }
+    found
+}
+
+/// Classical (Torgerson) MDS of the full matrix of `distances`, in `dimensions` dimensions
+///
+/// Takes `O(n²)` memory. Distances must be finite, see `distance::fill_unreachable`.
+pub fn classical_mds(distances: &[Vec<f32>], dimensions: usize) -> PointList {
+    let n = distances.len();
+    // Double centering of the squared distances
+    let squared: Vec<Vec<f32>> = distances
+        .iter()
+        .map(|row| row.iter().map(|d| d * d).collect())
+        .collect();
+    let means: Vec<f32> = squared
+        .iter()
+        .map(|row| row.iter().sum::<f32>() / n as f32)
+        .collect();
+    let mean = means.iter().sum::<f32>() / n.max(1) as f32;
+    let b: Vec<Vec<f32>> = squared
+        .par_iter()
+        .zip(means.par_iter())
+        .map(|(row, mi)| {
+            row.iter()
+                .zip(means.iter())
+                .map(|(d2, mj)| -0.5 * (d2 - mi - mj + mean))
+                .collect()
+        })
+        .collect();
+
+    let eigen = top_eigenvectors(n, dimensions, |v, out| {
+        out.par_iter_mut()
+            .zip(b.par_iter())
+            .for_each(|(o, row)| *o = row.iter().zip(v.iter()).map(|(a, b)| a * b).sum());
+    });
+    from_eigenvectors(n, dimensions, &eigen)
+}
+
+/// Landmark MDS (de Silva & Tenenbaum): classical MDS of the `pivots`, then each node
+/// triangulated from its distances to them
+///
+/// `rows` holds the distances from each pivot to every node, which must be finite. Takes
+/// `O(nk)` memory for `k` pivots.
+pub fn landmark_mds(pivots: &[usize], rows: &[Vec<f32>], dimensions: usize) -> PointList {
+    let k = pivots.len();
+    let n = rows.first().map_or(0, |row| row.len());
+    let between: Vec<Vec<f32>> = rows
+        .iter()
+        .map(|row| pivots.iter().map(|&p| row[p]).collect())
+        .collect();
+    let landmarks = classical_mds(&between, dimensions);
+    let means: Vec<f32> = between
+        .iter()
+        .map(|row| row.iter().map(|d| d * d).sum::<f32>() / k as f32)
+        .collect();
+
+    // Pseudo-inverse of the landmark coordinates, one row per dimension
+    let inverse: Vec<Vec<f32>> = (0..dimensions)
+        .map(|c| {
+            let value: f32 = (0..k).map(|p| landmarks.get(p)[c].powi(2)).sum();
+            (0..k)
+                .map(|p| {
+                    if value > 0.0 {
+                        landmarks.get(p)[c] / value
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut points = vec![0.0; n * dimensions];
+    points
+        .par_chunks_mut(dimensions)
+        .enumerate()
+        .for_each(|(i, point)| {
+            for (x, row) in point.iter_mut().zip(inverse.iter()) {
+                *x = -0.5
+                    * row
+                        .iter()
+                        .zip(rows.iter().zip(means.iter()))
+                        .map(|(l, (r, m))| l * (r[i] * r[i] - m))
+                        .sum::<f32>();
+            }
+        });
+    PointList { dimensions, points }
+}
+
+/// Pivot MDS (Brandes & Pich, "Eigensolver Methods for Progressive Multidimensional
+/// Scaling of Large Data")
+///
+/// `rows` holds the distances from each of the `k` nodes of `pivots` to every node, which
+/// must be finite.
+/// The double-centered `n` x `k` matrix of squared distances is projected on its top right
+/// singular vectors, computed from a `k` x `k` matrix. The result is scaled to match the
+/// distances to the pivots in the least squares sense. Takes `O(nk)` time and memory.
+pub fn pivot_mds(pivots: &[usize], rows: &[Vec<f32>], dimensions: usize) -> PointList {
+    assert_eq!(pivots.len(), rows.len());
+    let k = rows.len();
+    let n = rows.first().map_or(0, |row| row.len());
+
+    // Double centering, the pivots as columns
+    let squared: Vec<Vec<f32>> = rows
+        .par_iter()
+        .map(|row| row.iter().map(|d| d * d).collect())
+        .collect();
+    let column_means: Vec<f32> = squared
+        .iter()
+        .map(|row| row.iter().sum::<f32>() / n as f32)
+        .collect();
+    let row_means: Vec<f32> = (0..n)
+        .into_par_iter()
+        .map(|i| squared.iter().map(|row| row[i]).sum::<f32>() / k as f32)
+        .collect();
+    let mean = column_means.iter().sum::<f32>() / k.max(1) as f32;
+    let centered: Vec<Vec<f32>> = squared
+        .par_iter()
+        .zip(column_means.par_iter())
+        .map(|(row, cm)| {
+            row.iter()
+                .zip(row_means.iter())
+                .map(|(d2, rm)| -0.5 * (d2 - rm - cm + mean))
+                .collect()
+        })
+        .collect();
+
+    // `CᵀC`, `k` x `k`
+    let gram: Vec<Vec<f32>> = centered
+        .par_iter()
+        .map(|a| {
+            centered
+                .iter()
+                .map(|b| a.iter().zip(b.iter()).map(|(x, y)| x * y).sum())
+                .collect()
+        })
+        .collect();
+    let eigen = top_eigenvectors(k, dimensions, |v, out| {
+        for (o, row) in out.iter_mut().zip(gram.iter()) {
+            *o = row.iter().zip(v.iter()).map(|(a, b)| a * b).sum();
+        }
+    });
+
+    let mut points = vec![0.0; n * dimensions];
+    points
+        .par_chunks_mut(dimensions)
+        .enumerate()
+        .for_each(|(i, point)| {
+            for (x, (value, v)) in point.iter_mut().zip(eigen.iter()) {
+                // `C v` is the left singular vector scaled by the singular value `σ`, and
+                // coordinates by `√σ`
+                let scale = value.max(f32::MIN_POSITIVE).powf(0.25);
+                *x = centered
+                    .iter()
+                    .zip(v.iter())
+                    .map(|(row, a)| row[i] * a)
+                    .sum::<f32>()
+                    / scale;
+            }
+        });
+    let mut points = PointList { dimensions, points };
+
+    // Scale minimizing the squared errors on the distances to the pivots
+    let (mut along, mut norm) = (0.0, 0.0);
+    for (row, &p) in rows.iter().zip(pivots.iter()) {
+        let q = points.get(p).to_vec();
+        for (i, d) in row.iter().enumerate() {
+            let dist = points
+                .get(i)
+                .iter()
+                .zip(q.iter())
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f32>()
+                .sqrt();
+            along += d * dist;
+            norm += dist * dist;
+        }
+    }
+    if norm > 0.0 {
+        let scale = along / norm;
+        points.points.iter_mut().for_each(|x| *x *= scale);
+    }
+    points
+}
+
+impl Layout {
+    /// Replaces the positions of the nodes by their `pivot_mds` with `pivots` pivots, e.g. as
+    /// a start for force layouts on large graphs
+    ///
+    /// Edges count as `scale` long. Fixed nodes keep their positions.
+    pub fn seed_pivot_mds(&mut self, pivots: usize, scale: f32) {
+        let n = self.masses.len();
+        if n == 0 {
+            return;
+        }
+        let adjacency = weighted_adjacency(n, &self.edges, None);
+        let (pivots, mut rows) = max_min_pivots(&adjacency, pivots.max(1), 0);
+        fill_unreachable(&mut rows);
+        let mut points = pivot_mds(&pivots, &rows, self.settings.dimensions);
+        points.points.iter_mut().for_each(|x| *x *= scale);
+        self.seed(&points);
+    }
+}
+
+/// Coordinates along each eigenvector, scaled by the square root of its eigenvalue
+pub(crate) fn from_eigenvectors(
+    n: usize,
+    dimensions: usize,
+    eigen: &[(f32, Vec<f32>)],
+) -> PointList {
+    let mut points = vec![0.0; n * dimensions];
+    for (k, (value, vector)) in eigen.iter().enumerate() {
+        let scale = value.max(0.0).sqrt();
+        for (i, x) in vector.iter().enumerate() {
+            points[i * dimensions + k] = scale * x;
+        }
+    }
+    PointList { dimensions, points }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The corners of a 3 x 4 rectangle, and their distances
+    fn rectangle() -> Vec<Vec<f32>> {
+        let corners = [[0.0f32, 0.0], [3.0, 0.0], [3.0, 4.0], [0.0, 4.0]];
+        corners
+            .iter()
+            .map(|a| {
+                corners
+                    .iter()
+                    .map(|b| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn assert_distances(points: &PointList, distances: &[Vec<f32>]) {
+        for (i, row) in distances.iter().enumerate() {
+            for (j, expected) in row.iter().enumerate() {
+                let (p, q) = (points.get(i), points.get(j));
+                let d = ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2)).sqrt();
+                assert!((d - expected).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_classical_mds() {
+        let distances = rectangle();
+        let points = classical_mds(&distances, 2);
+        assert_distances(&points, &distances);
+        // The long side along the first axis
+        assert!((points.get(0)[0] - points.get(3)[0]).abs() > 3.9);
+    }
+
+    #[test]
+    fn test_landmark_mds() {
+        // Three corners as landmarks place the fourth one
+        let distances = rectangle();
+        assert_distances(&landmark_mds(&[0, 1, 2], &distances[..3], 2), &distances);
+    }
+
+    #[test]
+    fn test_pivot_mds_exact() {
+        // All four corners as pivots
+        let distances = rectangle();
+        assert_distances(&pivot_mds(&[0, 1, 2, 3], &distances, 2), &distances);
+    }
+
+    #[test]
+    fn test_pivot_mds() {
+        // A 20 x 5 grid, with 6 pivots
+        let (w, h) = (20, 5);
+        let mut edges = Vec::new();
+        for i in 0..h {
+            for j in 0..w {
+                if j + 1 < w {
+                    edges.push((i * w + j, i * w + j + 1));
+                }
+                if i + 1 < h {
+                    edges.push((i * w + j, (i + 1) * w + j));
+                }
+            }
+        }
+        let mut layout = Layout::from_position_graph(
+            edges,
+            crate::util::Nodes::Degree(w * h),
+            vec![0.0; w * h * 2],
+            None,
+            Default::default(),
+        );
+        layout.fixed = Some((0..w * h).map(|n| n == 1).collect());
+        layout.seed_pivot_mds(6, 10.0);
+        let points = &layout.points;
+        assert_eq!(points.get(1), &[0.0, 0.0]);
+        let distance = |a: usize, b: usize| {
+            let (p, q) = (points.get(a), points.get(b));
+            ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2)).sqrt()
+        };
+        // Grid distances are not Euclidean: MDS widens the short side as well
+        assert!((distance(0, w - 1) - 190.0).abs() < 20.0);
+        assert!(distance(0, (h - 1) * w) < distance(0, w - 1) / 1.2);
+    }
+}
@@ -0,0 +1,88 @@
+use super::{
+    angular_resolution, count_crossings, edge_length_cv, neighborhood_preservation, stress,
+    sweep_pairs, Metrics, Overlaps, Segment,
+};
+use crate::util::{Edge, PointList};
+use crate::{GraphConfig, GraphEdge, GraphNode};
+
+use graphlib_rust::Graph;
+use std::collections::HashMap;
+
+/// Computes the metrics of a graph laid out by `layout`
+///
+/// Nodes are the rectangles of their `width` and `height` around `(x, y)`, and edges the
+/// polylines of their `points` when set. Subgraph nodes are ignored.
+pub fn dagre_metrics(graph: &Graph<GraphConfig, GraphNode, GraphEdge>) -> Metrics {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut points = PointList {
+        dimensions: 2,
+        points: Vec::new(),
+    };
+    let mut boxes = Vec::new();
+    for v in graph.nodes() {
+        if !graph.children(&v).is_empty() {
+            continue;
+        }
+        if let Some(node) = graph.node(&v) {
+            index.insert(v.clone(), boxes.len());
+            points.points.push(node.x);
+            points.points.push(node.y);
+            boxes.push((node.width, node.height));
+        }
+    }
+
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut segments = Vec::new();
+    for e in graph.edges() {
+        let (n1, n2) = match (index.get(&e.v), index.get(&e.w)) {
+            (Some(n1), Some(n2)) if n1 != n2 => (*n1, *n2),
+            _ => continue,
+        };
+        edges.push((n1, n2));
+
+        let (p1, p2) = (points.get(n1), points.get(n2));
+        let mut polyline = vec![[p1[0], p1[1]]];
+        if let Some(edge_points) = graph
+            .edge_with_obj(&e)
+            .and_then(|edge| edge.points.as_ref())
+        {
+            polyline.extend(edge_points.iter().map(|p| [p.x, p.y]));
+        }
+        polyline.push([p2[0], p2[1]]);
+        segments.extend(polyline.windows(2).map(|w| Segment {
+            a: w[0],
+            b: w[1],
+            edge: (n1, n2),
+        }));
+    }
+
+    Metrics {
+        crossings: count_crossings(&segments),
+        overlaps: box_overlaps(&points, &boxes),
+        stress: stress(&points, &edges),
+        edge_length_cv: edge_length_cv(&points, &edges),
+        angular_resolution: angular_resolution(&points, &edges),
+        neighborhood_preservation: neighborhood_preservation(&points, &edges),
+    }
+}
+
+/// Overlaps between the rectangles `(width, height)` centered on `points`
+fn box_overlaps(points: &PointList, boxes: &[(f32, f32)]) -> Overlaps {
+    let ranges: Vec<(f32, f32)> = points
+        .iter()
+        .zip(boxes.iter())
+        .map(|(p, (width, _))| (p[0] - width / 2.0, p[0] + width / 2.0))
+        .collect();
+    let mut overlaps = Overlaps::default();
+    sweep_pairs(&ranges, |n1, n2| {
+        let (p1, p2) = (points.get(n1), points.get(n2));
+        let (b1, b2) = (boxes[n1], boxes[n2]);
+        let ox = (b1.0 + b2.0) / 2.0 - (p1[0] - p2[0]).abs();
+        let oy = (b1.1 + b2.1) / 2.0 - (p1[1] - p2[1]).abs();
+        if ox > 0.0 && oy > 0.0 {
+            overlaps.count += 1;
+            overlaps.area += ox.min(b1.0).min(b2.0) * oy.min(b1.1).min(b2.1);
+        }
+    });
+    overlaps
+}
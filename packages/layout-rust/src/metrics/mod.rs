@@ -0,0 +1,399 @@
+//! Quality metrics of a layout.
+//!
+//! Crossings, overlaps and angular resolution are computed in the plane of the first two
+//! coordinates; the other metrics use all of them.
+
+mod dagre;
+
+pub use self::dagre::dagre_metrics;
+
+use crate::util::{adjacency, bfs_distances, Edge, PointList};
+
+use std::f32::consts::PI;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub crossings: usize,
+    pub overlaps: Overlaps,
+    pub stress: f32,
+    pub edge_length_cv: f32,
+    pub angular_resolution: f32,
+    pub neighborhood_preservation: f32,
+}
+
+/// Overlapping pairs of nodes, and the total area of their intersections
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overlaps {
+    pub count: usize,
+    pub area: f32,
+}
+
+impl Metrics {
+    /// Computes all the metrics. Without `sizes`, nodes are points and never overlap.
+    pub fn new(points: &PointList, edges: &[Edge], sizes: Option<&[f32]>) -> Self {
+        Self {
+            crossings: edge_crossings(points, edges),
+            overlaps: sizes.map_or_else(Overlaps::default, |sizes| node_overlaps(points, sizes)),
+            stress: stress(points, edges),
+            edge_length_cv: edge_length_cv(points, edges),
+            angular_resolution: angular_resolution(points, edges),
+            neighborhood_preservation: neighborhood_preservation(points, edges),
+        }
+    }
+}
+
+fn nb_nodes(points: &PointList) -> usize {
+    points.points.len() / points.dimensions
+}
+
+fn distance(p1: &[f32], p2: &[f32]) -> f32 {
+    p1.iter()
+        .zip(p2.iter())
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Calls `f` on every pair of intervals `[min, max]` that intersect, sweeping them by `min`
+pub(crate) fn sweep_pairs(ranges: &[(f32, f32)], mut f: impl FnMut(usize, usize)) {
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by(|a, b| ranges[*a].0.total_cmp(&ranges[*b].0));
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let min = ranges[i].0;
+        active.retain(|j| ranges[*j].1 >= min);
+        for &j in active.iter() {
+            f(j, i);
+        }
+        active.push(i);
+    }
+}
+
+/// A straight piece of the drawing of an edge
+pub(crate) struct Segment {
+    pub a: [f32; 2],
+    pub b: [f32; 2],
+    /// End nodes of the edge the segment belongs to
+    pub edge: Edge,
+}
+
+fn orientation(p: [f32; 2], q: [f32; 2], r: [f32; 2]) -> f32 {
+    (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])
+}
+
+fn segments_cross(s1: &Segment, s2: &Segment) -> bool {
+    let o1 = orientation(s1.a, s1.b, s2.a);
+    let o2 = orientation(s1.a, s1.b, s2.b);
+    let o3 = orientation(s2.a, s2.b, s1.a);
+    let o4 = orientation(s2.a, s2.b, s1.b);
+    o1 * o2 < 0.0 && o3 * o4 < 0.0
+}
+
+/// Whether the bounding boxes of two segments intersect along the y axis
+fn y_extents_overlap(s1: &Segment, s2: &Segment) -> bool {
+    s1.a[1].min(s1.b[1]) <= s2.a[1].max(s2.b[1]) && s2.a[1].min(s2.b[1]) <= s1.a[1].max(s1.b[1])
+}
+
+/// Counts the proper crossings between segments of edges that share no node
+///
+/// Segments are swept along x while the active ones are kept in horizontal bands, so that
+/// only the pairs whose bounding boxes intersect are tested. Not a Bentley-Ottmann sweep:
+/// it stays quadratic when all the segments overlap each other.
+pub(crate) fn count_crossings(segments: &[Segment]) -> usize {
+    let x_ranges: Vec<(f32, f32)> = segments
+        .iter()
+        .map(|s| (s.a[0].min(s.b[0]), s.a[0].max(s.b[0])))
+        .collect();
+    let y_ranges: Vec<(f32, f32)> = segments
+        .iter()
+        .map(|s| (s.a[1].min(s.b[1]), s.a[1].max(s.b[1])))
+        .collect();
+    let (bottom, top) = y_ranges.iter().fold(
+        (f32::INFINITY, f32::NEG_INFINITY),
+        |(bottom, top), (min, max)| (bottom.min(*min), top.max(*max)),
+    );
+    let nb_bands = ((segments.len() as f32).sqrt() as usize).max(1);
+    let height = (top - bottom) / nb_bands as f32;
+    let band = |y: f32| {
+        if height > 0.0 {
+            (((y - bottom) / height) as usize).min(nb_bands - 1)
+        } else {
+            0
+        }
+    };
+
+    let mut order: Vec<usize> = (0..segments.len()).collect();
+    order.sort_by(|a, b| x_ranges[*a].0.total_cmp(&x_ranges[*b].0));
+    let mut bands: Vec<Vec<usize>> = vec![Vec::new(); nb_bands];
+    let mut crossings = 0;
+    for i in order {
+        let s1 = &segments[i];
+        let first = band(y_ranges[i].0);
+        for (b, active) in bands
+            .iter_mut()
+            .enumerate()
+            .take(band(y_ranges[i].1) + 1)
+            .skip(first)
+        {
+            active.retain(|j| x_ranges[*j].1 >= x_ranges[i].0);
+            for &j in active.iter() {
+                // A pair meets in every band both segments span, it is tested in the first
+                if b != first.max(band(y_ranges[j].0)) {
+                    continue;
+                }
+                let s2 = &segments[j];
+                let (e1, e2) = (s1.edge, s2.edge);
+                let adjacent = e1.0 == e2.0 || e1.0 == e2.1 || e1.1 == e2.0 || e1.1 == e2.1;
+                if !adjacent && y_extents_overlap(s1, s2) && segments_cross(s1, s2) {
+                    crossings += 1;
+                }
+            }
+            active.push(i);
+        }
+    }
+    crossings
+}
+
+/// Number of pairs of straight edges crossing each other
+///
+/// Edges sharing a node are not considered. Only edges whose bounding boxes intersect are
+/// tested, see `count_crossings`.
+pub fn edge_crossings(points: &PointList, edges: &[Edge]) -> usize {
+    let segments: Vec<Segment> = edges
+        .iter()
+        .map(|&(n1, n2)| {
+            let (p1, p2) = (points.get(n1), points.get(n2));
+            Segment {
+                a: [p1[0], p1[1]],
+                b: [p2[0], p2[1]],
+                edge: (n1, n2),
+            }
+        })
+        .collect();
+    count_crossings(&segments)
+}
+
+/// Area of the intersection of two discs
+fn lens_area(d: f32, r1: f32, r2: f32) -> f32 {
+    if d >= r1 + r2 {
+        0.0
+    } else if d <= (r1 - r2).abs() {
+        PI * r1.min(r2).powi(2)
+    } else {
+        let a1 = ((d * d + r1 * r1 - r2 * r2) / (2.0 * d * r1)).clamp(-1.0, 1.0);
+        let a2 = ((d * d + r2 * r2 - r1 * r1) / (2.0 * d * r2)).clamp(-1.0, 1.0);
+        let k = ((-d + r1 + r2) * (d + r1 - r2) * (d - r1 + r2) * (d + r1 + r2)).max(0.0);
+        r1 * r1 * a1.acos() + r2 * r2 * a2.acos() - k.sqrt() / 2.0
+    }
+}
+
+/// Overlaps between nodes drawn as discs of diameter `sizes`, as `Layout::sizes`
+pub fn node_overlaps(points: &PointList, sizes: &[f32]) -> Overlaps {
+    assert_eq!(sizes.len(), nb_nodes(points));
+    let ranges: Vec<(f32, f32)> = points
+        .iter()
+        .zip(sizes.iter())
+        .map(|(p, size)| (p[0] - size / 2.0, p[0] + size / 2.0))
+        .collect();
+    let mut overlaps = Overlaps::default();
+    sweep_pairs(&ranges, |n1, n2| {
+        let (p1, p2) = (points.get(n1), points.get(n2));
+        let d = distance(&p1[..2], &p2[..2]);
+        let area = lens_area(d, sizes[n1] / 2.0, sizes[n2] / 2.0);
+        if area > 0.0 {
+            overlaps.count += 1;
+            overlaps.area += area;
+        }
+    });
+    overlaps
+}
+
+/// Normalized stress against graph-theoretic distances
+///
+/// Layout distances are first scaled by the factor minimizing the stress, so that the
+/// metric does not depend on the unit of the layout. Returns the mean over reachable pairs
+/// of `((s * |pi - pj| - dij) / dij)²`.
+pub fn stress(points: &PointList, edges: &[Edge]) -> f32 {
+    let n = nb_nodes(points);
+    let adjacency = adjacency(n, edges);
+    // With r = |pi - pj| / dij, the sum of (s * r - 1)² is s² * Σr² - 2s * Σr + count
+    let (mut sum, mut sum2, mut count) = (0.0f64, 0.0f64, 0usize);
+    for n1 in 0..n {
+        let distances = bfs_distances(&adjacency, n1);
+        for (n2, &d) in distances.iter().enumerate().skip(n1 + 1) {
+            if d != usize::MAX {
+                let r = (distance(points.get(n1), points.get(n2)) / d as f32) as f64;
+                sum += r;
+                sum2 += r * r;
+                count += 1;
+            }
+        }
+    }
+    if count == 0 {
+        return 0.0;
+    }
+
+    // Minimized by s = Σr / Σr²
+    let stress = if sum2 > 0.0 {
+        count as f64 - sum * sum / sum2
+    } else {
+        count as f64
+    };
+    (stress.max(0.0) / count as f64) as f32
+}
+
+/// Coefficient of variation (standard deviation over mean) of the edge lengths
+pub fn edge_length_cv(points: &PointList, edges: &[Edge]) -> f32 {
+    let lengths: Vec<f32> = edges
+        .iter()
+        .map(|&(n1, n2)| distance(points.get(n1), points.get(n2)))
+        .collect();
+    if lengths.is_empty() {
+        return 0.0;
+    }
+    let mean = lengths.iter().sum::<f32>() / lengths.len() as f32;
+    if mean == 0.0 {
+        return 0.0;
+    }
+    let variance = lengths.iter().map(|l| (l - mean).powi(2)).sum::<f32>() / lengths.len() as f32;
+    variance.sqrt() / mean
+}
+
+/// Smallest angle, in radians, between two edges of a same node
+///
+/// `2π` when no node has two edges.
+pub fn angular_resolution(points: &PointList, edges: &[Edge]) -> f32 {
+    let adjacency = adjacency(nb_nodes(points), edges);
+    let mut resolution = 2.0 * PI;
+    let mut angles = Vec::new();
+    for (n1, neighbors) in adjacency.iter().enumerate() {
+        if neighbors.len() < 2 {
+            continue;
+        }
+        let p1 = points.get(n1);
+        angles.clear();
+        angles.extend(neighbors.iter().map(|&n2| {
+            let p2 = points.get(n2);
+            (p2[1] - p1[1]).atan2(p2[0] - p1[0])
+        }));
+        angles.sort_by(f32::total_cmp);
+        for w in angles.windows(2) {
+            resolution = resolution.min(w[1] - w[0]);
+        }
+        resolution = resolution.min(angles[0] + 2.0 * PI - angles[angles.len() - 1]);
+    }
+    resolution
+}
+
+/// Mean, over nodes with edges, of the Jaccard similarity between the neighbours of a node
+/// and as many of its nearest nodes in the layout
+pub fn neighborhood_preservation(points: &PointList, edges: &[Edge]) -> f32 {
+    let n = nb_nodes(points);
+    let mut adjacency = adjacency(n, edges);
+    let mut total = 0.0;
+    let mut count = 0;
+    let mut nearest: Vec<(f32, usize)> = Vec::with_capacity(n);
+    for (n1, neighbors) in adjacency.iter_mut().enumerate() {
+        neighbors.retain(|n2| *n2 != n1);
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        let k = neighbors.len();
+        if k == 0 {
+            continue;
+        }
+
+        let p1 = points.get(n1);
+        nearest.clear();
+        nearest.extend(
+            (0..n)
+                .filter(|n2| *n2 != n1)
+                .map(|n2| (distance(p1, points.get(n2)), n2)),
+        );
+        nearest.select_nth_unstable_by(k - 1, |a, b| a.0.total_cmp(&b.0));
+        let shared = nearest[..k]
+            .iter()
+            .filter(|(_, n2)| neighbors.binary_search(n2).is_ok())
+            .count();
+        total += shared as f32 / (2 * k - shared) as f32;
+        count += 1;
+    }
+    if count == 0 {
+        1.0
+    } else {
+        total / count as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::random_points;
+
+    fn points(points: Vec<f32>) -> PointList {
+        PointList {
+            dimensions: 2,
+            points,
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        // A unit square and its diagonals
+        let square = points(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]);
+        let edges = vec![(0, 1), (1, 2), (2, 3), (3, 0), (0, 2), (1, 3)];
+        assert_eq!(edge_crossings(&square, &edges), 1);
+        assert!((angular_resolution(&square, &edges) - PI / 4.0).abs() < 1e-5);
+        assert!(edge_length_cv(&square, &edges[..4]) < 1e-6);
+
+        let overlaps = node_overlaps(&square, &[2.0, 0.5, 0.5, 0.5]);
+        assert_eq!(overlaps.count, 2);
+        assert!((overlaps.area - 2.0 * lens_area(1.0, 1.0, 0.25)).abs() < 1e-5);
+        assert!((lens_area(0.0, 1.0, 2.0) - PI).abs() < 1e-5);
+
+        // A path drawn straight, at any scale
+        let path = points(vec![0.0, 0.0, 3.0, 0.0, 6.0, 0.0, 9.0, 0.0]);
+        let edges = vec![(0, 1), (1, 2), (2, 3)];
+        assert!(stress(&path, &edges) < 1e-6);
+        assert_eq!(neighborhood_preservation(&path, &edges), 1.0);
+        let folded = points(vec![0.0, 0.0, 3.0, 0.0, 3.0, 3.0, 0.0, 1.0]);
+        assert!(stress(&folded, &edges) > 0.01);
+        assert!(neighborhood_preservation(&folded, &edges) < 1.0);
+    }
+
+    #[test]
+    fn test_edge_crossings_grid() {
+        // 3 horizontal edges across 4 vertical ones, and a short edge crossing none
+        let mut coordinates = Vec::new();
+        for y in 0..3 {
+            coordinates.extend_from_slice(&[0.0, y as f32, 5.0, y as f32]);
+        }
+        for x in 1..5 {
+            coordinates.extend_from_slice(&[x as f32, -1.0, x as f32, 3.0]);
+        }
+        coordinates.extend_from_slice(&[1.2, 2.5, 1.8, 2.5]);
+        let edges: Vec<Edge> = (0..8).map(|e| (2 * e, 2 * e + 1)).collect();
+        assert_eq!(edge_crossings(&points(coordinates), &edges), 12);
+    }
+
+    #[test]
+    fn test_edge_crossings_all_pairs() {
+        let points = random_points(60, 2, 100.0, 5);
+        let edges: Vec<Edge> = (0..60).map(|n| (n, (n * 7 + 3) % 60)).collect();
+        let mut expected = 0;
+        for (i, &e1) in edges.iter().enumerate() {
+            for &e2 in edges[i + 1..].iter() {
+                let adjacent = e1.0 == e2.0 || e1.0 == e2.1 || e1.1 == e2.0 || e1.1 == e2.1;
+                let segment = |(n1, n2): Edge| Segment {
+                    a: [points.get(n1)[0], points.get(n1)[1]],
+                    b: [points.get(n2)[0], points.get(n2)[1]],
+                    edge: (n1, n2),
+                };
+                if !adjacent && segments_cross(&segment(e1), &segment(e2)) {
+                    expected += 1;
+                }
+            }
+        }
+        assert!(expected > 0);
+        assert_eq!(edge_crossings(&points, &edges), expected);
+    }
+}
@@ -0,0 +1,396 @@
+//! Multilevel layout, after Hu, "Efficient, High-Quality Force-Directed Graph Drawing", and
+//! Hachul & Jünger, "Drawing Large Graphs with a Potential-Field-Based Multilevel Algorithm".
+//!
+//! The graph is coarsened repeatedly by collapsing groups of adjacent nodes. The coarsest
+//! graph is laid out first; each level then starts from the positions of the coarser one
+//! and is refined with a few iterations. Every level runs the same `Settings`, hence the
+//! same kernels.
+
+use crate::layout::Layout;
+use crate::run::{RunOptions, RunReport};
+use crate::util::{Edge, Nodes};
+
+use std::collections::HashMap;
+
+/// How groups of nodes are collapsed into a coarser node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coarsening {
+    /// Heavy edge matching: pairs of adjacent nodes, preferring heavy edges between light
+    /// nodes. Halves the graph at best.
+    Matching,
+    /// A maximal independent set, each other node joining an adjacent node of the set.
+    /// Coarsens stars and other graphs with hubs faster than matching.
+    IndependentSet,
+}
+
+#[derive(Clone, Debug)]
+pub struct MultilevelOptions {
+    pub coarsening: Coarsening,
+    /// Stop coarsening at this number of nodes
+    pub min_nodes: usize,
+    /// Maximum number of levels coarser than the graph
+    pub max_levels: usize,
+    /// Stop coarsening once a level keeps more than this ratio of the nodes of the finer one
+    pub min_reduction: f32,
+    /// Iterations of the coarsest level
+    pub coarsest_iterations: usize,
+    /// Iterations of each other level
+    pub refine_iterations: usize,
+}
+
+impl Default for MultilevelOptions {
+    fn default() -> Self {
+        Self {
+            coarsening: Coarsening::Matching,
+            min_nodes: 100,
+            max_levels: 16,
+            min_reduction: 0.9,
+            coarsest_iterations: 500,
+            refine_iterations: 100,
+        }
+    }
+}
+
+/// A coarser graph, and the coarse node of each node of the finer one
+struct Level {
+    parent: Vec<usize>,
+    layout: Layout,
+}
+
+/// Neighbours of each node and the weight of the edge to them
+fn weighted_adjacency(layout: &Layout) -> Vec<Vec<(usize, f32)>> {
+    let mut adjacency = vec![Vec::new(); layout.masses.len()];
+    for (e, &(n1, n2)) in layout.edges.iter().enumerate() {
+        let w = layout.weights.as_ref().map_or(1.0, |weights| weights[e]);
+        adjacency[n1].push((n2, w));
+        adjacency[n2].push((n1, w));
+    }
+    adjacency
+}
+
+/// Coarse node of each node, and the number of coarse nodes
+fn heavy_edge_matching(layout: &Layout) -> (Vec<usize>, usize) {
+    let adjacency = weighted_adjacency(layout);
+    let n = adjacency.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&v| adjacency[v].len());
+
+    let mut parent = vec![usize::MAX; n];
+    let mut nb = 0;
+    for v in order {
+        if parent[v] != usize::MAX {
+            continue;
+        }
+        parent[v] = nb;
+        let mv = layout.masses[v] + 1.0;
+        let mut best: Option<(usize, f32)> = None;
+        for &(u, w) in adjacency[v].iter() {
+            if parent[u] != usize::MAX {
+                continue;
+            }
+            let score = w / (mv * (layout.masses[u] + 1.0));
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((u, score));
+            }
+        }
+        if let Some((u, _)) = best {
+            parent[u] = nb;
+        }
+        nb += 1;
+    }
+    (parent, nb)
+}
+
+/// Coarse node of each node, and the number of coarse nodes
+fn independent_set(layout: &Layout) -> (Vec<usize>, usize) {
+    let adjacency = weighted_adjacency(layout);
+    let n = adjacency.len();
+    // Hubs first, so that their neighbours collapse into them
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&v| std::cmp::Reverse(adjacency[v].len()));
+
+    let mut parent = vec![usize::MAX; n];
+    let mut selected = vec![false; n];
+    let mut nb = 0;
+    for v in order {
+        if adjacency[v].iter().all(|&(u, _)| !selected[u]) {
+            selected[v] = true;
+            parent[v] = nb;
+            nb += 1;
+        }
+    }
+    // The set being maximal, every other node has a neighbour in it
+    for v in 0..n {
+        if !selected[v] {
+            let mut best: Option<(usize, f32)> = None;
+            for &(u, w) in adjacency[v].iter() {
+                if selected[u] && best.is_none_or(|(_, b)| w > b) {
+                    best = Some((u, w));
+                }
+            }
+            parent[v] = parent[best.unwrap().0];
+        }
+    }
+    (parent, nb)
+}
+
+/// Collapses the nodes of `layout` into their `parent`
+///
+/// A coarse node weighs as much as its nodes together, sits at their barycenter and is fixed
+/// if any of them is. Edges between the same coarse nodes are merged, adding their weights.
+fn coarsen(layout: &Layout, parent: Vec<usize>, nb: usize) -> Level {
+    let d = layout.settings.dimensions;
+
+    let mut weight = vec![0.0f32; nb];
+    let mut positions = vec![0.0f32; nb * d];
+    let mut fixed = vec![false; nb];
+    for (v, &c) in parent.iter().enumerate() {
+        let m = layout.masses[v] + 1.0;
+        weight[c] += m;
+        for (x, p) in positions[c * d..(c + 1) * d]
+            .iter_mut()
+            .zip(layout.points.get(v))
+        {
+            *x += m * p;
+        }
+        if let Some(f) = &layout.fixed {
+            fixed[c] |= f[v];
+        }
+    }
+    for (c, w) in weight.iter().enumerate() {
+        for x in positions[c * d..(c + 1) * d].iter_mut() {
+            *x /= w;
+        }
+    }
+
+    let mut index: HashMap<Edge, usize> = HashMap::new();
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut weights: Vec<f32> = Vec::new();
+    for (e, &(n1, n2)) in layout.edges.iter().enumerate() {
+        let (c1, c2) = (parent[n1], parent[n2]);
+        if c1 == c2 {
+            continue;
+        }
+        let w = layout.weights.as_ref().map_or(1.0, |weights| weights[e]);
+        let key = (c1.min(c2), c1.max(c2));
+        match index.get(&key) {
+            Some(&i) => weights[i] += w,
+            None => {
+                index.insert(key, edges.len());
+                edges.push(key);
+                weights.push(w);
+            }
+        }
+    }
+
+    let mut coarse = Layout::from_position_graph(
+        edges,
+        Nodes::Mass(weight.into_iter().map(|w| w - 1.0).collect()),
+        positions,
+        Some(weights),
+        layout.settings.clone(),
+    );
+    coarse.fixed = layout.fixed.as_ref().map(|_| fixed);
+    Level {
+        parent,
+        layout: coarse,
+    }
+}
+
+/// Places the nodes of `fine` around their coarse node
+///
+/// Each node keeps the direction it had from the barycenter of its group, at a tenth of the
+/// mean edge length of the coarse layout. Fixed nodes do not move.
+fn prolong(fine: &mut Layout, level: &Level) {
+    let d = fine.settings.dimensions;
+    let coarse = &level.layout;
+    let length = if coarse.edges.is_empty() {
+        1.0
+    } else {
+        coarse
+            .edges
+            .iter()
+            .map(|&(n1, n2)| {
+                coarse
+                    .points
+                    .get(n1)
+                    .iter()
+                    .zip(coarse.points.get(n2))
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f32>()
+                    .sqrt()
+            })
+            .sum::<f32>()
+            / coarse.edges.len() as f32
+    };
+
+    // Barycenters of the groups before the coarse layout, as computed by `coarsen`
+    let mut weight = vec![0.0f32; coarse.masses.len()];
+    let mut barycenters = vec![0.0f32; coarse.masses.len() * d];
+    for (v, &c) in level.parent.iter().enumerate() {
+        let m = fine.masses[v] + 1.0;
+        weight[c] += m;
+        for (x, p) in barycenters[c * d..(c + 1) * d]
+            .iter_mut()
+            .zip(fine.points.get(v))
+        {
+            *x += m * p;
+        }
+    }
+
+    let fixed = fine.fixed.as_deref();
+    for (v, (&c, pos)) in level.parent.iter().zip(fine.points.iter_mut()).enumerate() {
+        if fixed.is_some_and(|fixed| fixed[v]) {
+            continue;
+        }
+        let mut offset: Vec<f32> = pos
+            .iter()
+            .zip(barycenters[c * d..(c + 1) * d].iter())
+            .map(|(p, b)| p - b / weight[c])
+            .collect();
+        let norm = offset.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            offset.iter_mut().for_each(|x| *x /= norm);
+        } else {
+            offset[v % d] = [1.0, -1.0][(v / d) % 2];
+        }
+        for ((p, c), o) in pos.iter_mut().zip(coarse.points.get(c)).zip(offset) {
+            *p = c + 0.1 * length * o;
+        }
+    }
+}
+
+impl Layout {
+    /// Lays out the graph level by level, from the coarsest one
+    ///
+    /// Returns the report of each level, the coarsest first and this layout last.
+    pub fn run_multilevel(&mut self, options: &MultilevelOptions) -> Vec<RunReport> {
+        let mut levels: Vec<Level> = Vec::new();
+        loop {
+            let finer = levels.last().map_or(&*self, |level| &level.layout);
+            let n = finer.masses.len();
+            if n <= options.min_nodes || levels.len() >= options.max_levels {
+                break;
+            }
+            let (parent, nb) = match options.coarsening {
+                Coarsening::Matching => heavy_edge_matching(finer),
+                Coarsening::IndependentSet => independent_set(finer),
+            };
+            if nb as f32 > options.min_reduction * n as f32 {
+                break;
+            }
+            let level = coarsen(finer, parent, nb);
+            levels.push(level);
+        }
+
+        let run = |layout: &mut Layout, max_iterations| {
+            layout.run(RunOptions {
+                max_iterations,
+                ..Default::default()
+            })
+        };
+        let mut reports = Vec::with_capacity(levels.len() + 1);
+        let mut iterations = options.coarsest_iterations;
+        while let Some(level) = levels.pop() {
+            let mut level = level;
+            reports.push(run(&mut level.layout, iterations));
+            iterations = options.refine_iterations;
+            match levels.last_mut() {
+                Some(finer) => prolong(&mut finer.layout, &level),
+                None => prolong(self, &level),
+            }
+        }
+        reports.push(run(self, iterations));
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Settings;
+    use crate::util::random_points;
+
+    /// A `side` x `side` grid
+    fn grid(side: usize) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for i in 0..side {
+            for j in 0..side {
+                let v = i * side + j;
+                if j + 1 < side {
+                    edges.push((v, v + 1));
+                }
+                if i + 1 < side {
+                    edges.push((v, v + side));
+                }
+            }
+        }
+        edges
+    }
+
+    fn layout(edges: Vec<Edge>, n: usize) -> Layout {
+        Layout::from_position_graph(
+            edges,
+            Nodes::Degree(n),
+            random_points(n, 2, 100.0, n as u64).points,
+            None,
+            Settings {
+                chunk_size: None,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_coarsen_matching() {
+        let fine = layout(grid(4), 16);
+        let (parent, nb) = heavy_edge_matching(&fine);
+        assert!(nb <= 10);
+        let level = coarsen(&fine, parent, nb);
+        let total = |l: &Layout| l.masses.iter().map(|m| m + 1.0).sum::<f32>();
+        assert!((total(&fine) - total(&level.layout)).abs() < 1e-3);
+        // Weights add up, less the edges collapsed
+        let collapsed = fine
+            .edges
+            .iter()
+            .filter(|(n1, n2)| level.parent[*n1] == level.parent[*n2])
+            .count();
+        let kept: f32 = level.layout.weights.as_ref().unwrap().iter().sum();
+        assert_eq!(kept as usize, fine.edges.len() - collapsed);
+    }
+
+    #[test]
+    fn test_independent_set_star() {
+        // A star collapses into its hub
+        let star = layout((1..20).map(|v| (0, v)).collect(), 20);
+        let (parent, nb) = independent_set(&star);
+        assert_eq!(nb, 1);
+        assert!(parent.iter().all(|&c| c == 0));
+    }
+
+    fn run_multilevel(coarsening: Coarsening) {
+        let side = 12;
+        let mut layout = layout(grid(side), side * side);
+        layout.fixed = Some((0..side * side).map(|v| v == 0).collect());
+        let anchor = layout.points.get(0).to_vec();
+        let reports = layout.run_multilevel(&MultilevelOptions {
+            coarsening,
+            min_nodes: 10,
+            refine_iterations: 50,
+            ..Default::default()
+        });
+        assert!(reports.len() > 2);
+        assert_eq!(layout.points.get(0), &anchor[..]);
+        assert!(layout.points.points.iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn test_run_multilevel_matching() {
+        run_multilevel(Coarsening::Matching);
+    }
+
+    #[test]
+    fn test_run_multilevel_independent_set() {
+        run_multilevel(Coarsening::IndependentSet);
+    }
+}
@@ -0,0 +1,287 @@
+//! Node overlap removal, after Dwyer, Marriott & Stuckey, "Fast Node Overlap Removal".
+//!
+//! Separation constraints are generated by a scan line, first horizontally between nodes
+//! that overlap vertically, then vertically between the nodes still overlapping. Each pass
+//! satisfies its constraints with the `satisfy` step of the VPSC solver, which keeps nodes
+//! close to their positions in the least squares sense, without the refinement making it
+//! optimal. Constraints always push nodes apart along their current order, which is
+//! therefore preserved.
+
+use crate::util::PointList;
+
+/// `x[right] - x[left] >= gap`
+struct Constraint {
+    left: usize,
+    right: usize,
+    gap: f32,
+}
+
+/// A rectangle of half-extents `(hw, hh)` around `(x, y)`
+#[derive(Clone, Copy)]
+struct Rect {
+    x: f32,
+    y: f32,
+    hw: f32,
+    hh: f32,
+}
+
+impl Rect {
+    fn overlap_x(&self, other: &Rect) -> f32 {
+        self.hw + other.hw - (self.x - other.x).abs()
+    }
+
+    fn overlap_y(&self, other: &Rect) -> f32 {
+        self.hh + other.hh - (self.y - other.y).abs()
+    }
+}
+
+/// Moves nodes so that the rectangles `(width, height)` centered on them, in the plane of
+/// the first two coordinates, are at least `padding` apart
+pub fn remove_overlaps(points: &mut PointList, sizes: &[(f32, f32)], padding: f32) {
+    let n = points.points.len() / points.dimensions;
+    assert_eq!(sizes.len(), n);
+    let mut rects: Vec<Rect> = points
+        .iter()
+        .zip(sizes.iter())
+        .map(|(p, (w, h))| Rect {
+            x: p[0],
+            y: p[1],
+            hw: (w + padding) / 2.0,
+            hh: (h + padding) / 2.0,
+        })
+        .collect();
+
+    let constraints = scan_constraints(&rects, true);
+    let desired: Vec<f32> = rects.iter().map(|r| r.x).collect();
+    let order = sorted_by(&desired);
+    for (r, x) in rects
+        .iter_mut()
+        .zip(satisfy(&desired, &order, &constraints))
+    {
+        r.x = x;
+    }
+
+    let constraints = scan_constraints(&rects, false);
+    let desired: Vec<f32> = rects.iter().map(|r| r.y).collect();
+    let order = sorted_by(&desired);
+    for (r, y) in rects
+        .iter_mut()
+        .zip(satisfy(&desired, &order, &constraints))
+    {
+        r.y = y;
+    }
+
+    for (p, r) in points.iter_mut().zip(rects.iter()) {
+        p[0] = r.x;
+        p[1] = r.y;
+    }
+}
+
+fn sorted_by(values: &[f32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]).then(a.cmp(b)));
+    order
+}
+
+/// Generates the constraints separating rectangles along x (`horizontal`) or y, sweeping a
+/// scan line across the other axis
+fn scan_constraints(rects: &[Rect], horizontal: bool) -> Vec<Constraint> {
+    let n = rects.len();
+    // Position along the separated axis, and extent along the swept one
+    let (pos, lo, hi): (Vec<f32>, Vec<f32>, Vec<f32>) = if horizontal {
+        (
+            rects.iter().map(|r| r.x).collect(),
+            rects.iter().map(|r| r.y - r.hh).collect(),
+            rects.iter().map(|r| r.y + r.hh).collect(),
+        )
+    } else {
+        (
+            rects.iter().map(|r| r.y).collect(),
+            rects.iter().map(|r| r.x - r.hw).collect(),
+            rects.iter().map(|r| r.x + r.hw).collect(),
+        )
+    };
+
+    // Events `(coordinate, closing, node)`, openings first on ties
+    let mut events: Vec<(f32, bool, usize)> = (0..n)
+        .flat_map(|v| [(lo[v], false, v), (hi[v], true, v)])
+        .collect();
+    events.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let key = |v: usize| (pos[v], v);
+    let mut scan: Vec<usize> = Vec::new();
+    let mut prev: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut next: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut constraints = Vec::new();
+
+    for (_, closing, v) in events {
+        let at = scan.partition_point(|&u| key(u) < key(v));
+        if !closing {
+            scan.insert(at, v);
+            if horizontal {
+                // Neighbours on each side, up to the first one not overlapping along x,
+                // unless they are better separated vertically
+                for u in scan[..at].iter().rev().cloned() {
+                    let ox = rects[u].overlap_x(&rects[v]);
+                    if ox <= 0.0 || ox <= rects[u].overlap_y(&rects[v]) {
+                        prev[v].push(u);
+                        next[u].push(v);
+                    }
+                    if ox <= 0.0 {
+                        break;
+                    }
+                }
+                for u in scan[at + 1..].iter().cloned() {
+                    let ox = rects[u].overlap_x(&rects[v]);
+                    if ox <= 0.0 || ox <= rects[u].overlap_y(&rects[v]) {
+                        next[v].push(u);
+                        prev[u].push(v);
+                    }
+                    if ox <= 0.0 {
+                        break;
+                    }
+                }
+            } else {
+                if at > 0 {
+                    let u = scan[at - 1];
+                    prev[v].push(u);
+                    next[u].push(v);
+                }
+                if at + 1 < scan.len() {
+                    let u = scan[at + 1];
+                    next[v].push(u);
+                    prev[u].push(v);
+                }
+            }
+        } else {
+            let gap = |u: usize| {
+                if horizontal {
+                    rects[u].hw + rects[v].hw
+                } else {
+                    rects[u].hh + rects[v].hh
+                }
+            };
+            for u in std::mem::take(&mut prev[v]) {
+                constraints.push(Constraint {
+                    left: u,
+                    right: v,
+                    gap: gap(u),
+                });
+                next[u].retain(|w| *w != v);
+            }
+            for u in std::mem::take(&mut next[v]) {
+                constraints.push(Constraint {
+                    left: v,
+                    right: u,
+                    gap: gap(u),
+                });
+                prev[u].retain(|w| *w != v);
+            }
+            scan.remove(at);
+        }
+    }
+    constraints
+}
+
+/// Positions closest to `desired` satisfying `constraints`, merging variables into blocks
+/// moving together as constraints become tight
+///
+/// `order` must list variables so that each constraint goes forward, which holds for
+/// constraints following the order of `desired`.
+fn satisfy(desired: &[f32], order: &[usize], constraints: &[Constraint]) -> Vec<f32> {
+    let n = desired.len();
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (c, constraint) in constraints.iter().enumerate() {
+        incoming[constraint.right].push(c);
+    }
+
+    // Block of each variable and its offset from the block position
+    let mut block = vec![0; n];
+    let mut offset = vec![0.0f32; n];
+    let mut members: Vec<Vec<usize>> = Vec::with_capacity(n);
+    let mut position: Vec<f32> = Vec::with_capacity(n);
+
+    for &v in order.iter() {
+        let b = members.len();
+        block[v] = b;
+        members.push(vec![v]);
+        position.push(desired[v]);
+
+        loop {
+            // Most violated constraint entering the block
+            let mut worst: Option<(usize, f32)> = None;
+            for &u in members[b].iter() {
+                for &c in incoming[u].iter() {
+                    let Constraint { left, right, gap } = constraints[c];
+                    if block[left] == b {
+                        continue;
+                    }
+                    let violation =
+                        position[block[left]] + offset[left] + gap - position[b] - offset[right];
+                    if violation > 1e-6 && worst.is_none_or(|(_, w)| violation > w) {
+                        worst = Some((c, violation));
+                    }
+                }
+            }
+            let c = match worst {
+                Some((c, _)) => c,
+                None => break,
+            };
+
+            // Merge the block of `left` into this one, making the constraint tight
+            let Constraint { left, right, gap } = constraints[c];
+            let merged = block[left];
+            let delta = offset[right] - gap - offset[left];
+            for u in std::mem::take(&mut members[merged]) {
+                block[u] = b;
+                offset[u] += delta;
+                members[b].push(u);
+            }
+            position[b] = members[b]
+                .iter()
+                .map(|&u| desired[u] - offset[u])
+                .sum::<f32>()
+                / members[b].len() as f32;
+        }
+    }
+
+    (0..n).map(|v| position[block[v]] + offset[v]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::random_points;
+
+    #[test]
+    fn test_remove_overlaps() {
+        let n = 60;
+        let mut points = random_points(n, 2, 29.0, 37);
+        let sizes: Vec<(f32, f32)> = (0..n)
+            .map(|i| (2.0 + (i % 5) as f32, 1.0 + (i % 3) as f32))
+            .collect();
+        remove_overlaps(&mut points, &sizes, 0.5);
+
+        for i in 0..n {
+            for j in 0..i {
+                let (pi, pj) = (points.get(i), points.get(j));
+                let ox = (sizes[i].0 + sizes[j].0) / 2.0 + 0.5 - (pi[0] - pj[0]).abs();
+                let oy = (sizes[i].1 + sizes[j].1) / 2.0 + 0.5 - (pi[1] - pj[1]).abs();
+                assert!(ox <= 1e-3 || oy <= 1e-3, "{} and {} overlap", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_overlaps_keeps_order() {
+        // A row of overlapping boxes
+        let mut row = PointList {
+            dimensions: 2,
+            points: vec![0.0, 0.0, 1.0, 0.1, 0.5, -0.1, 3.0, 0.0],
+        };
+        remove_overlaps(&mut row, &[(2.0, 2.0); 4], 0.0);
+        let xs: Vec<f32> = row.iter().map(|p| p[0]).collect();
+        assert!(xs[0] < xs[2] && xs[2] < xs[1] && xs[1] < xs[3]);
+        assert!(xs[2] - xs[0] >= 2.0 - 1e-4 && xs[1] - xs[2] >= 2.0 - 1e-4);
+    }
+}
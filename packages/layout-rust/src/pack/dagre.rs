@@ -0,0 +1,141 @@
+use super::{connected_components, pack_rectangles, PackOptions};
+use crate::dagre::layout;
+use crate::util::Edge;
+use crate::{GraphConfig, GraphEdge, GraphNode};
+
+use graphlib_rust::{Graph, GraphOption};
+use std::collections::HashMap;
+
+/// Runs `layout` on each connected component of `graph`, then packs them
+///
+/// Nodes of a same subgraph belong to the same component. Components are laid out one
+/// after the other; the graph `width` and `height` are updated to the packing, margins
+/// included.
+pub fn dagre_layout_components(
+    graph: &mut Graph<GraphConfig, GraphNode, GraphEdge>,
+    options: &PackOptions,
+) {
+    let ids = graph.nodes();
+    let index: HashMap<String, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(n, v)| (v.clone(), n))
+        .collect();
+    let mut links: Vec<Edge> = Vec::new();
+    for e in graph.edges() {
+        links.push((index[&e.v], index[&e.w]));
+    }
+    for (n, v) in ids.iter().enumerate() {
+        if let Some(parent) = graph.parent(v) {
+            links.push((n, index[parent]));
+        }
+    }
+    let components = connected_components(ids.len(), &links);
+    if components.len() < 2 {
+        layout(graph);
+        return;
+    }
+
+    let mut component_of = vec![0; ids.len()];
+    for (c, nodes) in components.iter().enumerate() {
+        for &n in nodes.iter() {
+            component_of[n] = c;
+        }
+    }
+
+    let mut boxes = Vec::with_capacity(components.len());
+    for (c, nodes) in components.iter().enumerate() {
+        let mut sub: Graph<GraphConfig, GraphNode, GraphEdge> = Graph::new(Some(GraphOption {
+            directed: Some(true),
+            multigraph: Some(true),
+            compound: Some(true),
+        }));
+        sub.set_graph(graph.graph().clone());
+        for &n in nodes.iter() {
+            sub.set_node(ids[n].clone(), graph.node(&ids[n]).cloned());
+        }
+        for &n in nodes.iter() {
+            let _ = sub.set_parent(&ids[n], graph.parent(&ids[n]).cloned());
+        }
+        for e in graph.edges() {
+            if component_of[index[&e.v]] == c {
+                let _ = sub.set_edge_with_obj(&e, graph.edge_with_obj(&e).cloned());
+            }
+        }
+
+        layout(&mut sub);
+
+        let mut bbox = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+        for &n in nodes.iter() {
+            let label = sub.node(&ids[n]).unwrap();
+            bbox[0] = bbox[0].min(label.x - label.width / 2.0);
+            bbox[1] = bbox[1].min(label.y - label.height / 2.0);
+            bbox[2] = bbox[2].max(label.x + label.width / 2.0);
+            bbox[3] = bbox[3].max(label.y + label.height / 2.0);
+            if let Some(node) = graph.node_mut(&ids[n]) {
+                node.x = label.x;
+                node.y = label.y;
+                if !sub.children(&ids[n]).is_empty() {
+                    node.width = label.width;
+                    node.height = label.height;
+                }
+            }
+        }
+        for e in sub.edges() {
+            let label = sub.edge_with_obj(&e).unwrap();
+            for p in label.points.iter().flatten() {
+                bbox[0] = bbox[0].min(p.x);
+                bbox[1] = bbox[1].min(p.y);
+                bbox[2] = bbox[2].max(p.x);
+                bbox[3] = bbox[3].max(p.y);
+            }
+            if let Some(edge) = graph.edge_mut_with_obj(&e) {
+                edge.points = label.points.clone();
+                edge.x = label.x;
+                edge.y = label.y;
+            }
+        }
+        boxes.push(bbox);
+    }
+
+    let rectangles: Vec<(f32, f32)> = boxes.iter().map(|b| (b[2] - b[0], b[3] - b[1])).collect();
+    let corners = pack_rectangles(&rectangles, options);
+    let marginx = graph.graph().marginx.unwrap_or(0.0);
+    let marginy = graph.graph().marginy.unwrap_or(0.0);
+    let mut offsets = Vec::with_capacity(components.len());
+    for (bbox, corner) in boxes.iter().zip(corners.iter()) {
+        offsets.push((corner.0 - bbox[0] + marginx, corner.1 - bbox[1] + marginy));
+    }
+
+    for (n, v) in ids.iter().enumerate() {
+        let (dx, dy) = offsets[component_of[n]];
+        if let Some(node) = graph.node_mut(v) {
+            node.x += dx;
+            node.y += dy;
+        }
+    }
+    for e in graph.edges() {
+        let (dx, dy) = offsets[component_of[index[&e.v]]];
+        if let Some(edge) = graph.edge_mut_with_obj(&e) {
+            edge.x += dx;
+            edge.y += dy;
+            for p in edge.points.iter_mut().flatten() {
+                p.x += dx;
+                p.y += dy;
+            }
+        }
+    }
+
+    let width = corners
+        .iter()
+        .zip(rectangles.iter())
+        .map(|(c, r)| c.0 + r.0)
+        .fold(0.0, f32::max);
+    let height = corners
+        .iter()
+        .zip(rectangles.iter())
+        .map(|(c, r)| c.1 + r.1)
+        .fold(0.0, f32::max);
+    graph.graph_mut().width = width + 2.0 * marginx;
+    graph.graph_mut().height = height + 2.0 * marginy;
+}
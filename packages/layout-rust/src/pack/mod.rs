@@ -0,0 +1,382 @@
+//! Packing of the connected components of a graph.
+//!
+//! Each component is laid out on its own, then the bounding boxes of the components are
+//! packed with a skyline bottom-left heuristic, tallest first, into a strip whose width
+//! follows `aspect_ratio`.
+
+mod dagre;
+
+pub use self::dagre::dagre_layout_components;
+
+use crate::layout::Layout;
+use crate::run::{RunOptions, RunReport};
+use crate::util::{adjacency, Edge, Nodes, PointList};
+
+use rayon::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct PackOptions {
+    /// Gap between the bounding boxes of two components
+    pub spacing: f32,
+    /// Target width over height of the packing
+    pub aspect_ratio: f32,
+    /// Lay out the components of a force layout in parallel
+    pub parallel: bool,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self {
+            spacing: 20.0,
+            aspect_ratio: 1.0,
+            parallel: true,
+        }
+    }
+}
+
+/// Nodes of each connected component, by smallest node
+pub fn connected_components(n: usize, edges: &[Edge]) -> Vec<Vec<usize>> {
+    let adjacency = adjacency(n, edges);
+    let mut component = vec![usize::MAX; n];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for root in 0..n {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        let c = components.len();
+        component[root] = c;
+        let mut nodes = vec![root];
+        let mut i = 0;
+        while i < nodes.len() {
+            for &n2 in adjacency[nodes[i]].iter() {
+                if component[n2] == usize::MAX {
+                    component[n2] = c;
+                    nodes.push(n2);
+                }
+            }
+            i += 1;
+        }
+        nodes.sort_unstable();
+        components.push(nodes);
+    }
+    components
+}
+
+/// Positions of the top-left corners of rectangles `(width, height)` packed without
+/// overlapping, `spacing` apart, from `(0, 0)`
+pub fn pack_rectangles(sizes: &[(f32, f32)], options: &PackOptions) -> Vec<(f32, f32)> {
+    let spacing = options.spacing;
+    let area: f32 = sizes
+        .iter()
+        .map(|(w, h)| (w + spacing) * (h + spacing))
+        .sum();
+    let widest = sizes.iter().map(|(w, _)| w + spacing).fold(0.0, f32::max);
+    let strip = (area * options.aspect_ratio).sqrt().max(widest);
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|a, b| {
+        sizes[*b]
+            .1
+            .total_cmp(&sizes[*a].1)
+            .then(sizes[*b].0.total_cmp(&sizes[*a].0))
+    });
+
+    // Segments `(x, width, y)` of the skyline, sorted by `x`
+    let mut skyline: Vec<(f32, f32, f32)> = vec![(0.0, strip, 0.0)];
+    let mut positions = vec![(0.0, 0.0); sizes.len()];
+    for i in order {
+        let (w, h) = (sizes[i].0 + spacing, sizes[i].1 + spacing);
+        let mut best: Option<(f32, f32)> = None;
+        for s in 0..skyline.len() {
+            let x = skyline[s].0;
+            if s > 0 && x + w > strip * (1.0 + f32::EPSILON) {
+                break;
+            }
+            let y = skyline[s..]
+                .iter()
+                .take_while(|seg| seg.0 < x + w)
+                .map(|seg| seg.2)
+                .fold(0.0, f32::max);
+            if best.is_none_or(|(_, best_y)| y < best_y) {
+                best = Some((x, y));
+            }
+        }
+        let (x, y) = best.unwrap();
+        positions[i] = (x, y);
+        raise_skyline(&mut skyline, x, w, y + h);
+    }
+    positions
+}
+
+/// Replaces the part of `skyline` between `x` and `x + w` by a segment at height `top`
+fn raise_skyline(skyline: &mut Vec<(f32, f32, f32)>, x: f32, w: f32, top: f32) {
+    let end = x + w;
+    let mut raised: Vec<(f32, f32, f32)> = Vec::with_capacity(skyline.len() + 2);
+    let mut inserted = false;
+    for &(sx, sw, sy) in skyline.iter() {
+        let s_end = sx + sw;
+        if s_end <= x {
+            raised.push((sx, sw, sy));
+            continue;
+        }
+        if sx < x {
+            raised.push((sx, x - sx, sy));
+        }
+        if !inserted && sx < end {
+            raised.push((x, w, top));
+            inserted = true;
+        }
+        if s_end > end {
+            let start = sx.max(end);
+            raised.push((start, s_end - start, sy));
+        }
+    }
+    if !inserted {
+        raised.push((x, w, top));
+    }
+
+    skyline.clear();
+    for seg in raised {
+        match skyline.last_mut() {
+            Some(last) if last.2 == seg.2 => last.1 = seg.0 + seg.1 - last.0,
+            _ => skyline.push(seg),
+        }
+    }
+}
+
+/// Translates the connected components of a layout so that their bounding boxes, in the
+/// plane of the first two coordinates, do not overlap. The packing is centered on the first
+/// two coordinates of `center`, as `Settings::center`.
+///
+/// `sizes` are node diameters, as `Layout::sizes`.
+pub fn pack_components(
+    points: &mut PointList,
+    edges: &[Edge],
+    sizes: Option<&[f32]>,
+    center: &[f32],
+    options: &PackOptions,
+) {
+    let components = connected_components(points.points.len() / points.dimensions, edges);
+    if components.len() < 2 {
+        return;
+    }
+
+    let boxes: Vec<[f32; 4]> = components
+        .iter()
+        .map(|nodes| {
+            let mut bbox = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+            for &n in nodes.iter() {
+                let p = points.get(n);
+                let r = sizes.map_or(0.0, |sizes| sizes[n] / 2.0);
+                bbox[0] = bbox[0].min(p[0] - r);
+                bbox[1] = bbox[1].min(p[1] - r);
+                bbox[2] = bbox[2].max(p[0] + r);
+                bbox[3] = bbox[3].max(p[1] + r);
+            }
+            bbox
+        })
+        .collect();
+    let rectangles: Vec<(f32, f32)> = boxes.iter().map(|b| (b[2] - b[0], b[3] - b[1])).collect();
+    let corners = pack_rectangles(&rectangles, options);
+
+    let width = corners
+        .iter()
+        .zip(rectangles.iter())
+        .map(|(c, r)| c.0 + r.0)
+        .fold(0.0, f32::max);
+    let height = corners
+        .iter()
+        .zip(rectangles.iter())
+        .map(|(c, r)| c.1 + r.1)
+        .fold(0.0, f32::max);
+    for ((nodes, bbox), corner) in components.iter().zip(boxes.iter()).zip(corners.iter()) {
+        let dx = corner.0 - bbox[0] - width / 2.0 + center[0];
+        let dy = corner.1 - bbox[1] - height / 2.0 + center[1];
+        for &n in nodes.iter() {
+            let p = points.get_mut(n);
+            p[0] += dx;
+            p[1] += dy;
+        }
+    }
+}
+
+impl Layout {
+    /// Runs each connected component as its own layout, then packs them with
+    /// `pack_components` around `settings.center`
+    ///
+    /// Keeps disconnected components from drifting apart, e.g. without gravity. Returns
+    /// the report of each component, in the order of `connected_components`. Fixed nodes are
+    /// translated along with their component.
+    pub fn run_components(
+        &mut self,
+        max_iterations: usize,
+        options: &PackOptions,
+    ) -> Vec<RunReport> {
+        let d = self.settings.dimensions;
+        let components = connected_components(self.masses.len(), &self.edges);
+        let run_options = || RunOptions {
+            max_iterations,
+            ..Default::default()
+        };
+        if components.len() < 2 {
+            return vec![self.run(run_options())];
+        }
+
+        // Component of each node, and its index in the component
+        let mut local = vec![(0, 0); self.masses.len()];
+        for (c, nodes) in components.iter().enumerate() {
+            for (i, &n) in nodes.iter().enumerate() {
+                local[n] = (c, i);
+            }
+        }
+        let mut edges: Vec<Vec<Edge>> = vec![Vec::new(); components.len()];
+        let mut weights: Vec<Vec<f32>> = vec![Vec::new(); components.len()];
+        for (e, &(n1, n2)) in self.edges.iter().enumerate() {
+            let c = local[n1].0;
+            edges[c].push((local[n1].1, local[n2].1));
+            if let Some(w) = &self.weights {
+                weights[c].push(w[e]);
+            }
+        }
+
+        let mut layouts: Vec<Layout> = components
+            .iter()
+            .zip(edges.into_iter().zip(weights))
+            .map(|(nodes, (edges, weights))| {
+                let mut layout = Layout::from_position_graph(
+                    edges,
+                    Nodes::Mass(nodes.iter().map(|&n| self.masses[n]).collect()),
+                    nodes
+                        .iter()
+                        .flat_map(|&n| self.points.get(n).iter().cloned())
+                        .collect(),
+                    self.weights.as_ref().map(|_| weights),
+                    self.settings.clone(),
+                );
+                layout.sizes = self
+                    .sizes
+                    .as_ref()
+                    .map(|sizes| nodes.iter().map(|&n| sizes[n]).collect());
+                layout.fixed = self
+                    .fixed
+                    .as_ref()
+                    .map(|fixed| nodes.iter().map(|&n| fixed[n]).collect());
+                layout
+            })
+            .collect();
+
+        let reports = if options.parallel {
+            layouts
+                .par_iter_mut()
+                .map(|layout| layout.run(run_options()))
+                .collect()
+        } else {
+            layouts
+                .iter_mut()
+                .map(|layout| layout.run(run_options()))
+                .collect()
+        };
+
+        for (nodes, layout) in components.iter().zip(layouts.iter()) {
+            for (&n, pos) in nodes.iter().zip(layout.points.iter()) {
+                self.points.points[n * d..(n + 1) * d].copy_from_slice(pos);
+            }
+        }
+        pack_components(
+            &mut self.points,
+            &self.edges,
+            self.sizes.as_deref(),
+            &self.settings.center,
+            options,
+        );
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Settings;
+
+    fn overlap(a: [f32; 4], b: [f32; 4]) -> bool {
+        a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+    }
+
+    #[test]
+    fn test_pack_rectangles() {
+        let sizes: Vec<(f32, f32)> = (0..30)
+            .map(|i| (1.0 + (i * 7 % 11) as f32, 1.0 + (i * 5 % 13) as f32))
+            .collect();
+        let options = PackOptions {
+            spacing: 1.0,
+            ..Default::default()
+        };
+        let corners = pack_rectangles(&sizes, &options);
+        let boxes: Vec<[f32; 4]> = corners
+            .iter()
+            .zip(sizes.iter())
+            .map(|(c, s)| [c.0, c.1, c.0 + s.0 + 1.0, c.1 + s.1 + 1.0])
+            .collect();
+        for i in 0..boxes.len() {
+            assert!(boxes[i][0] >= 0.0 && boxes[i][1] >= 0.0);
+            for j in 0..i {
+                assert!(!overlap(boxes[i], boxes[j]));
+            }
+        }
+        let width = boxes.iter().map(|b| b[2]).fold(0.0, f32::max);
+        let height = boxes.iter().map(|b| b[3]).fold(0.0, f32::max);
+        assert!(width / height > 0.5 && width / height < 2.0);
+    }
+
+    #[test]
+    fn test_run_components() {
+        // Two triangles and an isolated node, without gravity
+        let edges = vec![(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)];
+        let mut layout = Layout::from_position_graph(
+            edges,
+            Nodes::Degree(7),
+            (0..14).map(|i| ((i * 7) % 5) as f32).collect(),
+            None,
+            Settings {
+                chunk_size: None,
+                kg: 0.0,
+                center: vec![100.0, -50.0],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            connected_components(7, &layout.edges),
+            vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]
+        );
+        let options = PackOptions {
+            spacing: 5.0,
+            parallel: false,
+            ..Default::default()
+        };
+        let reports = layout.run_components(50, &options);
+        assert_eq!(reports.len(), 3);
+
+        let bbox = |nodes: &[usize]| {
+            let mut b = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+            for &n in nodes {
+                let p = layout.points.get(n);
+                b = [
+                    b[0].min(p[0]),
+                    b[1].min(p[1]),
+                    b[2].max(p[0]),
+                    b[3].max(p[1]),
+                ];
+            }
+            [b[0] - 2.0, b[1] - 2.0, b[2] + 2.0, b[3] + 2.0]
+        };
+        let boxes = [bbox(&[0, 1, 2]), bbox(&[3, 4, 5]), bbox(&[6])];
+        assert!(!overlap(boxes[0], boxes[1]));
+        assert!(!overlap(boxes[0], boxes[2]));
+        assert!(!overlap(boxes[1], boxes[2]));
+
+        // The packing is centered on `settings.center`
+        let all = bbox(&[0, 1, 2, 3, 4, 5, 6]);
+        assert!(((all[0] + all[2]) / 2.0 - 100.0).abs() < 1e-3);
+        assert!(((all[1] + all[3]) / 2.0 + 50.0).abs() < 1e-3);
+    }
+}
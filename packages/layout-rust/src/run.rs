@@ -0,0 +1,145 @@
+use crate::layout::Layout;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// When `Layout::run` stops
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    pub max_iterations: usize,
+    /// Stop once this much time is spent, checked after each iteration
+    ///
+    /// Relies on `std::time::Instant`, which panics on `wasm32-unknown-unknown`: leave it to
+    /// `None` there.
+    pub time_budget: Option<Duration>,
+    /// Called after each iteration with its index and how far nodes moved
+    pub on_tick: Option<&'a mut dyn FnMut(usize, f32)>,
+    /// Stop before the next iteration once set, e.g. from another thread
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// Nodes moved less than `min_movement`
+    Converged,
+    MaxIterations,
+    TimeBudget,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunReport {
+    /// Number of iterations computed
+    pub iterations: usize,
+    /// How far nodes moved in the last iteration, `None` if none was computed
+    pub movement: Option<f32>,
+    pub reason: StopReason,
+}
+
+impl Layout {
+    /// Iterates until convergence, or until one of the limits of `options` is reached
+    pub fn run(&mut self, mut options: RunOptions) -> RunReport {
+        let start = options.time_budget.map(|_| Instant::now());
+        let mut movement = None;
+
+        for i in 0..options.max_iterations {
+            if let Some(cancel) = options.cancel {
+                if cancel.load(Ordering::Relaxed) {
+                    return RunReport {
+                        iterations: i,
+                        movement,
+                        reason: StopReason::Cancelled,
+                    };
+                }
+            }
+
+            let distance = self.step(i);
+            movement = Some(distance);
+            if let Some(on_tick) = &mut options.on_tick {
+                on_tick(i, distance);
+            }
+
+            let reason = if distance < self.settings.min_movement {
+                StopReason::Converged
+            } else if start
+                .zip(options.time_budget)
+                .is_some_and(|(start, budget)| start.elapsed() >= budget)
+            {
+                StopReason::TimeBudget
+            } else {
+                continue;
+            };
+            return RunReport {
+                iterations: i + 1,
+                movement,
+                reason,
+            };
+        }
+
+        RunReport {
+            iterations: options.max_iterations,
+            movement,
+            reason: StopReason::MaxIterations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Settings;
+    use crate::util::Nodes;
+
+    fn layout(min_movement: f32) -> Layout {
+        Layout::from_position_graph(
+            vec![(0, 1), (1, 2), (2, 3)],
+            Nodes::Degree(4),
+            vec![0.0, 0.0, 5.0, 1.0, 2.0, 7.0, 9.0, 3.0],
+            None,
+            Settings {
+                chunk_size: None,
+                min_movement,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_run() {
+        let mut ticks = Vec::new();
+        let mut on_tick = |i, _| ticks.push(i);
+        let report = layout(0.0).run(RunOptions {
+            max_iterations: 5,
+            on_tick: Some(&mut on_tick),
+            ..Default::default()
+        });
+        assert_eq!(report.iterations, 5);
+        assert_eq!(report.reason, StopReason::MaxIterations);
+        assert_eq!(ticks, vec![0, 1, 2, 3, 4]);
+
+        let report = layout(f32::INFINITY).run(RunOptions {
+            max_iterations: 5,
+            ..Default::default()
+        });
+        assert_eq!(report.iterations, 1);
+        assert_eq!(report.reason, StopReason::Converged);
+
+        let cancel = AtomicBool::new(true);
+        let report = layout(0.0).run(RunOptions {
+            max_iterations: 5,
+            cancel: Some(&cancel),
+            ..Default::default()
+        });
+        assert_eq!(report.iterations, 0);
+        assert_eq!(report.movement, None);
+        assert_eq!(report.reason, StopReason::Cancelled);
+
+        let report = layout(0.0).run(RunOptions {
+            max_iterations: 5,
+            time_budget: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        assert_eq!(report.iterations, 1);
+        assert_eq!(report.reason, StopReason::TimeBudget);
+    }
+}
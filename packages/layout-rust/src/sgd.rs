@@ -0,0 +1,180 @@
+//! Stress layout by stochastic gradient descent, after Zheng, Pawar & Goodman, "Graph
+//! Drawing by Stochastic Gradient Descent".
+//!
+//! Each iteration visits the terms of stress in a random order, moving their node to reduce
+//! the error on that single term. The step size anneals exponentially from `1 / w_min`,
+//! which satisfies the first terms exactly, to `epsilon / w_max`. The terms are those of
+//! `stress_majorization`, all pairs or sparse with pivots.
+
+use crate::distance::{all_shortest_paths, fill_unreachable, max_min_pivots, weighted_adjacency};
+use crate::stress::{full_terms, sparse_terms, stress, StressReport};
+use crate::util::{Edge, PointList, Rng};
+
+#[derive(Clone, Debug)]
+pub struct SgdOptions {
+    pub dimensions: usize,
+    pub max_iterations: usize,
+    /// Final step size, relative to the strongest term
+    pub epsilon: f32,
+    /// Stop once no node moves further than this in an iteration
+    pub tolerance: f32,
+    /// Approximate stress with this many pivots, instead of computing all pairs
+    pub pivots: Option<usize>,
+    /// Seed of the random initial positions and visiting order
+    pub seed: u64,
+}
+
+impl Default for SgdOptions {
+    fn default() -> Self {
+        Self {
+            dimensions: 2,
+            max_iterations: 30,
+            epsilon: 0.1,
+            tolerance: 0.03,
+            pivots: None,
+            seed: 42,
+        }
+    }
+}
+
+/// Lays out the graph of `n` nodes so that Euclidean distances match shortest path lengths
+///
+/// `lengths` are the lengths of the edges, `1` if `None`. Starts from `initial` positions,
+/// or else from random ones. The result only depends on the graph and `options`.
+pub fn sgd_layout(
+    n: usize,
+    edges: &[Edge],
+    lengths: Option<&[f32]>,
+    initial: Option<&PointList>,
+    options: &SgdOptions,
+) -> (PointList, StressReport) {
+    let d = options.dimensions;
+    let adjacency = weighted_adjacency(n, edges, lengths);
+    let terms = match options.pivots {
+        None => {
+            let mut distances = all_shortest_paths(&adjacency);
+            fill_unreachable(&mut distances);
+            full_terms(&distances)
+        }
+        Some(k) => {
+            let (pivots, mut rows) = max_min_pivots(&adjacency, k.max(1), 0);
+            fill_unreachable(&mut rows);
+            sparse_terms(&adjacency, &pivots, &rows)
+        }
+    };
+
+    let mut rng = Rng::new(options.seed);
+    let mut points = match initial {
+        Some(initial) => {
+            assert_eq!(initial.dimensions, d);
+            assert_eq!(initial.points.len(), n * d);
+            initial.clone()
+        }
+        None => PointList {
+            dimensions: d,
+            points: (0..n * d).map(|_| rng.next_f32()).collect(),
+        },
+    };
+
+    // Each term moves its own node, `(i, j, d_ij, w_ij)`
+    let mut pairs: Vec<(usize, usize, f32, f32)> = terms
+        .iter()
+        .enumerate()
+        .flat_map(|(i, terms)| terms.iter().map(move |&(j, dij, w)| (i, j, dij, w)))
+        .collect();
+    let (w_min, w_max) = pairs
+        .iter()
+        .fold((f32::MAX, 0.0f32), |(lo, hi), p| (lo.min(p.3), hi.max(p.3)));
+    let eta_max = 1.0 / w_min;
+    let eta_min = options.epsilon / w_max;
+    let decay = if options.max_iterations > 1 {
+        (eta_max / eta_min).ln() / (options.max_iterations - 1) as f32
+    } else {
+        0.0
+    };
+
+    let mut report = StressReport {
+        iterations: 0,
+        stress: 0.0,
+        converged: false,
+    };
+    let mut delta = vec![0.0; d];
+    for iteration in 0..options.max_iterations {
+        if pairs.is_empty() {
+            break;
+        }
+        let eta = eta_max * (-decay * iteration as f32).exp();
+        rng.shuffle(&mut pairs);
+        let mut largest = 0.0f32;
+        for &(i, j, dij, w) in pairs.iter() {
+            let mu = (w * eta).min(1.0);
+            let pj = points.get(j);
+            for ((x, a), b) in delta.iter_mut().zip(points.get(i)).zip(pj) {
+                *x = a - b;
+            }
+            let dist = delta.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if dist == 0.0 {
+                continue;
+            }
+            let r = mu * (dist - dij) / dist;
+            for (p, x) in points.get_mut(i).iter_mut().zip(delta.iter()) {
+                *p -= r * x;
+            }
+            largest = largest.max(r.abs() * dist);
+        }
+        report.iterations = iteration + 1;
+        if largest < options.tolerance {
+            report.converged = true;
+            break;
+        }
+    }
+    report.stress = stress(&points, &terms);
+    (points, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sgd_layout() {
+        let mut grid = Vec::new();
+        for i in 0..8 {
+            for j in 0..8 {
+                if j < 7 {
+                    grid.push((i * 8 + j, i * 8 + j + 1));
+                }
+                if i < 7 {
+                    grid.push((i * 8 + j, i * 8 + j + 8));
+                }
+            }
+        }
+        let distance = |points: &PointList, i: usize, j: usize| {
+            let (p, q) = (points.get(i), points.get(j));
+            ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2)).sqrt()
+        };
+
+        // The corners of the grid end up as far apart as with stress majorization
+        let (points, report) = sgd_layout(64, &grid, None, None, &Default::default());
+        let (majorized, _) =
+            crate::stress::stress_majorization(64, &grid, None, None, &Default::default());
+        let side = distance(&majorized, 0, 7);
+        assert!((distance(&points, 0, 7) - side).abs() < 0.2);
+        assert!((distance(&points, 0, 56) - side).abs() < 0.2);
+        // Majorization from there barely improves stress
+        let (_, majorized) =
+            crate::stress::stress_majorization(64, &grid, None, Some(&points), &Default::default());
+        assert!(majorized.stress > 0.9 * report.stress);
+
+        // Seeded: the same options give the same layout
+        let options = SgdOptions {
+            pivots: Some(6),
+            ..Default::default()
+        };
+        let (a, report) = sgd_layout(64, &grid, None, None, &options);
+        let (b, _) = sgd_layout(64, &grid, None, None, &options);
+        assert_eq!(a.points, b.points);
+        assert!(report.stress.is_finite());
+        assert!((distance(&a, 0, 7) - side).abs() < 1.5);
+    }
+}
@@ -0,0 +1,342 @@
+//! Lane abstraction used by the SIMD force kernels.
+//!
+//! `Native` resolves at compile time to the widest vector type enabled for the target:
+//! AVX2 on `x86_64` with `-C target-feature=+avx2` (SSE2 otherwise), simd128 on `wasm32`
+//! with `-C target-feature=+simd128`, and a portable `[f32; 8]` elsewhere, left to the
+//! auto-vectorizer.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+pub trait Lanes:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+{
+    const LANES: usize;
+
+    fn splat(v: f32) -> Self;
+
+    /// Loads `LANES` consecutive values starting at `offset`.
+    ///
+    /// # Safety
+    /// `offset + LANES` must be in bounds.
+    unsafe fn load(s: &[f32], offset: usize) -> Self;
+
+    fn sqrt(self) -> Self;
+
+    /// Keeps the lanes where `lo < d2 < hi`, zeroes the others (including NaN lanes).
+    fn keep_between(self, d2: Self, lo: Self, hi: Self) -> Self;
+
+    fn sum(self) -> f32;
+}
+
+impl Lanes for f32 {
+    const LANES: usize = 1;
+
+    #[inline(always)]
+    fn splat(v: f32) -> Self {
+        v
+    }
+
+    #[inline(always)]
+    unsafe fn load(s: &[f32], offset: usize) -> Self {
+        *s.get_unchecked(offset)
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    #[inline(always)]
+    fn keep_between(self, d2: Self, lo: Self, hi: Self) -> Self {
+        if lo < d2 && d2 < hi {
+            self
+        } else {
+            0.0
+        }
+    }
+
+    #[inline(always)]
+    fn sum(self) -> f32 {
+        self
+    }
+}
+
+/// Portable 8-lane vector, left to the auto-vectorizer.
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub struct F32x8(pub [f32; 8]);
+
+macro_rules! impl_portable_op {
+    ($tr:ident, $f:ident, $op:tt) => {
+        impl $tr for F32x8 {
+            type Output = Self;
+
+            #[inline(always)]
+            fn $f(self, rhs: Self) -> Self {
+                let mut r = self.0;
+                r.iter_mut().zip(rhs.0.iter()).for_each(|(a, b)| *a $op *b);
+                F32x8(r)
+            }
+        }
+    };
+}
+
+impl_portable_op!(Add, add, +=);
+impl_portable_op!(Sub, sub, -=);
+impl_portable_op!(Mul, mul, *=);
+impl_portable_op!(Div, div, /=);
+
+impl Lanes for F32x8 {
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    fn splat(v: f32) -> Self {
+        F32x8([v; 8])
+    }
+
+    #[inline(always)]
+    unsafe fn load(s: &[f32], offset: usize) -> Self {
+        let mut r = [0.0; 8];
+        r.copy_from_slice(s.get_unchecked(offset..offset + 8));
+        F32x8(r)
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        let mut r = self.0;
+        r.iter_mut().for_each(|a| *a = a.sqrt());
+        F32x8(r)
+    }
+
+    #[inline(always)]
+    fn keep_between(self, d2: Self, lo: Self, hi: Self) -> Self {
+        let mut r = self.0;
+        for (i, a) in r.iter_mut().enumerate() {
+            // Non-short-circuiting `&` keeps the select branchless.
+            if !((lo.0[i] < d2.0[i]) & (d2.0[i] < hi.0[i])) {
+                *a = 0.0;
+            }
+        }
+        F32x8(r)
+    }
+
+    #[inline(always)]
+    fn sum(self) -> f32 {
+        self.0.iter().sum()
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+mod avx2 {
+    use super::Lanes;
+    use std::arch::x86_64::*;
+    use std::ops::{Add, Div, Mul, Sub};
+
+    #[derive(Clone, Copy)]
+    pub struct F32x8Avx2(__m256);
+
+    macro_rules! impl_avx2_op {
+        ($tr:ident, $f:ident, $intr:ident) => {
+            impl $tr for F32x8Avx2 {
+                type Output = Self;
+
+                #[inline(always)]
+                #[allow(unused_unsafe)]
+                fn $f(self, rhs: Self) -> Self {
+                    F32x8Avx2(unsafe { $intr(self.0, rhs.0) })
+                }
+            }
+        };
+    }
+
+    impl_avx2_op!(Add, add, _mm256_add_ps);
+    impl_avx2_op!(Sub, sub, _mm256_sub_ps);
+    impl_avx2_op!(Mul, mul, _mm256_mul_ps);
+    impl_avx2_op!(Div, div, _mm256_div_ps);
+
+    #[allow(unused_unsafe)]
+    impl Lanes for F32x8Avx2 {
+        const LANES: usize = 8;
+
+        #[inline(always)]
+        fn splat(v: f32) -> Self {
+            F32x8Avx2(unsafe { _mm256_set1_ps(v) })
+        }
+
+        #[inline(always)]
+        unsafe fn load(s: &[f32], offset: usize) -> Self {
+            F32x8Avx2(_mm256_loadu_ps(s.as_ptr().add(offset)))
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            F32x8Avx2(unsafe { _mm256_sqrt_ps(self.0) })
+        }
+
+        #[inline(always)]
+        fn keep_between(self, d2: Self, lo: Self, hi: Self) -> Self {
+            unsafe {
+                let mask = _mm256_and_ps(
+                    _mm256_cmp_ps::<_CMP_LT_OQ>(lo.0, d2.0),
+                    _mm256_cmp_ps::<_CMP_LT_OQ>(d2.0, hi.0),
+                );
+                F32x8Avx2(_mm256_and_ps(self.0, mask))
+            }
+        }
+
+        #[inline(always)]
+        fn sum(self) -> f32 {
+            let mut r = [0.0f32; 8];
+            unsafe { _mm256_storeu_ps(r.as_mut_ptr(), self.0) };
+            r.iter().sum()
+        }
+    }
+}
+
+#[cfg(all(
+    target_arch = "x86_64",
+    target_feature = "sse2",
+    not(target_feature = "avx2")
+))]
+mod sse2 {
+    use super::Lanes;
+    use std::arch::x86_64::*;
+    use std::ops::{Add, Div, Mul, Sub};
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4Sse2(__m128);
+
+    macro_rules! impl_sse2_op {
+        ($tr:ident, $f:ident, $intr:ident) => {
+            impl $tr for F32x4Sse2 {
+                type Output = Self;
+
+                #[inline(always)]
+                #[allow(unused_unsafe)]
+                fn $f(self, rhs: Self) -> Self {
+                    F32x4Sse2(unsafe { $intr(self.0, rhs.0) })
+                }
+            }
+        };
+    }
+
+    impl_sse2_op!(Add, add, _mm_add_ps);
+    impl_sse2_op!(Sub, sub, _mm_sub_ps);
+    impl_sse2_op!(Mul, mul, _mm_mul_ps);
+    impl_sse2_op!(Div, div, _mm_div_ps);
+
+    #[allow(unused_unsafe)]
+    impl Lanes for F32x4Sse2 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        fn splat(v: f32) -> Self {
+            F32x4Sse2(unsafe { _mm_set1_ps(v) })
+        }
+
+        #[inline(always)]
+        unsafe fn load(s: &[f32], offset: usize) -> Self {
+            F32x4Sse2(_mm_loadu_ps(s.as_ptr().add(offset)))
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            F32x4Sse2(unsafe { _mm_sqrt_ps(self.0) })
+        }
+
+        #[inline(always)]
+        fn keep_between(self, d2: Self, lo: Self, hi: Self) -> Self {
+            unsafe {
+                let mask = _mm_and_ps(_mm_cmplt_ps(lo.0, d2.0), _mm_cmplt_ps(d2.0, hi.0));
+                F32x4Sse2(_mm_and_ps(self.0, mask))
+            }
+        }
+
+        #[inline(always)]
+        fn sum(self) -> f32 {
+            let mut r = [0.0f32; 4];
+            unsafe { _mm_storeu_ps(r.as_mut_ptr(), self.0) };
+            r.iter().sum()
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod simd128 {
+    use super::Lanes;
+    use std::arch::wasm32::*;
+    use std::ops::{Add, Div, Mul, Sub};
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4Simd128(v128);
+
+    macro_rules! impl_simd128_op {
+        ($tr:ident, $f:ident, $intr:ident) => {
+            impl $tr for F32x4Simd128 {
+                type Output = Self;
+
+                #[inline(always)]
+                fn $f(self, rhs: Self) -> Self {
+                    F32x4Simd128($intr(self.0, rhs.0))
+                }
+            }
+        };
+    }
+
+    impl_simd128_op!(Add, add, f32x4_add);
+    impl_simd128_op!(Sub, sub, f32x4_sub);
+    impl_simd128_op!(Mul, mul, f32x4_mul);
+    impl_simd128_op!(Div, div, f32x4_div);
+
+    impl Lanes for F32x4Simd128 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        fn splat(v: f32) -> Self {
+            F32x4Simd128(f32x4_splat(v))
+        }
+
+        #[inline(always)]
+        unsafe fn load(s: &[f32], offset: usize) -> Self {
+            F32x4Simd128(v128_load(s.as_ptr().add(offset) as *const v128))
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            F32x4Simd128(f32x4_sqrt(self.0))
+        }
+
+        #[inline(always)]
+        fn keep_between(self, d2: Self, lo: Self, hi: Self) -> Self {
+            let mask = v128_and(f32x4_lt(lo.0, d2.0), f32x4_lt(d2.0, hi.0));
+            F32x4Simd128(v128_and(self.0, mask))
+        }
+
+        #[inline(always)]
+        fn sum(self) -> f32 {
+            f32x4_extract_lane::<0>(self.0)
+                + f32x4_extract_lane::<1>(self.0)
+                + f32x4_extract_lane::<2>(self.0)
+                + f32x4_extract_lane::<3>(self.0)
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+pub type Native = avx2::F32x8Avx2;
+
+#[cfg(all(
+    target_arch = "x86_64",
+    target_feature = "sse2",
+    not(target_feature = "avx2")
+))]
+pub type Native = sse2::F32x4Sse2;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub type Native = simd128::F32x4Simd128;
+
+#[cfg(not(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    all(target_arch = "wasm32", target_feature = "simd128")
+)))]
+pub type Native = F32x8;
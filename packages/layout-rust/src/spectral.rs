@@ -0,0 +1,387 @@
+//! Spectral layout, after Koren, "Drawing Graphs by Eigenvectors: Theory and Practice".
+//!
+//! The coordinates of the nodes are the eigenvectors of the smallest non-zero eigenvalues of
+//! the weighted Laplacian `L = D - W`. They are the largest eigenvectors of `cI - L`, for `c`
+//! bounding the spectrum of `L`, which the Lanczos iteration finds in a few sparse
+//! products. The constant eigenvector is projected out of the Krylov subspace.
+
+use crate::layout::Layout;
+use crate::pack::{connected_components, pack_components, PackOptions};
+use crate::util::{Edge, PointList};
+
+#[derive(Clone, Debug)]
+pub struct SpectralOptions {
+    /// Size of the Krylov subspace
+    pub subspace: usize,
+    /// Restarts of the Lanczos iteration, if the eigenvectors have not converged
+    pub restarts: usize,
+    /// Residual below which an eigenvector has converged, relative to its eigenvalue
+    pub tolerance: f32,
+    /// Root mean square of the coordinates, relative to the square root of the number of
+    /// nodes
+    pub scale: f32,
+    /// Packing of the connected components, each laid out on its own
+    pub pack: PackOptions,
+}
+
+impl Default for SpectralOptions {
+    fn default() -> Self {
+        Self {
+            subspace: 40,
+            restarts: 10,
+            tolerance: 1e-4,
+            scale: 10.0,
+            pack: PackOptions::default(),
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| *x as f64 * *y as f64)
+        .sum()
+}
+
+/// Subtracts from `v` its projection on the orthonormal `basis`, twice for stability, then
+/// normalizes it. Returns its norm before normalizing.
+fn orthonormalize(v: &mut [f32], basis: &[Vec<f32>]) -> f64 {
+    for _ in 0..2 {
+        for u in basis.iter() {
+            let d = dot(v, u) as f32;
+            v.iter_mut().zip(u.iter()).for_each(|(x, y)| *x -= d * y);
+        }
+    }
+    let norm = dot(v, v).sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm as f32);
+    }
+    norm
+}
+
+/// Eigenvalues and eigenvectors (as columns) of the symmetric matrix `a`, by cyclic Jacobi
+/// rotations, largest eigenvalue first
+fn symmetric_eigen(mut a: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let m = a.len();
+    let mut v: Vec<Vec<f64>> = (0..m)
+        .map(|i| (0..m).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    for _ in 0..100 {
+        let off: f64 = (0..m)
+            .flat_map(|i| (0..m).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        if off < 1e-22 {
+            break;
+        }
+        for p in 0..m {
+            for q in p + 1..m {
+                if a[p][q].abs() < 1e-30 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (x, y) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    let (u, w) = (*x, *y);
+                    *x = c * u - s * w;
+                    *y = s * u + c * w;
+                }
+                for row in v.iter_mut() {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|i, j| a[*j][*j].total_cmp(&a[*i][*i]));
+    let values = order.iter().map(|&i| a[i][i]).collect();
+    let vectors = v
+        .iter()
+        .map(|row| order.iter().map(|&i| row[i]).collect())
+        .collect();
+    (values, vectors)
+}
+
+/// The `k` largest eigenvectors of the symmetric `n` x `n` matrix applied by `mul`,
+/// orthogonal to the orthonormal `deflate`, by restarted Lanczos with full
+/// reorthogonalization
+fn lanczos<F>(
+    n: usize,
+    k: usize,
+    deflate: &[Vec<f32>],
+    options: &SpectralOptions,
+    mul: F,
+) -> Vec<Vec<f32>>
+where
+    F: Fn(&[f32], &mut [f32]),
+{
+    let m = options.subspace.max(k + 1).min(n - deflate.len());
+    let mut start: Vec<f32> = (0..n)
+        .map(|i| ((i * 7_919) % 1_009) as f32 - 504.0)
+        .collect();
+    orthonormalize(&mut start, deflate);
+
+    let mut ritz = Vec::new();
+    for restart in 0..=options.restarts {
+        let mut basis: Vec<Vec<f32>> = deflate.to_vec();
+        let mut alpha: Vec<f64> = Vec::with_capacity(m);
+        let mut beta: Vec<f64> = Vec::with_capacity(m);
+        let mut q = start.clone();
+        let mut w = vec![0.0; n];
+        while alpha.len() < m {
+            mul(&q, &mut w);
+            alpha.push(dot(&q, &w));
+            basis.push(q);
+            let norm = orthonormalize(&mut w, &basis);
+            if alpha.len() == m || norm < 1e-10 {
+                beta.push(norm);
+                break;
+            }
+            beta.push(norm);
+            q = std::mem::replace(&mut w, vec![0.0; n]);
+        }
+
+        let size = alpha.len();
+        let tridiagonal: Vec<Vec<f64>> = (0..size)
+            .map(|i| {
+                (0..size)
+                    .map(|j| {
+                        if i == j {
+                            alpha[i]
+                        } else if i + 1 == j {
+                            beta[i]
+                        } else if j + 1 == i {
+                            beta[j]
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let (values, vectors) = symmetric_eigen(tridiagonal);
+
+        let krylov = &basis[deflate.len()..];
+        let wanted = k.min(size);
+        ritz = (0..wanted)
+            .map(|e| {
+                let mut y = vec![0.0f32; n];
+                for (q, row) in krylov.iter().zip(vectors.iter()) {
+                    let s = row[e] as f32;
+                    y.iter_mut().zip(q.iter()).for_each(|(a, b)| *a += s * b);
+                }
+                y
+            })
+            .collect();
+
+        let last = beta[size - 1];
+        let converged = (0..wanted).all(|e| {
+            (last * vectors[size - 1][e]).abs()
+                <= options.tolerance as f64 * values[e].abs().max(1e-12)
+        });
+        if converged || last < 1e-10 || size < m || restart == options.restarts {
+            break;
+        }
+        start = vec![0.0; n];
+        for y in ritz.iter() {
+            start.iter_mut().zip(y.iter()).for_each(|(a, b)| *a += b);
+        }
+        orthonormalize(&mut start, deflate);
+    }
+
+    // A deterministic sign: the first significant coordinate is positive
+    for y in ritz.iter_mut() {
+        if y.iter().find(|x| x.abs() > 1e-6).is_some_and(|x| *x < 0.0) {
+            y.iter_mut().for_each(|x| *x = -*x);
+        }
+    }
+    ritz
+}
+
+/// Neighbours of each of the `n` nodes, with the weights of the edges, `1` if `None`
+fn weighted_neighbours(
+    n: usize,
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+) -> Vec<Vec<(usize, f32)>> {
+    let mut neighbours: Vec<Vec<(usize, f32)>> = vec![Vec::new(); n];
+    for (e, &(n1, n2)) in edges.iter().enumerate() {
+        if n1 != n2 {
+            let w = weights.map_or(1.0, |weights| weights[e]);
+            neighbours[n1].push((n2, w));
+            neighbours[n2].push((n1, w));
+        }
+    }
+    neighbours
+}
+
+/// The `k` smallest non-constant eigenvectors of the Laplacian of the connected component
+/// of `nodes`, indexed like `nodes`
+///
+/// `local` is the index of each node of the component in `nodes`.
+fn component_eigenvectors(
+    neighbours: &[Vec<(usize, f32)>],
+    nodes: &[usize],
+    local: &[usize],
+    k: usize,
+    options: &SpectralOptions,
+) -> Vec<Vec<f32>> {
+    let size = nodes.len();
+    let degrees: Vec<f32> = nodes
+        .iter()
+        .map(|&v| neighbours[v].iter().map(|(_, w)| w).sum())
+        .collect();
+    let bound = 2.0 * degrees.iter().cloned().fold(0.0, f32::max);
+    let constant = vec![1.0 / (size as f32).sqrt(); size];
+
+    lanczos(size, k, &[constant], options, |x, out| {
+        for (i, o) in out.iter_mut().enumerate() {
+            let neighbourhood: f32 = neighbours[nodes[i]]
+                .iter()
+                .map(|(u, w)| w * x[local[*u]])
+                .sum();
+            *o = (bound - degrees[i]) * x[i] + neighbourhood;
+        }
+    })
+}
+
+/// Spectral layout of the graph of `n` nodes, in `dimensions` dimensions
+///
+/// `weights` are those of the edges, `1` if `None`, as `Layout::weights`. Each connected
+/// component is laid out on its own, then they are packed with `pack_components`.
+pub fn spectral_layout(
+    n: usize,
+    edges: &[Edge],
+    weights: Option<&[f32]>,
+    dimensions: usize,
+    options: &SpectralOptions,
+) -> PointList {
+    let neighbours = weighted_neighbours(n, edges, weights);
+    let mut points = PointList {
+        dimensions,
+        points: vec![0.0; n * dimensions],
+    };
+    let components = connected_components(n, edges);
+    let mut local = vec![0; n];
+    for nodes in components.iter() {
+        for (i, &v) in nodes.iter().enumerate() {
+            local[v] = i;
+        }
+        let size = nodes.len();
+        if size < 2 {
+            continue;
+        }
+        let vectors = component_eigenvectors(&neighbours, nodes, &local, dimensions, options);
+        let scale = options.scale * size as f32;
+        for (c, y) in vectors.iter().enumerate() {
+            for (&v, x) in nodes.iter().zip(y.iter()) {
+                points.get_mut(v)[c] = scale * x;
+            }
+        }
+    }
+
+    pack_components(&mut points, edges, None, &[0.0, 0.0], &options.pack);
+    points
+}
+
+/// Nodes of the graph in the order of the Fiedler vector of their connected component,
+/// which minimizes the sum of the squared distances between neighbours on a line
+///
+/// Components follow each other, by smallest node.
+pub(crate) fn spectral_order(n: usize, edges: &[Edge], weights: Option<&[f32]>) -> Vec<usize> {
+    let neighbours = weighted_neighbours(n, edges, weights);
+    let options = SpectralOptions::default();
+    let mut order = Vec::with_capacity(n);
+    let mut local = vec![0; n];
+    for nodes in connected_components(n, edges) {
+        for (i, &v) in nodes.iter().enumerate() {
+            local[v] = i;
+        }
+        if nodes.len() < 3 {
+            order.extend(nodes);
+            continue;
+        }
+        let fiedler = component_eigenvectors(&neighbours, &nodes, &local, 1, &options).remove(0);
+        let mut ranked: Vec<(f32, usize)> = fiedler.into_iter().zip(nodes).collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        order.extend(ranked.into_iter().map(|(_, v)| v));
+    }
+    order
+}
+
+impl Layout {
+    /// Replaces the positions of the nodes by their `spectral_layout`, e.g. as a
+    /// deterministic start for force layouts
+    ///
+    /// Fixed nodes keep their positions.
+    pub fn seed_spectral(&mut self, options: &SpectralOptions) {
+        let points = spectral_layout(
+            self.masses.len(),
+            &self.edges,
+            self.weights.as_deref(),
+            self.settings.dimensions,
+            options,
+        );
+        self.seed(&points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spectral_layout() {
+        // A path unfolds along the first eigenvector
+        let path: Vec<Edge> = (0..9).map(|i| (i, i + 1)).collect();
+        let points = spectral_layout(10, &path, None, 2, &Default::default());
+        let xs: Vec<f32> = points.iter().map(|p| p[0]).collect();
+        assert!(xs.windows(2).all(|w| w[0] < w[1]) || xs.windows(2).all(|w| w[0] > w[1]));
+
+        // A cycle is drawn as a regular polygon
+        let cycle: Vec<Edge> = (0..12).map(|i| (i, (i + 1) % 12)).collect();
+        let points = spectral_layout(12, &cycle, None, 2, &Default::default());
+        let radius = |p: &[f32]| (p[0] * p[0] + p[1] * p[1]).sqrt();
+        let r0 = radius(points.get(0));
+        assert!(r0 > 1.0);
+        assert!(points.iter().all(|p| (radius(p) - r0).abs() < 1e-2 * r0));
+
+        // Same result for the same graph, components packed apart
+        let mut edges = cycle.clone();
+        edges.extend(path.iter().map(|(a, b)| (a + 12, b + 12)));
+        let mut layout = Layout::from_position_graph(
+            edges,
+            crate::util::Nodes::Degree(22),
+            vec![0.0; 44],
+            Some(vec![1.0; 21]),
+            Default::default(),
+        );
+        layout.seed_spectral(&Default::default());
+        let again = spectral_layout(22, &layout.edges, Some(&[1.0; 21]), 2, &Default::default());
+        assert_eq!(layout.points.points, again.points);
+        let bbox = |nodes: std::ops::Range<usize>| {
+            nodes.fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |b, n| {
+                let p = layout.points.get(n);
+                [
+                    b[0].min(p[0]),
+                    b[1].min(p[1]),
+                    b[2].max(p[0]),
+                    b[3].max(p[1]),
+                ]
+            })
+        };
+        let (a, b) = (bbox(0..12), bbox(12..22));
+        assert!(a[2] < b[0] || b[2] < a[0] || a[3] < b[1] || b[3] < a[1]);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    forces::{attraction, gravity, repulsion},
+    layout::{Layout, LayoutType},
+};
+
+/// Convergence diagnostics of an iteration, over the nodes that are not fixed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IterationStats {
+    pub iteration: usize,
+    /// Sum of `|displacement|² / 2`, nodes having unit masses
+    pub kinetic_energy: f32,
+    pub max_displacement: f32,
+    pub avg_displacement: f32,
+    /// Sum of the squared forces, `0` at equilibrium
+    pub squared_force: f32,
+    /// Potential energy of the attraction, repulsion and gravity laws, which the layout
+    /// descends. Only computed when `history` is recorded, the repulsion being all-pairs.
+    pub potential_energy: f32,
+    /// ForceAtlas2. Global swinging: sum of `(mass + 1) * |speed - old_speed|`
+    pub swing: f32,
+    /// ForceAtlas2. Global effective traction: sum of `(mass + 1) * |speed + old_speed| / 2`
+    pub traction: f32,
+}
+
+impl IterationStats {
+    pub(crate) fn record_displacement(&mut self, distance: f32) {
+        self.kinetic_energy += distance * distance / 2.0;
+        self.max_displacement = self.max_displacement.max(distance);
+        self.avg_displacement += distance;
+    }
+}
+
+impl Layout {
+    /// Fills the statistics computed from the forces, before they move nodes
+    pub(crate) fn force_stats(&self, stats: &mut IterationStats) {
+        if self.history.is_some() {
+            stats.potential_energy = attraction::attraction_potential(self)
+                + repulsion::repulsion_potential(self)
+                + gravity::gravity_potential(self);
+        }
+        let forceatlas2 = matches!(self.settings.name, LayoutType::ForceAtlas2);
+        let fixed = self.fixed.as_deref();
+        for (n, (speed, old_speed)) in self.speeds.iter().zip(self.old_speeds.iter()).enumerate() {
+            if fixed.is_some_and(|fixed| fixed[n]) {
+                continue;
+            }
+            stats.squared_force += speed.iter().map(|s| s * s).sum::<f32>();
+            if forceatlas2 {
+                let mass = self.masses[n] + 1.0;
+                let (mut swinging, mut traction) = (0.0f32, 0.0f32);
+                for (s, old_s) in speed.iter().zip(old_speed.iter()) {
+                    swinging += (s - old_s).powi(2);
+                    traction += (s + old_s).powi(2);
+                }
+                stats.swing += mass * swinging.sqrt();
+                stats.traction += mass * traction.sqrt() / 2.0;
+            }
+        }
+    }
+}
+
+/// Whether the kinetic energy alternately rose and fell over the last `window` iterations
+/// of `history`
+pub fn is_oscillating(history: &[IterationStats], window: usize) -> bool {
+    if window < 3 || history.len() < window {
+        return false;
+    }
+    let last = &history[history.len() - window..];
+    let deltas: Vec<f32> = last
+        .windows(2)
+        .map(|w| w[1].kinetic_energy - w[0].kinetic_energy)
+        .collect();
+    deltas
+        .windows(2)
+        .all(|d| (d[0] > 0.0 && d[1] < 0.0) || (d[0] < 0.0 && d[1] > 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Settings;
+    use crate::util::{random_points, Nodes};
+
+    #[test]
+    fn test_history() {
+        let mut layout = Layout::from_position_graph(
+            vec![(0, 1), (1, 2), (2, 3), (3, 0)],
+            Nodes::Degree(5),
+            vec![0.0, 0.0, 5.0, 1.0, 2.0, 7.0, 9.0, 3.0, -4.0, 2.0],
+            None,
+            Settings {
+                chunk_size: None,
+                ..Default::default()
+            },
+        );
+        layout.fixed = Some(vec![false, false, false, false, true]);
+        layout.history = Some(Vec::new());
+        for i in 0..10 {
+            layout.iteration(i);
+        }
+
+        let history = layout.history.as_ref().unwrap();
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.last(), Some(&layout.stats));
+        for (i, stats) in history.iter().enumerate() {
+            assert_eq!(stats.iteration, i);
+            assert!(stats.max_displacement >= stats.avg_displacement);
+            assert!(stats.avg_displacement > 0.0);
+            assert!(stats.kinetic_energy > 0.0 && stats.squared_force > 0.0);
+            assert!(stats.traction > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_potential_energy() {
+        let points = random_points(30, 2, 100.0, 3);
+        let edges: Vec<(usize, usize)> = (0..30)
+            .flat_map(|n| vec![(n, (n + 1) % 30), (n, (n + 7) % 30)])
+            .collect();
+        for name in [LayoutType::ForceAtlas2, LayoutType::Fruchterman] {
+            let mut layout = Layout::from_position_graph(
+                edges.clone(),
+                Nodes::Degree(30),
+                points.points.clone(),
+                None,
+                Settings {
+                    name,
+                    chunk_size: None,
+                    ..Default::default()
+                },
+            );
+            layout.iteration(0);
+            assert_eq!(layout.stats.potential_energy, 0.0);
+
+            layout.history = Some(Vec::new());
+            for i in 0..100 {
+                layout.iteration(i);
+            }
+            let history = layout.history.as_ref().unwrap();
+            let first = history[0].potential_energy;
+            let last = history[99].potential_energy;
+            assert!(last < first, "{} not below {}", last, first);
+            assert!(history[90..]
+                .iter()
+                .all(|stats| stats.potential_energy < first));
+        }
+    }
+
+    #[test]
+    fn test_is_oscillating() {
+        let history = |energies: &[f32]| -> Vec<IterationStats> {
+            energies
+                .iter()
+                .map(|&kinetic_energy| IterationStats {
+                    kinetic_energy,
+                    ..Default::default()
+                })
+                .collect()
+        };
+        assert!(is_oscillating(&history(&[9.0, 1.0, 2.0, 1.0, 2.0, 1.0]), 5));
+        assert!(!is_oscillating(&history(&[5.0, 4.0, 3.0, 2.0, 1.0]), 5));
+        assert!(!is_oscillating(&history(&[1.0, 2.0, 1.0]), 5));
+    }
+}
@@ -0,0 +1,335 @@
+//! Stress majorization, after Gansner, Koren & North, "Graph Drawing by Stress Majorization".
+//!
+//! Stress sums `w_ij (|x_i - x_j| - d_ij)²` over pairs of nodes, `d_ij` being the length of
+//! the shortest path between them and `w_ij = d_ij⁻²`. Each iteration moves every node to
+//! the position minimizing the majorant of its own terms, which never increases stress.
+//!
+//! With pivots, the sparse stress model of Ortmann, Klimenta & Brandes only keeps the terms
+//! between neighbours and between each node and `k` pivots. A pivot term stands for the
+//! nodes of the pivot region, closer to that pivot than to the node, and is weighted by
+//! their number.
+
+use crate::distance::{all_shortest_paths, fill_unreachable, max_min_pivots, weighted_adjacency};
+use crate::layout::Layout;
+use crate::mds::{classical_mds, landmark_mds};
+use crate::util::{Edge, PointList};
+
+#[derive(Clone, Debug)]
+pub struct StressOptions {
+    pub dimensions: usize,
+    pub max_iterations: usize,
+    /// Stop once an iteration decreases stress by less than this ratio
+    pub tolerance: f32,
+    /// Approximate stress with this many pivots, instead of computing all pairs
+    pub pivots: Option<usize>,
+}
+
+impl Default for StressOptions {
+    fn default() -> Self {
+        Self {
+            dimensions: 2,
+            max_iterations: 500,
+            tolerance: 1e-4,
+            pivots: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StressReport {
+    pub iterations: usize,
+    /// Stress of the result, sparse stress with pivots
+    pub stress: f32,
+    /// Whether `tolerance` was reached before `max_iterations`
+    pub converged: bool,
+}
+
+/// Term `(j, d_ij, w_ij)` of the stress of a node
+pub(crate) type Term = (usize, f32, f32);
+
+pub(crate) fn full_terms(distances: &[Vec<f32>]) -> Vec<Vec<Term>> {
+    distances
+        .iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, d)| **d > 0.0)
+                .map(|(j, d)| (j, *d, 1.0 / (d * d)))
+                .collect()
+        })
+        .collect()
+}
+
+pub(crate) fn sparse_terms(
+    adjacency: &[Vec<(usize, f32)>],
+    pivots: &[usize],
+    rows: &[Vec<f32>],
+) -> Vec<Vec<Term>> {
+    let n = adjacency.len();
+    // Distances from each pivot to the nodes closer to it than to any other pivot, sorted
+    let mut region: Vec<Vec<f32>> = vec![Vec::new(); pivots.len()];
+    for i in 0..n {
+        let (closest, d) = rows
+            .iter()
+            .map(|row| row[i])
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        region[closest].push(d);
+    }
+    for distances in region.iter_mut() {
+        distances.sort_by(f32::total_cmp);
+    }
+
+    (0..n)
+        .map(|i| {
+            let neighbours = adjacency[i]
+                .iter()
+                .filter(|(j, d)| *j != i && *d > 0.0)
+                .map(|&(j, d)| (j, d, 1.0 / (d * d)));
+            // A pivot stands for the nodes of its region closer to it than to `i`
+            let far = pivots
+                .iter()
+                .zip(rows.iter().zip(region.iter()))
+                .filter(|(_, (row, _))| row[i] > 0.0)
+                .map(|(&p, (row, region))| {
+                    let d = row[i];
+                    let count = region.partition_point(|r| *r <= d / 2.0).max(1);
+                    (p, d, count as f32 / (d * d))
+                });
+            neighbours.chain(far).collect()
+        })
+        .collect()
+}
+
+pub(crate) fn stress(points: &PointList, terms: &[Vec<Term>]) -> f32 {
+    terms
+        .iter()
+        .enumerate()
+        .map(|(i, terms)| {
+            let pi = points.get(i);
+            terms
+                .iter()
+                .map(|&(j, d, w)| {
+                    let dist = pi
+                        .iter()
+                        .zip(points.get(j))
+                        .map(|(a, b)| (a - b) * (a - b))
+                        .sum::<f32>()
+                        .sqrt();
+                    w * (dist - d) * (dist - d)
+                })
+                .sum::<f32>()
+        })
+        .sum()
+}
+
+/// Lays out the graph of `n` nodes so that Euclidean distances match shortest path lengths
+///
+/// `lengths` are the lengths of the edges, `1` if `None`. Starts from `initial` positions,
+/// e.g. those of a `Layout`, or else from MDS: classical MDS of all pairs, or landmark MDS of
+/// the pivots. Nodes of different connected components are kept one more than the longest
+/// shortest path apart.
+pub fn stress_majorization(
+    n: usize,
+    edges: &[Edge],
+    lengths: Option<&[f32]>,
+    initial: Option<&PointList>,
+    options: &StressOptions,
+) -> (PointList, StressReport) {
+    majorize(n, edges, lengths, initial, None, options)
+}
+
+/// `stress_majorization`, the nodes of `fixed` staying at their `initial` positions
+fn majorize(
+    n: usize,
+    edges: &[Edge],
+    lengths: Option<&[f32]>,
+    initial: Option<&PointList>,
+    fixed: Option<&[bool]>,
+    options: &StressOptions,
+) -> (PointList, StressReport) {
+    let d = options.dimensions;
+    let adjacency = weighted_adjacency(n, edges, lengths);
+    let (terms, mds) = match options.pivots {
+        None => {
+            let mut distances = all_shortest_paths(&adjacency);
+            fill_unreachable(&mut distances);
+            let mds = initial.is_none().then(|| classical_mds(&distances, d));
+            (full_terms(&distances), mds)
+        }
+        Some(k) => {
+            let (pivots, mut rows) = max_min_pivots(&adjacency, k.max(1), 0);
+            fill_unreachable(&mut rows);
+            let mds = initial.is_none().then(|| landmark_mds(&pivots, &rows, d));
+            (sparse_terms(&adjacency, &pivots, &rows), mds)
+        }
+    };
+    let mut points = match (initial, mds) {
+        (Some(initial), _) => {
+            assert_eq!(initial.dimensions, d);
+            assert_eq!(initial.points.len(), n * d);
+            initial.clone()
+        }
+        (None, mds) => mds.unwrap(),
+    };
+
+    let mut current = stress(&points, &terms);
+    let mut report = StressReport {
+        iterations: 0,
+        stress: current,
+        converged: false,
+    };
+    let mut position = vec![0.0; d];
+    for iteration in 0..options.max_iterations {
+        for (i, terms) in terms.iter().enumerate() {
+            if terms.is_empty() || fixed.is_some_and(|fixed| fixed[i]) {
+                continue;
+            }
+            position.iter_mut().for_each(|x| *x = 0.0);
+            let mut total = 0.0;
+            let pi = points.get(i);
+            for &(j, dij, w) in terms.iter() {
+                let pj = points.get(j);
+                let dist = pi
+                    .iter()
+                    .zip(pj)
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f32>()
+                    .sqrt();
+                let pull = if dist > 0.0 { dij / dist } else { 0.0 };
+                for ((x, a), b) in position.iter_mut().zip(pi).zip(pj) {
+                    *x += w * (b + pull * (a - b));
+                }
+                total += w;
+            }
+            for (p, x) in points.get_mut(i).iter_mut().zip(position.iter()) {
+                *p = x / total;
+            }
+        }
+
+        let next = stress(&points, &terms);
+        report.iterations = iteration + 1;
+        report.stress = next;
+        if current - next <= options.tolerance * current {
+            report.converged = true;
+            break;
+        }
+        current = next;
+    }
+    (points, report)
+}
+
+impl Layout {
+    /// Moves the nodes by `stress_majorization` from their current positions, which should
+    /// not all coincide, e.g. after `seed_pivot_mds`
+    ///
+    /// Edges count as `1` long and `options.dimensions` is that of the layout. Fixed nodes
+    /// keep their positions, the others are placed around them.
+    pub fn seed_stress(&mut self, options: &StressOptions) -> StressReport {
+        let options = StressOptions {
+            dimensions: self.settings.dimensions,
+            ..options.clone()
+        };
+        let (points, report) = majorize(
+            self.masses.len(),
+            &self.edges,
+            None,
+            Some(&self.points),
+            self.fixed.as_deref(),
+            &options,
+        );
+        self.seed(&points);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::random_points;
+
+    fn distance(points: &PointList, i: usize, j: usize) -> f32 {
+        let (p, q) = (points.get(i), points.get(j));
+        ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2)).sqrt()
+    }
+
+    /// A 6 x 6 grid
+    fn grid() -> Vec<Edge> {
+        let mut grid = Vec::new();
+        for i in 0..6 {
+            for j in 0..6 {
+                if j < 5 {
+                    grid.push((i * 6 + j, i * 6 + j + 1));
+                }
+                if i < 5 {
+                    grid.push((i * 6 + j, i * 6 + j + 6));
+                }
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_stress_majorization_path() {
+        // A path lies straight, its nodes as far apart as along the path
+        let path: Vec<Edge> = (0..5).map(|i| (i, i + 1)).collect();
+        let (points, report) = stress_majorization(6, &path, None, None, &Default::default());
+        assert!(report.converged);
+        assert!(report.stress < 1e-3);
+        assert!((distance(&points, 0, 5) - 5.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_stress_majorization_initial() {
+        let initial = random_points(36, 2, 100.0, 36);
+        let (points, _) =
+            stress_majorization(36, &grid(), None, Some(&initial), &Default::default());
+        assert!((distance(&points, 0, 5) - 5.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_stress_majorization_pivots() {
+        let grid = grid();
+        let options = StressOptions {
+            pivots: Some(8),
+            ..Default::default()
+        };
+        let lengths = vec![2.0; grid.len()];
+        let (points, report) = stress_majorization(36, &grid, Some(&lengths), None, &options);
+        assert!(report.stress.is_finite());
+        assert!((distance(&points, 0, 5) - 10.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_seed_stress() {
+        let initial = random_points(36, 3, 10.0, 7);
+        let mut layout = Layout::from_position_graph(
+            grid(),
+            crate::util::Nodes::Degree(36),
+            initial.points.clone(),
+            None,
+            crate::layout::Settings {
+                dimensions: 3,
+                ..Default::default()
+            },
+        );
+        let mut fixed = vec![false; 36];
+        fixed[0] = true;
+        layout.fixed = Some(fixed);
+
+        let report = layout.seed_stress(&Default::default());
+        assert!(report.stress.is_finite());
+        assert_eq!(layout.points.get(0), initial.get(0));
+        let d = |i: usize, j: usize| {
+            let (p, q) = (layout.points.get(i), layout.points.get(j));
+            p.iter()
+                .zip(q)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        };
+        assert!((d(0, 5) - 5.0).abs() < 1.0);
+        assert!((d(0, 1) - 1.0).abs() < 0.5);
+    }
+}
@@ -0,0 +1,74 @@
+use super::{normalize, Affine, NormalizeOptions};
+use crate::{GraphConfig, GraphEdge, GraphNode};
+
+use graphlib_rust::Graph;
+
+/// Normalizes a graph laid out by `layout`, returning the map applied
+///
+/// The map is computed on the corners of the nodes and on the edge points. It moves nodes,
+/// edge points and edge labels, but does not resize nodes. The graph `width` and `height`
+/// are updated to the viewport, or to the new bounding box.
+pub fn normalize_dagre(
+    graph: &mut Graph<GraphConfig, GraphNode, GraphEdge>,
+    options: &NormalizeOptions,
+) -> Affine {
+    let mut plane: Vec<[f32; 2]> = Vec::new();
+    for v in graph.nodes() {
+        if let Some(node) = graph.node(&v) {
+            let (hw, hh) = (node.width / 2.0, node.height / 2.0);
+            plane.push([node.x - hw, node.y - hh]);
+            plane.push([node.x + hw, node.y + hh]);
+        }
+    }
+    for e in graph.edges() {
+        if let Some(points) = graph
+            .edge_with_obj(&e)
+            .and_then(|edge| edge.points.as_ref())
+        {
+            plane.extend(points.iter().map(|p| [p.x, p.y]));
+        }
+    }
+    if plane.is_empty() {
+        return Affine::identity();
+    }
+    let map = normalize(&plane, options);
+
+    let mut bbox = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+    let mut extend = |x: f32, y: f32, hw: f32, hh: f32| {
+        bbox = [
+            bbox[0].min(x - hw),
+            bbox[1].min(y - hh),
+            bbox[2].max(x + hw),
+            bbox[3].max(y + hh),
+        ];
+    };
+    for v in graph.nodes() {
+        if let Some(node) = graph.node_mut(&v) {
+            let [x, y] = map.apply([node.x, node.y]);
+            node.x = x;
+            node.y = y;
+            extend(x, y, node.width / 2.0, node.height / 2.0);
+        }
+    }
+    for e in graph.edges() {
+        if let Some(edge) = graph.edge_mut_with_obj(&e) {
+            let [x, y] = map.apply([edge.x, edge.y]);
+            edge.x = x;
+            edge.y = y;
+            for p in edge.points.iter_mut().flatten() {
+                let [x, y] = map.apply([p.x, p.y]);
+                p.x = x;
+                p.y = y;
+                extend(x, y, 0.0, 0.0);
+            }
+        }
+    }
+
+    let (width, height) = match &options.viewport {
+        Some(viewport) => (viewport.width, viewport.height),
+        None => (bbox[2] - bbox[0], bbox[3] - bbox[1]),
+    };
+    graph.graph_mut().width = width;
+    graph.graph_mut().height = height;
+    map
+}
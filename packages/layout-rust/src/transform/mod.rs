@@ -0,0 +1,289 @@
+//! Normalization of layout coordinates: rotation, flips, centering and viewport fitting.
+//!
+//! Transforms act in the plane of the first two coordinates; other coordinates are left
+//! untouched.
+
+mod dagre;
+
+pub use self::dagre::normalize_dagre;
+
+use crate::util::PointList;
+
+/// Affine map `p -> m · p + t` of the plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine {
+    pub m: [[f32; 2]; 2],
+    pub t: [f32; 2],
+}
+
+impl Default for Affine {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Affine {
+    pub fn identity() -> Self {
+        Self {
+            m: [[1.0, 0.0], [0.0, 1.0]],
+            t: [0.0, 0.0],
+        }
+    }
+
+    pub fn translate(dx: f32, dy: f32) -> Self {
+        Self {
+            t: [dx, dy],
+            ..Self::identity()
+        }
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Self {
+        Self {
+            m: [[sx, 0.0], [0.0, sy]],
+            t: [0.0, 0.0],
+        }
+    }
+
+    /// Counter-clockwise rotation of `angle` radians around the origin
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m: [[cos, -sin], [sin, cos]],
+            t: [0.0, 0.0],
+        }
+    }
+
+    /// `self`, then `other`
+    pub fn then(&self, other: &Affine) -> Self {
+        let (a, b) = (&other.m, &self.m);
+        Self {
+            m: [
+                [
+                    a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                    a[0][0] * b[0][1] + a[0][1] * b[1][1],
+                ],
+                [
+                    a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                    a[1][0] * b[0][1] + a[1][1] * b[1][1],
+                ],
+            ],
+            t: other.apply(self.t),
+        }
+    }
+
+    pub fn apply(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            self.m[0][0] * p[0] + self.m[0][1] * p[1] + self.t[0],
+            self.m[1][0] * p[0] + self.m[1][1] * p[1] + self.t[1],
+        ]
+    }
+
+    pub fn apply_points(&self, points: &mut PointList) {
+        for p in points.iter_mut() {
+            let [x, y] = self.apply([p[0], p[1]]);
+            p[0] = x;
+            p[1] = y;
+        }
+    }
+}
+
+/// Rectangle to fit a layout into
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    pub width: f32,
+    pub height: f32,
+    /// Margin kept on each side
+    pub padding: f32,
+    /// Scale both axes alike. Otherwise, the layout is stretched to fill the viewport.
+    pub preserve_aspect: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NormalizeOptions {
+    /// Rotate the principal axis of the layout horizontal
+    pub rotate: bool,
+    /// Flip each axis so that the layout leans the same way between runs
+    pub flip: bool,
+    /// Fit the layout into `[0, width] x [0, height]`, or center it on the origin if `None`
+    pub viewport: Option<Viewport>,
+}
+
+/// Centroid of `points`
+fn centroid(points: &[[f32; 2]]) -> [f32; 2] {
+    let n = points.len().max(1) as f32;
+    let (sx, sy) = points
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), p| (sx + p[0], sy + p[1]));
+    [sx / n, sy / n]
+}
+
+/// `[min_x, min_y, max_x, max_y]` of `points`
+fn bounding_box(points: &[[f32; 2]]) -> [f32; 4] {
+    points
+        .iter()
+        .fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |b, p| {
+            [
+                b[0].min(p[0]),
+                b[1].min(p[1]),
+                b[2].max(p[0]),
+                b[3].max(p[1]),
+            ]
+        })
+}
+
+/// Rotation around the centroid bringing the principal axis of `points` horizontal
+pub fn principal_axis(points: &[[f32; 2]]) -> Affine {
+    let c = centroid(points);
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for p in points.iter() {
+        let (dx, dy) = (p[0] - c[0], p[1] - c[1]);
+        sxx += dx * dx;
+        syy += dy * dy;
+        sxy += dx * dy;
+    }
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    Affine::translate(-c[0], -c[1])
+        .then(&Affine::rotate(-angle))
+        .then(&Affine::translate(c[0], c[1]))
+}
+
+/// Mirror around the centroid making the third moment of each axis non-negative
+///
+/// Layouts that differ by a flip are mapped to the same orientation.
+pub fn flip(points: &[[f32; 2]]) -> Affine {
+    let c = centroid(points);
+    let (mut mx, mut my) = (0.0, 0.0);
+    for p in points.iter() {
+        mx += (p[0] - c[0]).powi(3);
+        my += (p[1] - c[1]).powi(3);
+    }
+    let sx = if mx < 0.0 { -1.0 } else { 1.0 };
+    let sy = if my < 0.0 { -1.0 } else { 1.0 };
+    Affine::translate(-c[0], -c[1])
+        .then(&Affine::scale(sx, sy))
+        .then(&Affine::translate(c[0], c[1]))
+}
+
+/// Map fitting the bounding box of `points` into `viewport`, centered
+pub fn fit(points: &[[f32; 2]], viewport: &Viewport) -> Affine {
+    let b = bounding_box(points);
+    let (w, h) = (b[2] - b[0], b[3] - b[1]);
+    let inner_w = (viewport.width - 2.0 * viewport.padding).max(0.0);
+    let inner_h = (viewport.height - 2.0 * viewport.padding).max(0.0);
+    let scale_x = if w > 0.0 { inner_w / w } else { 1.0 };
+    let scale_y = if h > 0.0 { inner_h / h } else { 1.0 };
+    let (sx, sy) = if viewport.preserve_aspect {
+        let s = match (w > 0.0, h > 0.0) {
+            (true, true) => scale_x.min(scale_y),
+            (true, false) => scale_x,
+            (false, true) => scale_y,
+            (false, false) => 1.0,
+        };
+        (s, s)
+    } else {
+        (scale_x, scale_y)
+    };
+    Affine::translate(-(b[0] + b[2]) / 2.0, -(b[1] + b[3]) / 2.0)
+        .then(&Affine::scale(sx, sy))
+        .then(&Affine::translate(
+            viewport.width / 2.0,
+            viewport.height / 2.0,
+        ))
+}
+
+/// Composes the transforms of `options`, computed on `points`
+pub fn normalize(points: &[[f32; 2]], options: &NormalizeOptions) -> Affine {
+    let mut points = points.to_vec();
+    let mut map = Affine::identity();
+    let mut step = |step: Affine, points: &mut Vec<[f32; 2]>| {
+        for p in points.iter_mut() {
+            *p = step.apply(*p);
+        }
+        map = map.then(&step);
+    };
+
+    if options.rotate {
+        step(principal_axis(&points), &mut points);
+    }
+    if options.flip {
+        step(flip(&points), &mut points);
+    }
+    match &options.viewport {
+        Some(viewport) => step(fit(&points, viewport), &mut points),
+        None => {
+            let b = bounding_box(&points);
+            step(
+                Affine::translate(-(b[0] + b[2]) / 2.0, -(b[1] + b[3]) / 2.0),
+                &mut points,
+            )
+        }
+    }
+    map
+}
+
+/// Normalizes `points` in place, returning the map applied
+pub fn normalize_points(points: &mut PointList, options: &NormalizeOptions) -> Affine {
+    let plane: Vec<[f32; 2]> = points.iter().map(|p| [p[0], p[1]]).collect();
+    if plane.is_empty() {
+        return Affine::identity();
+    }
+    let map = normalize(&plane, options);
+    map.apply_points(points);
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_normalize() {
+        // A tilted, lopsided shape, and its mirror image
+        let shape: Vec<f32> = vec![0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 6.0, 6.0, 1.0, 2.0];
+        let mirror: Vec<f32> = shape.chunks(2).flat_map(|p| vec![-p[0], p[1]]).collect();
+        let options = NormalizeOptions {
+            rotate: true,
+            flip: true,
+            viewport: Some(Viewport {
+                width: 200.0,
+                height: 100.0,
+                padding: 10.0,
+                preserve_aspect: true,
+            }),
+        };
+
+        let mut a = PointList {
+            dimensions: 2,
+            points: shape,
+        };
+        let mut b = PointList {
+            dimensions: 2,
+            points: mirror,
+        };
+        normalize_points(&mut a, &options);
+        normalize_points(&mut b, &options);
+
+        let plane: Vec<[f32; 2]> = a.iter().map(|p| [p[0], p[1]]).collect();
+        let bbox = bounding_box(&plane);
+        assert!(close(bbox[0], 10.0) && close(bbox[2], 190.0));
+        assert!(bbox[1] >= 10.0 - 1e-3 && bbox[3] <= 90.0 + 1e-3);
+        // The principal axis is horizontal
+        assert!(bbox[2] - bbox[0] > 4.0 * (bbox[3] - bbox[1]));
+        // Node 0 on the short side of the skewed distribution, on the left
+        assert!(a.get(0)[0] < a.get(3)[0]);
+        for (p, q) in a.points.iter().zip(b.points.iter()) {
+            assert!(close(*p, *q));
+        }
+
+        let mut c = PointList {
+            dimensions: 3,
+            points: vec![1.0, 1.0, 7.0, 3.0, 5.0, 8.0],
+        };
+        normalize_points(&mut c, &NormalizeOptions::default());
+        assert_eq!(c.points, vec![-1.0, -2.0, 7.0, 1.0, 2.0, 8.0]);
+    }
+}
@@ -0,0 +1,535 @@
+//! Layouts of rooted trees: tidy tree, dendrogram, indented tree, mindmap and radial tree.
+//!
+//! Layouts are computed in a frame where the tree grows downwards: the depth of a node is
+//! its coordinate along the levels, its breadth the one across. `Direction` maps that frame
+//! to the plane, the root being at the origin. Positions are those of the centers of the
+//! nodes.
+
+mod nonlayered;
+mod radial;
+mod tidy;
+
+pub use self::radial::{radial_tree, RadialOptions};
+pub(crate) use self::tidy::tidy_breadth;
+
+use self::nonlayered::nonlayered_breadth;
+
+use crate::util::{adjacency, Edge, PointList};
+
+/// Where the tree grows from its root
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Top to bottom
+    TB,
+    /// Bottom to top
+    BT,
+    /// Left to right
+    LR,
+    /// Right to left
+    RL,
+    /// Both left and right: the first half of the children of the root on the right, the
+    /// other half on the left
+    H,
+    /// Both down and up: the first half of the children of the root below, the other half
+    /// above
+    V,
+}
+
+/// A rooted tree, with its children in order
+#[derive(Clone, Debug)]
+pub struct Tree {
+    pub root: usize,
+    pub children: Vec<Vec<usize>>,
+}
+
+impl Tree {
+    /// Tree of the `parents` of each node, `None` for the root
+    ///
+    /// Children are in the order of their indices. Panics unless there is exactly one root.
+    pub fn from_parents(parents: &[Option<usize>]) -> Self {
+        let mut children = vec![Vec::new(); parents.len()];
+        let mut roots = Vec::new();
+        for (v, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(p) => children[*p].push(v),
+                None => roots.push(v),
+            }
+        }
+        assert_eq!(roots.len(), 1, "a tree has a single root");
+        Self {
+            root: roots[0],
+            children,
+        }
+    }
+
+    /// Breadth-first search tree of the graph of `n` nodes, from `root`
+    ///
+    /// Children are in the order of `edges`. Nodes out of reach of `root` are out of the
+    /// tree.
+    pub fn from_edges(n: usize, edges: &[Edge], root: usize) -> Self {
+        let adjacency = adjacency(n, edges);
+        let mut children = vec![Vec::new(); n];
+        let mut seen = vec![false; n];
+        let mut queue = std::collections::VecDeque::new();
+        seen[root] = true;
+        queue.push_back(root);
+        while let Some(v) = queue.pop_front() {
+            for &w in adjacency[v].iter() {
+                if !seen[w] {
+                    seen[w] = true;
+                    children[v].push(w);
+                    queue.push_back(w);
+                }
+            }
+        }
+        Self { root, children }
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Parent of each node, `None` for the root and nodes out of the tree
+    pub fn parents(&self) -> Vec<Option<usize>> {
+        let mut parents = vec![None; self.len()];
+        for (v, kids) in self.children.iter().enumerate() {
+            for &w in kids.iter() {
+                parents[w] = Some(v);
+            }
+        }
+        parents
+    }
+
+    /// Nodes of the tree, each before its children
+    pub fn preorder(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack = vec![self.root];
+        while let Some(v) = stack.pop() {
+            order.push(v);
+            stack.extend(self.children[v].iter().rev());
+        }
+        order
+    }
+
+    /// Nodes of the tree, each after its children
+    pub fn postorder(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack = vec![(self.root, 0)];
+        while let Some((v, i)) = stack.pop() {
+            match self.children[v].get(i) {
+                Some(&w) => {
+                    stack.push((v, i + 1));
+                    stack.push((w, 0));
+                }
+                None => order.push(v),
+            }
+        }
+        order
+    }
+
+    /// Level of each node of the tree, `0` for the root
+    pub fn levels(&self) -> Vec<usize> {
+        let mut levels = vec![0; self.len()];
+        for v in self.preorder() {
+            for &w in self.children[v].iter() {
+                levels[w] = levels[v] + 1;
+            }
+        }
+        levels
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeOptions {
+    pub direction: Direction,
+    /// Width and height of the nodes without a size
+    pub node_size: (f32, f32),
+    /// Gap between adjacent siblings
+    pub sibling_gap: f32,
+    /// Gap between adjacent nodes of different parents
+    pub subtree_gap: f32,
+    /// Gap between two levels
+    pub level_gap: f32,
+    /// Gap between each level and the next, instead of `level_gap`
+    ///
+    /// Levels past the end of the list are `level_gap` apart.
+    pub level_gaps: Vec<f32>,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            direction: Direction::TB,
+            node_size: (10.0, 10.0),
+            sibling_gap: 10.0,
+            subtree_gap: 20.0,
+            level_gap: 40.0,
+            level_gaps: Vec::new(),
+        }
+    }
+}
+
+impl TreeOptions {
+    fn level_gap(&self, level: usize) -> f32 {
+        self.level_gaps
+            .get(level)
+            .cloned()
+            .unwrap_or(self.level_gap)
+    }
+
+    fn is_horizontal(&self) -> bool {
+        matches!(self.direction, Direction::LR | Direction::RL | Direction::H)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Tidy,
+    Dendrogram,
+    Indented,
+    Mindmap,
+}
+
+/// Extents of the nodes across and along the levels
+fn extents(n: usize, sizes: Option<&[(f32, f32)]>, options: &TreeOptions) -> Vec<(f32, f32)> {
+    (0..n)
+        .map(|v| {
+            let (w, h) = sizes.map_or(options.node_size, |sizes| sizes[v]);
+            if options.is_horizontal() {
+                (h, w)
+            } else {
+                (w, h)
+            }
+        })
+        .collect()
+}
+
+/// Depth of the center of each level, levels being as deep as their deepest node
+fn level_depths(tree: &Tree, levels: &[usize], depths: &[f32], options: &TreeOptions) -> Vec<f32> {
+    let mut deepest: Vec<f32> = Vec::new();
+    for v in tree.preorder() {
+        if deepest.len() <= levels[v] {
+            deepest.resize(levels[v] + 1, 0.0);
+        }
+        deepest[levels[v]] = deepest[levels[v]].max(depths[v]);
+    }
+    let mut centers = vec![0.0; deepest.len()];
+    for l in 1..deepest.len() {
+        centers[l] =
+            centers[l - 1] + (deepest[l - 1] + deepest[l]) / 2.0 + options.level_gap(l - 1);
+    }
+    centers
+}
+
+/// Breadth and depth of each node of `tree` in the downwards frame
+fn layout_frame(
+    tree: &Tree,
+    extents: &[(f32, f32)],
+    kind: Kind,
+    options: &TreeOptions,
+) -> (Vec<f32>, Vec<f32>) {
+    let n = tree.len();
+    let breadths: Vec<f32> = extents.iter().map(|e| e.0).collect();
+    let depths: Vec<f32> = extents.iter().map(|e| e.1).collect();
+    let levels = tree.levels();
+    let parents = tree.parents();
+    let gap = |u: usize, v: usize| {
+        if parents[u] == parents[v] {
+            options.sibling_gap
+        } else {
+            options.subtree_gap
+        }
+    };
+
+    let mut breadth = vec![0.0; n];
+    let mut depth = vec![0.0; n];
+    match kind {
+        Kind::Tidy => {
+            breadth = tidy_breadth(tree, &breadths, options.sibling_gap, options.subtree_gap);
+        }
+        Kind::Dendrogram => {
+            // Leaves side by side, each parent in the middle of its children
+            let mut previous: Option<usize> = None;
+            for v in tree.preorder() {
+                if tree.children[v].is_empty() {
+                    breadth[v] = previous.map_or(0.0, |u| {
+                        breadth[u] + (breadths[u] + breadths[v]) / 2.0 + gap(u, v)
+                    });
+                    previous = Some(v);
+                }
+            }
+            for v in tree.postorder() {
+                if let (Some(first), Some(last)) =
+                    (tree.children[v].first(), tree.children[v].last())
+                {
+                    breadth[v] = (breadth[*first] + breadth[*last]) / 2.0;
+                }
+            }
+        }
+        Kind::Indented => {
+            // A node on each row, in depth-first order
+            let mut previous: Option<usize> = None;
+            for v in tree.preorder() {
+                breadth[v] = previous.map_or(0.0, |u| {
+                    breadth[u] + (breadths[u] + breadths[v]) / 2.0 + options.sibling_gap
+                });
+                previous = Some(v);
+            }
+        }
+        // Depends on the depths, placed below
+        Kind::Mindmap => {}
+    }
+
+    match kind {
+        Kind::Tidy | Kind::Dendrogram => {
+            let centers = level_depths(tree, &levels, &depths, options);
+            let bottom = centers.len() - 1;
+            for v in tree.preorder() {
+                let leaf = kind == Kind::Dendrogram && tree.children[v].is_empty();
+                depth[v] = centers[if leaf { bottom } else { levels[v] }];
+            }
+        }
+        Kind::Indented => {
+            // The near side of each node is past the near side of its parent by the gap
+            for v in tree.preorder() {
+                if let Some(p) = parents[v] {
+                    let near = depth[p] - depths[p] / 2.0 + options.level_gap(levels[p]);
+                    depth[v] = near + depths[v] / 2.0;
+                }
+            }
+        }
+        Kind::Mindmap => {
+            // Each node right after its parent, whatever the depths of the other nodes
+            for v in tree.preorder() {
+                if let Some(p) = parents[v] {
+                    depth[v] =
+                        depth[p] + (depths[p] + depths[v]) / 2.0 + options.level_gap(levels[p]);
+                }
+            }
+            // Levels are not aligned, nodes only clear those they share depths with
+            breadth = nonlayered_breadth(
+                tree,
+                &breadths,
+                &depth,
+                &depths,
+                options.sibling_gap,
+                options.subtree_gap,
+            );
+        }
+    }
+    (breadth, depth)
+}
+
+fn layout(
+    tree: &Tree,
+    sizes: Option<&[(f32, f32)]>,
+    kind: Kind,
+    options: &TreeOptions,
+) -> PointList {
+    let n = tree.len();
+    if let Some(sizes) = sizes {
+        assert_eq!(sizes.len(), n);
+    }
+    let extents = extents(n, sizes, options);
+
+    // Subtrees of the root on each side, with the sign of their depths
+    let kids = &tree.children[tree.root];
+    let sides = match options.direction {
+        Direction::H | Direction::V => {
+            let half = kids.len().div_ceil(2);
+            vec![(kids[..half].to_vec(), 1.0), (kids[half..].to_vec(), -1.0)]
+        }
+        Direction::BT | Direction::RL => vec![(kids.clone(), -1.0)],
+        Direction::TB | Direction::LR => vec![(kids.clone(), 1.0)],
+    };
+
+    let mut points = PointList {
+        dimensions: 2,
+        points: vec![0.0; n * 2],
+    };
+    for (kids, sign) in sides {
+        let mut side = Tree {
+            root: tree.root,
+            children: tree.children.clone(),
+        };
+        side.children[tree.root] = kids;
+        let (breadth, depth) = layout_frame(&side, &extents, kind, options);
+        let offset = breadth[tree.root];
+        for v in side.preorder() {
+            let (b, d) = (breadth[v] - offset, sign * depth[v]);
+            let p = points.get_mut(v);
+            if options.is_horizontal() {
+                p.copy_from_slice(&[d, b]);
+            } else {
+                p.copy_from_slice(&[b, d]);
+            }
+        }
+    }
+    points
+}
+
+/// Tidy tree: levels are aligned, subtrees as close as they can be, each parent centered
+/// over its children, by Walker's algorithm
+///
+/// `sizes` are the widths and heights of the nodes, `options.node_size` if `None`. Nodes out
+/// of the tree stay at the origin.
+pub fn tidy_tree(tree: &Tree, sizes: Option<&[(f32, f32)]>, options: &TreeOptions) -> PointList {
+    layout(tree, sizes, Kind::Tidy, options)
+}
+
+/// Dendrogram: leaves are side by side on the last level, each parent centered over its
+/// children
+pub fn dendrogram(tree: &Tree, sizes: Option<&[(f32, f32)]>, options: &TreeOptions) -> PointList {
+    layout(tree, sizes, Kind::Dendrogram, options)
+}
+
+/// Indented tree: a node on each row in depth-first order, indented from its parent by the
+/// gap of its level
+///
+/// Rows are `sibling_gap` apart. Indented trees usually grow `LR`.
+pub fn indented_tree(
+    tree: &Tree,
+    sizes: Option<&[(f32, f32)]>,
+    options: &TreeOptions,
+) -> PointList {
+    layout(tree, sizes, Kind::Indented, options)
+}
+
+/// Mindmap: a tidy tree where each node is right after its parent, instead of aligned on
+/// its level
+///
+/// Nodes only keep clear of those they share depths with, whatever their levels.
+///
+/// Mindmaps usually grow `H`, on both sides of the root.
+pub fn mindmap(tree: &Tree, sizes: Option<&[(f32, f32)]>, options: &TreeOptions) -> PointList {
+    layout(tree, sizes, Kind::Mindmap, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_layouts() {
+        //      0
+        //   1     2
+        //  3 4    5
+        //         6
+        let tree =
+            Tree::from_parents(&[None, Some(0), Some(0), Some(1), Some(1), Some(2), Some(5)]);
+        assert_eq!(tree.preorder(), vec![0, 1, 3, 4, 2, 5, 6]);
+        assert_eq!(tree.postorder(), vec![3, 4, 1, 6, 5, 2, 0]);
+        let from_edges = Tree::from_edges(7, &[(0, 1), (2, 0), (1, 3), (4, 1), (5, 2), (6, 5)], 0);
+        assert_eq!(from_edges.children, tree.children);
+
+        let options = TreeOptions::default();
+        let points = tidy_tree(&tree, None, &options);
+        assert_eq!(points.get(0), &[0.0, 0.0]);
+        // Levels are 10 deep and 40 apart, siblings 20 apart center to center
+        assert_eq!(points.get(3)[1], 100.0);
+        assert_eq!(points.get(4)[0] - points.get(3)[0], 20.0);
+        assert_eq!(
+            points.get(1)[0],
+            (points.get(3)[0] + points.get(4)[0]) / 2.0
+        );
+        assert_eq!(points.get(2)[0], points.get(5)[0]);
+
+        // Other directions swap and flip the axes
+        let lr = tidy_tree(
+            &tree,
+            None,
+            &TreeOptions {
+                direction: Direction::LR,
+                ..Default::default()
+            },
+        );
+        let bt = tidy_tree(
+            &tree,
+            None,
+            &TreeOptions {
+                direction: Direction::BT,
+                ..Default::default()
+            },
+        );
+        for (v, p) in points.iter().enumerate() {
+            assert_eq!(lr.get(v), &[p[1], p[0]]);
+            assert_eq!(bt.get(v), &[p[0], -p[1]]);
+        }
+
+        // Per-level gaps and sizes
+        let sizes = vec![(10.0, 30.0); 7];
+        let options = TreeOptions {
+            level_gaps: vec![5.0],
+            ..Default::default()
+        };
+        let points = tidy_tree(&tree, Some(&sizes), &options);
+        assert_eq!(points.get(1)[1], 35.0);
+        assert_eq!(points.get(3)[1], 105.0);
+
+        // The leaves of a dendrogram are on the last level
+        let points = dendrogram(&tree, None, &Default::default());
+        for leaf in [3, 4, 6] {
+            assert_eq!(points.get(leaf)[1], 150.0);
+        }
+        assert_eq!(points.get(5)[1], 100.0);
+        assert_eq!(points.get(4)[0] - points.get(3)[0], 20.0);
+        assert_eq!(points.get(6)[0] - points.get(4)[0], 30.0);
+
+        // An indented tree has a row for each node
+        let options = TreeOptions {
+            direction: Direction::LR,
+            level_gap: 15.0,
+            ..Default::default()
+        };
+        let points = indented_tree(&tree, None, &options);
+        let rows: Vec<f32> = tree.preorder().iter().map(|v| points.get(*v)[1]).collect();
+        assert_eq!(rows, vec![0.0, 20.0, 40.0, 60.0, 80.0, 100.0, 120.0]);
+        assert_eq!(points.get(3)[0], 30.0);
+
+        // A mindmap splits the children of the root on both sides
+        let sizes = vec![
+            (10.0, 10.0),
+            (50.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 10.0),
+        ];
+        let options = TreeOptions {
+            direction: Direction::H,
+            ..Default::default()
+        };
+        let points = mindmap(&tree, Some(&sizes), &options);
+        assert_eq!(points.get(1), &[70.0, 0.0]);
+        assert_eq!(points.get(3)[0], 140.0);
+        assert_eq!(points.get(2), &[-50.0, 0.0]);
+        assert_eq!(points.get(6)[0], -150.0);
+    }
+
+    #[test]
+    fn test_mindmap_unequal_sizes() {
+        //    0
+        //  1   2
+        //      3
+        // 1 is deep enough to share depths with 3, which is wide
+        let tree = Tree::from_parents(&[None, Some(0), Some(0), Some(2)]);
+        let sizes = vec![(10.0, 10.0), (10.0, 60.0), (10.0, 10.0), (80.0, 10.0)];
+        let points = mindmap(&tree, Some(&sizes), &Default::default());
+        for u in 0..4 {
+            for v in u + 1..4 {
+                let (p, q) = (points.get(u), points.get(v));
+                let apart_x = (p[0] - q[0]).abs() >= (sizes[u].0 + sizes[v].0) / 2.0;
+                let apart_y = (p[1] - q[1]).abs() >= (sizes[u].1 + sizes[v].1) / 2.0;
+                assert!(apart_x || apart_y, "{} and {} overlap", u, v);
+            }
+        }
+        // 3 is right below 2, and clears 1 by the gap between subtrees
+        assert_eq!(points.get(3)[0], points.get(2)[0]);
+        assert_eq!(points.get(3)[0] - points.get(1)[0], 5.0 + 40.0 + 20.0);
+    }
+}
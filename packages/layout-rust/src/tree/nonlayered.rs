@@ -0,0 +1,176 @@
+//! Tidy tree positions when the nodes of a level are not aligned, as in a mindmap.
+//!
+//! Each subtree is summed up by its left and right contours: for each band of depths, the
+//! outermost edge of its nodes in that band and the node it belongs to. Going up the tree,
+//! the subtrees of the children are placed left to right, each as close as its contour
+//! allows to the contour of those on its left, and the parent is centered over them.
+
+use super::Tree;
+
+/// Outermost edge of a subtree over the depths `top..bottom`
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    top: f32,
+    bottom: f32,
+    edge: f32,
+    node: usize,
+}
+
+/// Left and right contours of a subtree, sorted by depth, relative to its root
+#[derive(Clone, Debug, Default)]
+struct Contour {
+    left: Vec<Segment>,
+    right: Vec<Segment>,
+}
+
+fn shifted(segments: &[Segment], shift: f32) -> Vec<Segment> {
+    segments
+        .iter()
+        .map(|s| Segment {
+            edge: s.edge + shift,
+            ..*s
+        })
+        .collect()
+}
+
+/// `front` where it is defined, `back` elsewhere
+fn overlay(front: &[Segment], back: &[Segment]) -> Vec<Segment> {
+    let mut merged = front.to_vec();
+    for b in back.iter() {
+        let mut top = b.top;
+        for f in front
+            .iter()
+            .filter(|f| f.bottom > b.top && f.top < b.bottom)
+        {
+            if f.top > top {
+                merged.push(Segment {
+                    top,
+                    bottom: f.top,
+                    ..*b
+                });
+            }
+            top = top.max(f.bottom);
+        }
+        if top < b.bottom {
+            merged.push(Segment { top, ..*b });
+        }
+    }
+    merged.sort_by(|s, t| s.top.partial_cmp(&t.top).unwrap());
+    merged
+}
+
+/// Position across levels of each node of `tree`, the root being at `0`
+///
+/// `depth` is the position of each node along the levels, `extents` and `depths` its size
+/// across and along them. Nodes whose depths overlap are at least `sibling_gap` apart when
+/// siblings, `subtree_gap` otherwise.
+pub(crate) fn nonlayered_breadth(
+    tree: &Tree,
+    extents: &[f32],
+    depth: &[f32],
+    depths: &[f32],
+    sibling_gap: f32,
+    subtree_gap: f32,
+) -> Vec<f32> {
+    let n = tree.len();
+    let parents = tree.parents();
+    let gap = |u: usize, v: usize| {
+        if parents[u] == parents[v] {
+            sibling_gap
+        } else {
+            subtree_gap
+        }
+    };
+
+    // Position of each node relative to its parent
+    let mut relative = vec![0.0; n];
+    let mut contours = vec![Contour::default(); n];
+    for v in tree.postorder() {
+        let kids = &tree.children[v];
+        let mut forest = Contour::default();
+        let mut offsets = Vec::with_capacity(kids.len());
+        for &w in kids.iter() {
+            let contour = std::mem::take(&mut contours[w]);
+            // Both contours are sorted by depth, a sweep finds the overlapping segments
+            let mut offset = f32::NEG_INFINITY;
+            let (mut i, mut j) = (0, 0);
+            while let (Some(r), Some(l)) = (forest.right.get(i), contour.left.get(j)) {
+                if r.top < l.bottom && l.top < r.bottom {
+                    offset = offset.max(r.edge - l.edge + gap(r.node, l.node));
+                }
+                if r.bottom < l.bottom {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            let offset = if offset.is_finite() { offset } else { 0.0 };
+            forest.left = overlay(&forest.left, &shifted(&contour.left, offset));
+            forest.right = overlay(&shifted(&contour.right, offset), &forest.right);
+            offsets.push(offset);
+        }
+
+        let middle = match (offsets.first(), offsets.last()) {
+            (Some(first), Some(last)) => (first + last) / 2.0,
+            _ => 0.0,
+        };
+        for (&w, offset) in kids.iter().zip(offsets.iter()) {
+            relative[w] = offset - middle;
+        }
+        let own = |edge: f32| Segment {
+            top: depth[v] - depths[v] / 2.0,
+            bottom: depth[v] + depths[v] / 2.0,
+            edge,
+            node: v,
+        };
+        contours[v] = Contour {
+            left: overlay(&[own(-extents[v] / 2.0)], &shifted(&forest.left, -middle)),
+            right: overlay(&[own(extents[v] / 2.0)], &shifted(&forest.right, -middle)),
+        };
+    }
+
+    let mut breadth = vec![0.0; n];
+    for v in tree.preorder() {
+        if let Some(p) = parents[v] {
+            breadth[v] = breadth[p] + relative[v];
+        }
+    }
+    breadth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonlayered_breadth() {
+        //      0
+        //   1     2
+        //  3 4    5
+        // Node 1 is deep, so its children are further down than the one of 2
+        let tree = Tree::from_parents(&[None, Some(0), Some(0), Some(1), Some(1), Some(2)]);
+        let extents = vec![10.0, 10.0, 10.0, 40.0, 10.0, 60.0];
+        let depths = vec![10.0, 50.0, 10.0, 10.0, 10.0, 10.0];
+        let mut depth = vec![0.0; 6];
+        for v in tree.preorder() {
+            for &w in tree.children[v].iter() {
+                depth[w] = depth[v] + (depths[v] + depths[w]) / 2.0 + 10.0;
+            }
+        }
+        let x = nonlayered_breadth(&tree, &extents, &depth, &depths, 1.0, 2.0);
+
+        assert_eq!(x[0], 0.0);
+        assert!(((x[3] + x[4]) / 2.0 - x[1]).abs() < 1e-4);
+        assert!(((x[1] + x[2]) / 2.0 - x[0]).abs() < 1e-4);
+        // No two nodes overlap
+        for u in 0..6 {
+            for v in u + 1..6 {
+                let apart_across = (x[u] - x[v]).abs() >= (extents[u] + extents[v]) / 2.0;
+                let apart_along = (depth[u] - depth[v]).abs() >= (depths[u] + depths[v]) / 2.0;
+                assert!(apart_across || apart_along, "{} and {} overlap", u, v);
+            }
+        }
+        // 5 only needs to clear 1, not 3 and 4 which are further down
+        assert!((x[5] - x[1] - (60.0 + 10.0) / 2.0 - 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,175 @@
+//! Radial tree: levels on concentric circles around the root.
+//!
+//! Leaves are spread over the angular range in preorder, further apart when they are not
+//! siblings, and each node is in the middle of the span of its leaves: wedges are
+//! proportional to the number of leaves they hold.
+
+use super::Tree;
+use crate::util::PointList;
+
+use std::f32::consts::PI;
+
+#[derive(Clone, Debug)]
+pub struct RadialOptions {
+    /// Distance between two levels
+    pub level_radius: f32,
+    /// Radius of each level below the root, instead of `level_radius` further than the
+    /// previous one
+    pub radii: Vec<f32>,
+    /// Angular range of the leaves, in radians, counterclockwise from the x axis
+    pub start_angle: f32,
+    pub end_angle: f32,
+    /// Separation of adjacent sibling leaves, leaves sharing the angular range in proportion
+    pub sibling_separation: f32,
+    /// Separation of adjacent leaves of different parents
+    pub subtree_separation: f32,
+}
+
+impl Default for RadialOptions {
+    fn default() -> Self {
+        Self {
+            level_radius: 100.0,
+            radii: Vec::new(),
+            start_angle: 0.0,
+            end_angle: 2.0 * PI,
+            sibling_separation: 1.0,
+            subtree_separation: 2.0,
+        }
+    }
+}
+
+/// Lays out `tree` on concentric circles around its root, at the origin
+///
+/// For the tree of a graph, see `Tree::from_edges`. Nodes out of the tree stay at the
+/// origin.
+pub fn radial_tree(tree: &Tree, options: &RadialOptions) -> PointList {
+    let n = tree.len();
+    let levels = tree.levels();
+    let parents = tree.parents();
+    let order = tree.preorder();
+
+    // Leaves one after another, with the separations between them
+    let mut position = vec![0.0f32; n];
+    let leaves: Vec<usize> = order
+        .iter()
+        .cloned()
+        .filter(|v| tree.children[*v].is_empty())
+        .collect();
+    let separation = |u: usize, v: usize| {
+        if parents[u] == parents[v] {
+            options.sibling_separation
+        } else {
+            options.subtree_separation
+        }
+    };
+    for w in leaves.windows(2) {
+        position[w[1]] = position[w[0]] + separation(w[0], w[1]);
+    }
+    let range = options.end_angle - options.start_angle;
+    let mut total = leaves.last().map_or(0.0, |v| position[*v]);
+    if range.abs() >= 2.0 * PI - 1e-4 && leaves.len() > 1 {
+        // Around the circle, the last leaf is followed by the first one
+        total += separation(leaves[leaves.len() - 1], leaves[0]);
+    }
+    // First and last leaves of each subtree
+    let mut spans: Vec<(usize, usize)> = (0..n).map(|v| (v, v)).collect();
+    for v in tree.postorder() {
+        if let (Some(first), Some(last)) = (tree.children[v].first(), tree.children[v].last()) {
+            spans[v] = (spans[*first].0, spans[*last].1);
+            position[v] = (position[spans[v].0] + position[spans[v].1]) / 2.0;
+        }
+    }
+
+    let mut radii = vec![0.0f32; levels.iter().max().map_or(1, |l| l + 1)];
+    for l in 1..radii.len() {
+        radii[l] = options
+            .radii
+            .get(l - 1)
+            .cloned()
+            .unwrap_or(radii[l - 1] + options.level_radius);
+    }
+
+    let mut points = PointList {
+        dimensions: 2,
+        points: vec![0.0; n * 2],
+    };
+    for v in order {
+        if v == tree.root {
+            continue;
+        }
+        let angle = if total > 0.0 {
+            options.start_angle + range * position[v] / total
+        } else {
+            options.start_angle
+        };
+        let r = radii[levels[v]];
+        points
+            .get_mut(v)
+            .copy_from_slice(&[r * angle.cos(), r * angle.sin()]);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_radial_tree() {
+        let angle = |points: &PointList, v: usize| {
+            let p = points.get(v);
+            p[1].atan2(p[0]).rem_euclid(2.0 * PI)
+        };
+        let radius = |points: &PointList, v: usize| {
+            let p = points.get(v);
+            (p[0] * p[0] + p[1] * p[1]).sqrt()
+        };
+
+        // A star splits the circle evenly
+        let star = Tree::from_edges(5, &[(0, 1), (0, 2), (0, 3), (0, 4)], 0);
+        let points = radial_tree(&star, &Default::default());
+        assert_eq!(points.get(0), &[0.0, 0.0]);
+        for v in 1..5 {
+            assert!((radius(&points, v) - 100.0).abs() < 1e-3);
+            assert!((angle(&points, v) - (v - 1) as f32 * PI / 2.0).abs() < 1e-4);
+        }
+
+        //     0
+        //   1   2
+        //  3 4  5
+        let tree = Tree::from_parents(&[None, Some(0), Some(0), Some(1), Some(1), Some(2)]);
+        let options = RadialOptions {
+            radii: vec![10.0],
+            start_angle: 0.0,
+            end_angle: PI,
+            ..Default::default()
+        };
+        let points = radial_tree(&tree, &options);
+        assert!((radius(&points, 1) - 10.0).abs() < 1e-4);
+        assert!((radius(&points, 3) - 110.0).abs() < 1e-4);
+        // Leaves at 0, 1 and 3 separations over a half circle, parents in their middle
+        assert!(angle(&points, 3) < 1e-4);
+        assert!((angle(&points, 4) - PI / 3.0).abs() < 1e-4);
+        assert!((angle(&points, 5) - PI).abs() < 1e-4);
+        assert!((angle(&points, 1) - PI / 6.0).abs() < 1e-4);
+        assert!((angle(&points, 2) - PI).abs() < 1e-4);
+
+        //   0
+        //   1
+        //  2  3
+        // 456
+        // 1 is in the middle of its leaves 4 to 3, not of its children 2 and 3
+        let tree =
+            Tree::from_parents(&[None, Some(0), Some(1), Some(1), Some(2), Some(2), Some(2)]);
+        let options = RadialOptions {
+            start_angle: 0.0,
+            end_angle: PI,
+            ..Default::default()
+        };
+        let points = radial_tree(&tree, &options);
+        // Leaves at 0, 1, 2 and 4 separations
+        assert!((angle(&points, 3) - PI).abs() < 1e-4);
+        assert!((angle(&points, 2) - PI / 4.0).abs() < 1e-4);
+        assert!((angle(&points, 1) - PI / 2.0).abs() < 1e-4);
+    }
+}
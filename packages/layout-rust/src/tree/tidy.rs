@@ -0,0 +1,238 @@
+//! Tidy tree positions, by Walker's algorithm in linear time after Buchheim, Jünger &
+//! Leipert, "Improving Walker's Algorithm to Run in Linear Time".
+//!
+//! The first walk goes up the tree: it places each subtree as close as possible to the
+//! subtrees on its left, following their contours by threads, and spreads the shift over
+//! the subtrees in between. The second walk goes down, summing the modifiers.
+
+use super::Tree;
+
+struct Walker<'a> {
+    children: &'a [Vec<usize>],
+    parents: &'a [Option<usize>],
+    /// Extent of each node across levels
+    extents: &'a [f32],
+    sibling_gap: f32,
+    subtree_gap: f32,
+    /// Index of each node among its siblings
+    number: Vec<usize>,
+    prelim: Vec<f32>,
+    modifier: Vec<f32>,
+    shift: Vec<f32>,
+    change: Vec<f32>,
+    thread: Vec<Option<usize>>,
+    ancestor: Vec<usize>,
+}
+
+impl<'a> Walker<'a> {
+    /// Distance between the centers of `v` and `w`, next to each other on a level
+    fn distance(&self, v: usize, w: usize) -> f32 {
+        let gap = if self.parents[v] == self.parents[w] {
+            self.sibling_gap
+        } else {
+            self.subtree_gap
+        };
+        (self.extents[v] + self.extents[w]) / 2.0 + gap
+    }
+
+    fn left_sibling(&self, v: usize) -> Option<usize> {
+        match (self.parents[v], self.number[v]) {
+            (Some(p), i) if i > 0 => Some(self.children[p][i - 1]),
+            _ => None,
+        }
+    }
+
+    fn next_left(&self, v: usize) -> Option<usize> {
+        self.children[v].first().cloned().or(self.thread[v])
+    }
+
+    fn next_right(&self, v: usize) -> Option<usize> {
+        self.children[v].last().cloned().or(self.thread[v])
+    }
+
+    fn first_walk(&mut self, v: usize) {
+        let left = self.left_sibling(v);
+        let kids = &self.children[v];
+        if kids.is_empty() {
+            self.prelim[v] = left.map_or(0.0, |w| self.prelim[w] + self.distance(w, v));
+        } else {
+            let (first, last) = (kids[0], kids[kids.len() - 1]);
+            self.execute_shifts(v);
+            let midpoint = (self.prelim[first] + self.prelim[last]) / 2.0;
+            match left {
+                Some(w) => {
+                    self.prelim[v] = self.prelim[w] + self.distance(w, v);
+                    self.modifier[v] = self.prelim[v] - midpoint;
+                }
+                None => self.prelim[v] = midpoint,
+            }
+        }
+    }
+
+    /// Moves the subtree of `v` clear of the subtrees of its left siblings, returning the
+    /// new default ancestor
+    fn apportion(&mut self, v: usize, default_ancestor: usize) -> usize {
+        let w = match self.left_sibling(v) {
+            Some(w) => w,
+            None => return default_ancestor,
+        };
+        let mut default_ancestor = default_ancestor;
+        let parent = self.parents[v].unwrap();
+        // Inside and outside contours, on the right (p) and on the left (m)
+        let (mut vip, mut vop) = (v, v);
+        let (mut vim, mut vom) = (w, self.children[parent][0]);
+        let (mut sip, mut sop) = (self.modifier[vip], self.modifier[vop]);
+        let (mut sim, mut som) = (self.modifier[vim], self.modifier[vom]);
+        while let (Some(right), Some(left)) = (self.next_right(vim), self.next_left(vip)) {
+            vim = right;
+            vip = left;
+            vom = self.next_left(vom).unwrap();
+            vop = self.next_right(vop).unwrap();
+            self.ancestor[vop] = v;
+            let shift =
+                (self.prelim[vim] + sim) - (self.prelim[vip] + sip) + self.distance(vim, vip);
+            if shift > 0.0 {
+                let a = if self.parents[self.ancestor[vim]] == Some(parent) {
+                    self.ancestor[vim]
+                } else {
+                    default_ancestor
+                };
+                self.move_subtree(a, v, shift);
+                sip += shift;
+                sop += shift;
+            }
+            sim += self.modifier[vim];
+            sip += self.modifier[vip];
+            som += self.modifier[vom];
+            sop += self.modifier[vop];
+        }
+        if let (Some(right), None) = (self.next_right(vim), self.next_right(vop)) {
+            self.thread[vop] = Some(right);
+            self.modifier[vop] += sim - sop;
+        }
+        if let (Some(left), None) = (self.next_left(vip), self.next_left(vom)) {
+            self.thread[vom] = Some(left);
+            self.modifier[vom] += sip - som;
+            default_ancestor = v;
+        }
+        default_ancestor
+    }
+
+    fn move_subtree(&mut self, wm: usize, wp: usize, shift: f32) {
+        let subtrees = (self.number[wp] - self.number[wm]) as f32;
+        self.change[wp] -= shift / subtrees;
+        self.shift[wp] += shift;
+        self.change[wm] += shift / subtrees;
+        self.prelim[wp] += shift;
+        self.modifier[wp] += shift;
+    }
+
+    fn execute_shifts(&mut self, v: usize) {
+        let (mut shift, mut change) = (0.0, 0.0);
+        for &w in self.children[v].iter().rev() {
+            self.prelim[w] += shift;
+            self.modifier[w] += shift;
+            change += self.change[w];
+            shift += self.shift[w] + change;
+        }
+    }
+}
+
+/// Position across levels of each node of `tree`, the root being at `0`
+///
+/// `extents` are those of the nodes across levels. Adjacent nodes of a level are at least
+/// `sibling_gap` apart when siblings, `subtree_gap` otherwise.
+pub(crate) fn tidy_breadth(
+    tree: &Tree,
+    extents: &[f32],
+    sibling_gap: f32,
+    subtree_gap: f32,
+) -> Vec<f32> {
+    let n = tree.children.len();
+    let parents = tree.parents();
+    let mut number = vec![0; n];
+    for kids in tree.children.iter() {
+        for (i, &c) in kids.iter().enumerate() {
+            number[c] = i;
+        }
+    }
+    let mut walker = Walker {
+        children: &tree.children,
+        parents: &parents,
+        extents,
+        sibling_gap,
+        subtree_gap,
+        number,
+        prelim: vec![0.0; n],
+        modifier: vec![0.0; n],
+        shift: vec![0.0; n],
+        change: vec![0.0; n],
+        thread: vec![None; n],
+        ancestor: (0..n).collect(),
+    };
+
+    let mut default_ancestors: Vec<usize> = (0..n).collect();
+    for v in tree.postorder() {
+        walker.first_walk(v);
+        if let Some(p) = parents[v] {
+            if walker.number[v] == 0 {
+                default_ancestors[p] = v;
+            }
+            default_ancestors[p] = walker.apportion(v, default_ancestors[p]);
+        }
+    }
+
+    let mut breadth = vec![0.0; n];
+    let mut stack = vec![(tree.root, -walker.prelim[tree.root])];
+    while let Some((v, m)) = stack.pop() {
+        breadth[v] = walker.prelim[v] + m;
+        for &w in tree.children[v].iter() {
+            stack.push((w, m + walker.modifier[v]));
+        }
+    }
+    breadth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tidy_breadth() {
+        // Small subtrees between two wide ones are spread evenly
+        //         0
+        //  1   2  3  4    5
+        // 6 7            8 9
+        let parents = vec![
+            None,
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(1),
+            Some(1),
+            Some(5),
+            Some(5),
+        ];
+        let tree = Tree::from_parents(&parents);
+        let mut extents = vec![1.0; 10];
+        extents[6] = 21.0;
+        extents[9] = 21.0;
+        let x = tidy_breadth(&tree, &extents, 1.0, 1.0);
+
+        assert_eq!(x[0], 0.0);
+        // Parents are centered over their children
+        assert!(((x[6] + x[7]) / 2.0 - x[1]).abs() < 1e-4);
+        assert!(((x[8] + x[9]) / 2.0 - x[5]).abs() < 1e-4);
+        assert!(((x[1] + x[5]) / 2.0 - x[0]).abs() < 1e-4);
+        // Children are in order, and the middle ones evenly spaced
+        assert!(x.windows(2).skip(1).take(4).all(|w| w[0] < w[1]));
+        let spacing = x[3] - x[2];
+        assert!((x[2] - x[1] - spacing).abs() < 1e-4);
+        assert!((x[5] - x[4] - spacing).abs() < 1e-4);
+        // Just enough for the subtrees of 1 and 5 not to overlap
+        assert!((x[8] - x[7] - 2.0).abs() < 1e-4);
+        assert!((spacing - 3.5).abs() < 1e-4);
+    }
+}
@@ -22,24 +22,12 @@ pub fn norm(n: &Position) -> f32 {
     n.iter().map(|i| i.clone().powi(2)).sum::<f32>().sqrt()
 }
 
-/// Allocate Vec without initializing
-#[allow(clippy::uninit_vec)]
+/// Allocate a zeroed Vec
+///
+/// Reading uninitialized floats is undefined behavior, so the buffer is zeroed, which is
+/// nearly free for fresh allocations.
 pub fn valloc(n: usize) -> Vec<f32> {
-    let mut v = Vec::with_capacity(n);
-    unsafe {
-        v.set_len(n);
-    }
-    v
-}
-
-pub(crate) unsafe fn split_at_mut_unchecked(s: &mut [f32], mid: usize) -> (&mut [f32], &mut [f32]) {
-    let len = s.len();
-    let ptr = s.as_mut_ptr();
-
-    (
-        std::slice::from_raw_parts_mut(ptr, mid),
-        std::slice::from_raw_parts_mut(ptr.add(mid), len - mid),
-    )
+    vec![0.0; n]
 }
 
 pub struct PointIter<'a> {
@@ -78,23 +66,23 @@ impl<'a> Iterator for PointIter<'a> {
 
 pub struct PointIterMut<'a> {
     pub dimensions: usize,
+    /// Offset of the next element in the iterated list
     pub offset: usize,
-    pub list: &'a mut Vec<f32>,
+    /// Remaining elements
+    pub list: &'a mut [f32],
 }
 
 impl<'a> Iterator for PointIterMut<'a> {
     type Item = &'a mut [f32];
 
-    fn next<'b>(&'b mut self) -> Option<Self::Item> {
-        if self.offset >= self.list.len() {
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.list.len() < self.dimensions || self.dimensions == 0 {
             return None;
         }
-        let ret: &'b mut [f32] = unsafe {
-            self.list
-                .get_unchecked_mut(self.offset..self.offset + self.dimensions)
-        };
+        let (ret, rest) = std::mem::take(&mut self.list).split_at_mut(self.dimensions);
+        self.list = rest;
         self.offset += self.dimensions;
-        Some(unsafe { std::mem::transmute(ret) })
+        Some(ret)
     }
 }
 
@@ -132,16 +120,20 @@ impl<'a> PointList {
         &mut self.points[offset..offset + self.dimensions]
     }
 
-    /// n1 < n2
+    /// Mutably borrows two distinct vectors, in any order.
+    ///
+    /// # Panics
+    /// If `n1 == n2`, or either is out of bounds.
     pub fn get_2_mut(&mut self, n1: usize, n2: usize) -> (&mut Position, &mut Position) {
-        let offset1 = n1 * self.dimensions;
-        let offset2 = n2 * self.dimensions;
-        unsafe {
-            let (s1, s2) = split_at_mut_unchecked(&mut self.points, offset2);
-            (
-                s1.get_unchecked_mut(offset1..offset1 + self.dimensions),
-                s2.get_unchecked_mut(..self.dimensions),
-            )
+        assert_ne!(n1, n2, "get_2_mut requires distinct vectors");
+        let d = self.dimensions;
+        let (lo, hi) = (n1.min(n2), n1.max(n2));
+        let (s1, s2) = self.points.split_at_mut(hi * d);
+        let (lo, hi) = (&mut s1[lo * d..(lo + 1) * d], &mut s2[..d]);
+        if n1 < n2 {
+            (lo, hi)
+        } else {
+            (hi, lo)
         }
     }
 
@@ -175,10 +167,11 @@ impl<'a> PointList {
     }
 
     pub fn iter_mut_from(&mut self, offset: usize) -> PointIterMut {
+        let offset = offset * self.dimensions;
         PointIterMut {
             dimensions: self.dimensions,
-            list: &mut self.points,
-            offset: offset * self.dimensions,
+            list: &mut self.points[offset..],
+            offset,
         }
     }
 
@@ -191,17 +184,103 @@ impl<'a> PointList {
     }
 }
 
-pub(crate) struct SendPtr<T>(pub std::ptr::NonNull<T>);
+/// Structure-of-arrays copy of a `PointList`, with one contiguous array per axis
+#[derive(Clone, Default)]
+pub struct PointListSoa {
+    /// `axes[d][n]` is the `d`-th coordinate of the `n`-th vector
+    pub axes: Vec<Vec<f32>>,
+}
+
+impl PointListSoa {
+    pub fn axis(&self, d: usize) -> &[f32] {
+        &self.axes[d]
+    }
+
+    /// Refreshes the copy from `list`, reusing the allocated arrays.
+    pub fn update(&mut self, list: &PointList) {
+        let n = list.points.len() / list.dimensions;
+        self.axes.resize_with(list.dimensions, Vec::new);
+        for (d, axis) in self.axes.iter_mut().enumerate() {
+            axis.clear();
+            axis.extend(list.points.iter().skip(d).step_by(list.dimensions).take(n));
+        }
+    }
+}
 
-impl<T> Copy for SendPtr<T> {}
-impl<T> Clone for SendPtr<T> {
-    fn clone(&self) -> Self {
-        SendPtr(self.0)
+impl From<&PointList> for PointListSoa {
+    fn from(list: &PointList) -> Self {
+        let mut soa = Self::default();
+        soa.update(list);
+        soa
     }
 }
 
-unsafe impl<T> Send for SendPtr<T> {}
-unsafe impl<T> Sync for SendPtr<T> {}
+/// Neighbours of each of the `n` nodes, in the order of `edges`
+pub fn adjacency(n: usize, edges: &[Edge]) -> Vec<Vec<usize>> {
+    let mut adjacency = vec![Vec::new(); n];
+    for &(n1, n2) in edges.iter() {
+        adjacency[n1].push(n2);
+        adjacency[n2].push(n1);
+    }
+    adjacency
+}
+
+/// Number of edges from `source` to each node, `usize::MAX` for unreachable nodes
+pub fn bfs_distances(adjacency: &[Vec<usize>], source: usize) -> Vec<usize> {
+    let mut distances = vec![usize::MAX; adjacency.len()];
+    let mut queue = std::collections::VecDeque::new();
+    distances[source] = 0;
+    queue.push_back(source);
+    while let Some(n1) = queue.pop_front() {
+        for &n2 in adjacency[n1].iter() {
+            if distances[n2] == usize::MAX {
+                distances[n2] = distances[n1] + 1;
+                queue.push_back(n2);
+            }
+        }
+    }
+    distances
+}
+
+/// Seeded pseudo-random generator (SplitMix64), for reproducible layouts
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Fisher-Yates shuffle
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// `n` points drawn uniformly in a cube of side `side` from the origin, a test fixture
+#[cfg(test)]
+pub(crate) fn random_points(n: usize, dimensions: usize, side: f32, seed: u64) -> PointList {
+    let mut rng = Rng::new(seed);
+    PointList {
+        dimensions,
+        points: (0..n * dimensions).map(|_| rng.next_f32() * side).collect(),
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -209,12 +288,12 @@ mod tests {
 
     #[test]
     fn test_clone_slice_mut() {
-        let a = [1, 2, 3, 4, 5];
+        let a = [1., 2., 3., 4., 5.];
         let mut bv = clone_slice_mut(&a);
         let b = bv.as_mut_slice();
-        b[2] = 6;
+        b[2] = 6.;
         assert_eq!(b.len(), 5);
-        assert_eq!(b, [1, 2, 6, 4, 5]);
+        assert_eq!(b, [1., 2., 6., 4., 5.]);
     }
 
     #[test]
@@ -226,5 +305,18 @@ mod tests {
         let (s1, s2) = a.get_2_mut(1, 3);
         assert_eq!(s1.to_vec(), [2., 3.]);
         assert_eq!(s2.to_vec(), [6., 7.]);
+        let (s1, s2) = a.get_2_mut(4, 2);
+        assert_eq!(s1.to_vec(), [8., 9.]);
+        assert_eq!(s2.to_vec(), [4., 5.]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_2_mut_same() {
+        let mut a = PointList {
+            dimensions: 2,
+            points: vec![0., 1., 2., 3.],
+        };
+        a.get_2_mut(1, 1);
     }
 }
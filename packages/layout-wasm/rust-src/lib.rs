@@ -1,7 +1,6 @@
 use std::vec;
 
 use antv_layout::*;
-use js_sys::Array;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
@@ -17,19 +16,11 @@ pub fn start() {
     console_error_panic_hook::set_once();
 }
 
-/// Force layout. eg. ForceAtlas2, Force2...
+/// Parameters shared by the force layouts.
 #[derive(Serialize, Deserialize)]
-pub struct ForceLayoutOptions {
+pub struct ForceSettingsOptions {
     pub name: usize,
     pub dimensions: usize,
-    /// A list of coordinates, e.g. `[x1, y1, x2, y2, ...]`.
-    pub nodes: Vec<f32>,
-    /// Assumes edges `(n1, n2)` respect `n1 < n2`
-    pub edges: Vec<(usize, usize)>,
-    /// A list of masses, e.g. `[m1, m2, ...]`.
-    pub masses: Vec<f32>,
-    /// A list of weights, e.g. `[e1, e2, ...]`.
-    pub weights: Vec<f32>,
     /// Iterations to execute.
     pub iterations: u32,
     pub distance_threshold_mode: usize,
@@ -61,77 +52,414 @@ pub struct ForceLayoutOptions {
     /// Fruchterman. The center of the graph.
     pub center: Vec<f32>,
     pub max_speed: f32,
+    /// Number of nodes computed by each thread. `0` runs the repulsion serially.
     pub chunk_size: usize,
     pub max_distance: f32,
+    /// Use the vectorized repulsion kernels. Build with `-C target-feature=+simd128`.
+    #[serde(default)]
+    pub simd: bool,
+    /// 0: all pairs, 1: spatial hash with cells of side `max_distance`.
+    #[serde(default)]
+    pub neighbor_search: usize,
+    /// Make the parallel repulsion reproducible across thread counts.
+    #[serde(default)]
+    pub deterministic: bool,
 }
 
-#[wasm_bindgen(js_name = "force")]
-pub fn force(val: JsValue) -> Array {
-    let options: ForceLayoutOptions = serde_wasm_bindgen::from_value(val).unwrap();
-
-    let mut layout = Layout::from_position_graph(
-        options.edges,
-        Nodes::Mass(options.masses),
-        options.nodes,
-        Some(options.weights),
+impl ForceSettingsOptions {
+    fn to_settings(&self) -> Settings {
         Settings {
-            name: match options.name {
+            name: match self.name {
                 0 => LayoutType::ForceAtlas2,
                 1 => LayoutType::Force2,
                 2 => LayoutType::Fruchterman,
                 _ => panic!("Unknown layout type"),
             },
-            chunk_size: Some(options.chunk_size),
-            dimensions: options.dimensions,
-            dissuade_hubs: options.dissuade_hubs,
-            ka: options.ka,
-            kg: options.kg,
-            kr: options.kr,
-            lin_log: options.lin_log,
-            prevent_overlapping: if !options.prevent_overlapping {
+            chunk_size: if self.chunk_size == 0 {
                 None
             } else {
-                Some((options.node_radius, options.kr_prime))
+                Some(self.chunk_size)
+            },
+            thread_pool: None,
+            simd: self.simd,
+            neighbor_search: match self.neighbor_search {
+                0 => NeighborSearch::AllPairs,
+                1 => NeighborSearch::Grid,
+                _ => panic!("Unknown neighbor search"),
             },
-            speed: options.speed,
-            strong_gravity: options.strong_gravity,
-            edge_strength: options.edge_strength,
-            link_distance: options.link_distance,
-            node_strength: options.node_strength,
-            coulomb_dis_scale: options.coulomb_dis_scale,
-            factor: options.factor,
-            damping: options.damping,
-            interval: options.interval,
-            center: options.center,
-            max_speed: options.max_speed,
-            min_movement: options.min_movement,
-            distance_threshold_mode: match options.distance_threshold_mode {
+            deterministic: self.deterministic,
+            dimensions: self.dimensions,
+            dissuade_hubs: self.dissuade_hubs,
+            ka: self.ka,
+            kg: self.kg,
+            kr: self.kr,
+            lin_log: self.lin_log,
+            prevent_overlapping: if !self.prevent_overlapping {
+                None
+            } else {
+                Some((self.node_radius, self.kr_prime))
+            },
+            speed: self.speed,
+            strong_gravity: self.strong_gravity,
+            edge_strength: self.edge_strength,
+            link_distance: self.link_distance,
+            node_strength: self.node_strength,
+            coulomb_dis_scale: self.coulomb_dis_scale,
+            factor: self.factor,
+            damping: self.damping,
+            interval: self.interval,
+            center: self.center.clone(),
+            max_speed: self.max_speed,
+            min_movement: self.min_movement,
+            distance_threshold_mode: match self.distance_threshold_mode {
                 0 => DistanceThresholdMode::Average,
                 1 => DistanceThresholdMode::Min,
                 2 => DistanceThresholdMode::Max,
                 _ => panic!("Unknown layout type"),
             },
-            max_distance: options.max_distance,
-        },
-    );
-
-    for i in 0..options.iterations {
-        if layout.iteration(i as usize) {
-            // Break early if layout is convergent.
-            break;
+            max_distance: self.max_distance,
         }
     }
 
-    let nodes = Array::new();
-    for pos in layout.points.iter() {
-        nodes.push(&pos[0].into());
-        nodes.push(&pos[1].into());
-        if options.dimensions == 3 {
-            nodes.push(&pos[2].into());
-        }
+    fn run(&self, layout: &mut Layout) {
+        // Stops early if layout is convergent.
+        layout.run(RunOptions {
+            max_iterations: self.iterations as usize,
+            ..Default::default()
+        });
     }
+}
 
-    nodes
+/// Force layout. eg. ForceAtlas2, Force2...
+#[derive(Serialize, Deserialize)]
+pub struct ForceLayoutOptions {
+    /// A list of coordinates, e.g. `[x1, y1, x2, y2, ...]`.
+    pub nodes: Vec<f32>,
+    /// Edges `(n1, n2)` in any orientation. Self-loops are ignored.
+    pub edges: Vec<(usize, usize)>,
+    /// A list of masses, e.g. `[m1, m2, ...]`.
+    pub masses: Vec<f32>,
+    /// A list of weights, e.g. `[e1, e2, ...]`.
+    pub weights: Vec<f32>,
+    /// Merge duplicate edges, summing their weights.
+    #[serde(default)]
+    pub merge_edges: bool,
+    #[serde(flatten)]
+    pub settings: ForceSettingsOptions,
+}
+
+/// Edges reversed to be oriented, merged by `merge_edges`, or dropped as self-loops.
+#[derive(Serialize, Deserialize)]
+pub struct EdgeReportResult {
+    pub reversed: usize,
+    pub merged: usize,
+    pub self_loops: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForceResult {
+    /// A list of coordinates, e.g. `[x1, y1, x2, y2, ...]`.
+    pub nodes: Vec<f32>,
+    pub report: EdgeReportResult,
+}
+
+#[wasm_bindgen(js_name = "force")]
+pub fn force(val: JsValue) -> JsValue {
+    let options: ForceLayoutOptions = serde_wasm_bindgen::from_value(val).unwrap();
+
+    let (edges, weights, report) = if options.merge_edges {
+        normalize_edges(&options.edges, Some(&options.weights))
+    } else {
+        orient_edges(&options.edges, Some(&options.weights))
+    };
+
+    let mut layout = Layout::from_position_graph(
+        edges,
+        Nodes::Mass(options.masses),
+        options.nodes,
+        weights,
+        options.settings.to_settings(),
+    );
+    options.settings.run(&mut layout);
+
+    serde_wasm_bindgen::to_value(&ForceResult {
+        nodes: layout.points.points,
+        report: EdgeReportResult {
+            reversed: report.reversed,
+            merged: report.merged,
+            self_loops: report.self_loops,
+        },
+    })
+    .unwrap()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForceGraphNode {
+    pub id: String,
+    /// Defaults to the degree of the node, at least `1`.
+    pub mass: Option<f32>,
+    /// Diameter used with `prevent_overlapping`: two nodes overlap when closer than the
+    /// mean of their sizes. Defaults to `node_radius`, the distance under which nodes overlap.
+    pub size: Option<f32>,
+    /// Initial position, defaults to a spiral around `center`.
+    pub position: Option<Vec<f32>>,
+    /// Keep the node at its initial position.
+    #[serde(default)]
+    pub fixed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForceGraphEdge {
+    pub source: String,
+    pub target: String,
+    /// Defaults to `1`.
+    pub weight: Option<f32>,
+}
+
+/// Force layout of nodes identified by string ids.
+#[derive(Serialize, Deserialize)]
+pub struct ForceGraphOptions {
+    pub nodes: Vec<ForceGraphNode>,
+    /// Edges in any orientation. Duplicates are merged, self-loops are ignored.
+    pub edges: Vec<ForceGraphEdge>,
+    #[serde(flatten)]
+    pub settings: ForceSettingsOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForceGraphNodeResult {
+    pub id: String,
+    pub position: Vec<f32>,
+}
+
+#[wasm_bindgen(js_name = "forceGraph")]
+pub fn force_graph(val: JsValue) -> JsValue {
+    let options: ForceGraphOptions = serde_wasm_bindgen::from_value(val).unwrap();
+
+    let mut builder = ForceGraphBuilder::new();
+    for node in options.nodes {
+        builder.add_node(
+            node.id,
+            NodeOptions {
+                mass: node.mass,
+                size: node.size,
+                position: node.position,
+                fixed: node.fixed,
+            },
+        );
+    }
+    for edge in options.edges {
+        builder.add_edge(edge.source, edge.target, edge.weight.unwrap_or(1.0));
+    }
+
+    let mut graph = builder.build(options.settings.to_settings());
+    options.settings.run(&mut graph.layout);
+
+    let nodes: Vec<ForceGraphNodeResult> = graph
+        .positions()
+        .map(|(id, position)| ForceGraphNodeResult {
+            id: id.clone(),
+            position: position.to_vec(),
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&nodes).unwrap()
+}
+
+/// Force-directed edge bundling of a finished layout.
+#[derive(Serialize, Deserialize)]
+pub struct EdgeBundlingOptions {
+    /// A list of coordinates, e.g. `[x1, y1, x2, y2, ...]`.
+    pub nodes: Vec<f32>,
+    pub edges: Vec<(usize, usize)>,
+    pub dimensions: Option<usize>,            // default 2
+    pub stiffness: Option<f32>,               // default 0.1
+    pub cycles: Option<usize>,                // default 6
+    pub initial_subdivisions: Option<usize>,  // default 1
+    pub subdivision_rate: Option<usize>,      // default 2
+    pub initial_step: Option<f32>,            // default 0.001, relative to the mean edge length
+    pub initial_iterations: Option<usize>,    // default 50
+    pub iteration_rate: Option<f32>,          // default 2 / 3
+    pub compatibility_threshold: Option<f32>, // default 0.6
+}
+
+/// Returns a polyline for each edge, e.g. `[x1, y1, x2, y2, ...]`.
+#[wasm_bindgen(js_name = "edgeBundling")]
+pub fn edge_bundling(val: JsValue) -> JsValue {
+    let options: EdgeBundlingOptions = serde_wasm_bindgen::from_value(val).unwrap();
+    let defaults = bundling::FdebOptions::default();
+    let fdeb_options = bundling::FdebOptions {
+        stiffness: options.stiffness.unwrap_or(defaults.stiffness),
+        cycles: options.cycles.unwrap_or(defaults.cycles),
+        initial_subdivisions: options
+            .initial_subdivisions
+            .unwrap_or(defaults.initial_subdivisions),
+        subdivision_rate: options
+            .subdivision_rate
+            .unwrap_or(defaults.subdivision_rate),
+        initial_step: options.initial_step.unwrap_or(defaults.initial_step),
+        initial_iterations: options
+            .initial_iterations
+            .unwrap_or(defaults.initial_iterations),
+        iteration_rate: options.iteration_rate.unwrap_or(defaults.iteration_rate),
+        compatibility_threshold: options
+            .compatibility_threshold
+            .unwrap_or(defaults.compatibility_threshold),
+    };
+    let points = PointList {
+        dimensions: options.dimensions.unwrap_or(2),
+        points: options.nodes,
+    };
+
+    let lines: Vec<Vec<f32>> = bundling::fdeb(&points, &options.edges, &fdeb_options)
+        .into_iter()
+        .map(|line| line.points)
+        .collect();
+    serde_wasm_bindgen::to_value(&lines).unwrap()
+}
+
+/// Tree layouts of a rooted tree.
+#[derive(Serialize, Deserialize)]
+pub struct TreeLayoutOptions {
+    /// The parent of each node, `null` for the root.
+    pub parents: Vec<Option<usize>>,
+    /// A list of sizes, e.g. `[width1, height1, width2, height2, ...]`.
+    pub sizes: Option<Vec<f32>>,
+    pub layout: Option<String>, // tidy, dendrogram, indented, mindmap // default tidy
+    pub direction: Option<String>, // tb, bt, lr, rl, h, v // default tb
+    pub node_width: Option<f32>, // default 10
+    pub node_height: Option<f32>, // default 10
+    pub sibling_gap: Option<f32>, // default 10
+    pub subtree_gap: Option<f32>, // default 20
+    pub level_gap: Option<f32>, // default 40
+    /// Gap between each level and the next, instead of `level_gap`.
+    #[serde(default)]
+    pub level_gaps: Vec<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TreeResult {
+    /// The centers of the nodes, e.g. `[x1, y1, x2, y2, ...]`.
+    pub nodes: Vec<f32>,
+}
+
+/// Throws on an unknown `layout` or `direction`.
+#[wasm_bindgen(js_name = "tree")]
+pub fn tree_layout(val: JsValue) -> Result<JsValue, JsValue> {
+    let options: TreeLayoutOptions = serde_wasm_bindgen::from_value(val)?;
+    let defaults = tree::TreeOptions::default();
+    let tree_options = tree::TreeOptions {
+        direction: match options
+            .direction
+            .as_deref()
+            .map(|d| d.to_lowercase())
+            .as_deref()
+        {
+            None | Some("tb") => tree::Direction::TB,
+            Some("bt") => tree::Direction::BT,
+            Some("lr") => tree::Direction::LR,
+            Some("rl") => tree::Direction::RL,
+            Some("h") => tree::Direction::H,
+            Some("v") => tree::Direction::V,
+            _ => return Err(JsValue::from_str("Unknown direction")),
+        },
+        node_size: (
+            options.node_width.unwrap_or(defaults.node_size.0),
+            options.node_height.unwrap_or(defaults.node_size.1),
+        ),
+        sibling_gap: options.sibling_gap.unwrap_or(defaults.sibling_gap),
+        subtree_gap: options.subtree_gap.unwrap_or(defaults.subtree_gap),
+        level_gap: options.level_gap.unwrap_or(defaults.level_gap),
+        level_gaps: options.level_gaps,
+    };
+
+    let tree = tree::Tree::from_parents(&options.parents);
+    let sizes: Option<Vec<(f32, f32)>> = options
+        .sizes
+        .map(|sizes| sizes.chunks(2).map(|s| (s[0], s[1])).collect());
+    let sizes = sizes.as_deref();
+    let points = match options.layout.as_deref() {
+        None | Some("tidy") => tree::tidy_tree(&tree, sizes, &tree_options),
+        Some("dendrogram") => tree::dendrogram(&tree, sizes, &tree_options),
+        Some("indented") => tree::indented_tree(&tree, sizes, &tree_options),
+        Some("mindmap") => tree::mindmap(&tree, sizes, &tree_options),
+        _ => return Err(JsValue::from_str("Unknown tree layout")),
+    };
+
+    Ok(serde_wasm_bindgen::to_value(&TreeResult {
+        nodes: points.points,
+    })?)
+}
+
+/// Linear layout, e.g. arc diagrams.
+#[derive(Serialize, Deserialize)]
+pub struct LinearLayoutOptions {
+    pub nodes: usize,
+    pub edges: Vec<(usize, usize)>,
+    /// A list of weights, e.g. `[e1, e2, ...]`.
+    pub weights: Option<Vec<f32>>,
+    /// Initial order of the nodes, instead of the spectral one.
+    pub order: Option<Vec<usize>>,
+    pub iterations: Option<usize>, // default 20
+    pub objective: Option<String>, // length, crossings // default crossings
+    pub spacing: Option<f32>,      // default 20
+    pub both_sides: Option<bool>,  // default true
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArcResult {
+    pub center: f32,
+    pub radius: f32,
+    pub above: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LinearResult {
+    order: Vec<usize>,
+    nodes: Vec<f32>,
+    arcs: Vec<ArcResult>,
+    crossings: usize,
+}
+
+#[wasm_bindgen(js_name = "linear")]
+pub fn linear_layout(val: JsValue) -> JsValue {
+    let options: LinearLayoutOptions = serde_wasm_bindgen::from_value(val).unwrap();
+    let defaults = linear::LinearOptions::default();
+    let linear_options = linear::LinearOptions {
+        initial: options
+            .order
+            .map_or(defaults.initial, linear::LinearOrder::Given),
+        iterations: options.iterations.unwrap_or(defaults.iterations),
+        objective: match options.objective.as_deref() {
+            None | Some("crossings") => linear::LinearObjective::Crossings,
+            Some("length") => linear::LinearObjective::Length,
+            _ => panic!("Unknown objective"),
+        },
+        spacing: options.spacing.unwrap_or(defaults.spacing),
+        both_sides: options.both_sides.unwrap_or(defaults.both_sides),
+    };
+
+    let layout = linear::linear_layout(
+        options.nodes,
+        &options.edges,
+        options.weights.as_deref(),
+        &linear_options,
+    );
+    let arcs = layout
+        .arcs
+        .iter()
+        .map(|arc| ArcResult {
+            center: arc.center,
+            radius: arc.radius,
+            above: arc.above,
+        })
+        .collect();
+    serde_wasm_bindgen::to_value(&LinearResult {
+        order: layout.order,
+        nodes: layout.points.points,
+        arcs,
+        crossings: layout.crossings,
+    })
+    .unwrap()
 }
 
 #[derive(Serialize, Deserialize)]
@@ -263,3 +591,58 @@ pub fn dagre(val: JsValue) -> JsValue {
 
     serde_wasm_bindgen::to_value(&DagreResult { nodes, edges }).unwrap()
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct BipartiteLayoutOptions {
+    /// A list of sizes, e.g. `[width1, height1, width2, height2, ...]`.
+    pub nodes: Vec<f32>,
+    /// Whether each node is on the bottom layer, else on the top one.
+    pub bottom: Vec<bool>,
+    /// Edges `(n1, n2)` join both layers, in any orientation.
+    pub edges: Vec<(usize, usize)>,
+    /// A list of weights, e.g. `[e1, e2, ...]`.
+    pub weights: Option<Vec<f32>>,
+
+    pub nodesep: Option<f32>,  // default 50
+    pub ranksep: Option<f32>,  // default 50
+    pub sweeps: Option<usize>, // default 4
+    pub align: Option<String>, // ul, ur, dl, dr // default balanced
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BipartiteResult {
+    layers: [Vec<usize>; 2],
+    /// The centers of the nodes, e.g. `[x1, y1, x2, y2, ...]`.
+    nodes: Vec<f32>,
+    /// A polyline for each edge, e.g. `[x1, y1, x2, y2, ...]`.
+    edges: Vec<Vec<f32>>,
+    crossings: f32,
+}
+
+#[wasm_bindgen(js_name = "bipartite")]
+pub fn bipartite(val: JsValue) -> JsValue {
+    let options: BipartiteLayoutOptions = serde_wasm_bindgen::from_value(val).unwrap();
+    let defaults = BipartiteOptions::default();
+    let bipartite_options = BipartiteOptions {
+        node_sep: options.nodesep.unwrap_or(defaults.node_sep),
+        rank_sep: options.ranksep.unwrap_or(defaults.rank_sep),
+        sweeps: options.sweeps.unwrap_or(defaults.sweeps),
+        align: options.align.map(|align| align.to_lowercase()),
+    };
+    let sizes: Vec<(f32, f32)> = options.nodes.chunks(2).map(|s| (s[0], s[1])).collect();
+
+    let layout = bipartite_layout(
+        &sizes,
+        &options.bottom,
+        &options.edges,
+        options.weights.as_deref(),
+        &bipartite_options,
+    );
+    serde_wasm_bindgen::to_value(&BipartiteResult {
+        layers: layout.layers,
+        nodes: layout.points.points,
+        edges: layout.edges.into_iter().map(|line| line.points).collect(),
+        crossings: layout.crossings,
+    })
+    .unwrap()
+}
@@ -2,13 +2,29 @@ import { AntVDagreLayout } from './dagre';
 import { ForceLayout } from './force';
 import { ForceAtlas2Layout } from './forceatlas2';
 import { FruchtermanLayout } from './fruchterman';
-import type { Threads } from './interface';
+import type {
+  EdgeBundlingOptions,
+  EdgeReport,
+  ForceGraphEdge,
+  ForceGraphLayoutOptions,
+  ForceGraphNode,
+  ForceGraphNodeResult,
+  Threads,
+  TreeLayoutOptions,
+} from './interface';
 import { initThreads, supportsThreads } from './main';
 
 export {
   supportsThreads,
   initThreads,
   Threads,
+  EdgeBundlingOptions,
+  EdgeReport,
+  ForceGraphEdge,
+  ForceGraphLayoutOptions,
+  ForceGraphNode,
+  ForceGraphNodeResult,
+  TreeLayoutOptions,
   FruchtermanLayout,
   ForceAtlas2Layout,
   ForceLayout,
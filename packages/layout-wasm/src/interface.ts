@@ -1,11 +1,22 @@
 export interface Threads {
   forceatlas2: (
     options: Forceatlas2LayoutOptions,
-  ) => Promise<{ nodes: number[] }>;
-  force2: (options: Force2LayoutOptions) => Promise<{ nodes: number[] }>;
+  ) => Promise<{ nodes: number[]; report: EdgeReport }>;
+  force2: (
+    options: Force2LayoutOptions,
+  ) => Promise<{ nodes: number[]; report: EdgeReport }>;
   fruchterman: (
     options: FruchtermanLayoutOptions,
-  ) => Promise<{ nodes: number[] }>;
+  ) => Promise<{ nodes: number[]; report: EdgeReport }>;
+  forceGraph: (
+    options: ForceGraphLayoutOptions,
+  ) => Promise<{ nodes: ForceGraphNodeResult[] }>;
+  /** A polyline for each edge, e.g. `[x1, y1, x2, y2, ...]` */
+  edgeBundling: (
+    options: EdgeBundlingOptions,
+  ) => Promise<{ edges: number[][] }>;
+  /** Rejects on an unknown `layout` or `direction` */
+  tree: (options: TreeLayoutOptions) => Promise<{ nodes: number[] }>;
   dagre: (options: AntVDagreLayoutOptions) => Promise<{
     nodes: {
       x: number;
@@ -60,6 +71,8 @@ export interface ForceLayoutOptions {
   edges: number[][];
   masses?: number[];
   weights?: number[];
+  /** Merge duplicate edges, summing their weights. */
+  merge_edges?: boolean;
   iterations: number;
   min_movement?: number;
   distance_threshold_mode?: number;
@@ -67,6 +80,85 @@ export interface ForceLayoutOptions {
   max_distance?: number;
 }
 
+/**
+ * Edges changed before the layout: reversed to `n1 < n2`, merged into a previous edge
+ * between the same nodes with `merge_edges`, or dropped as self-loops.
+ */
+export interface EdgeReport {
+  reversed: number;
+  merged: number;
+  self_loops: number;
+}
+
+export interface ForceGraphNode {
+  id: string;
+  mass?: number; // default degree, at least 1
+  size?: number; // diameter used with prevent_overlapping
+  position?: number[]; // default spiral around center
+  fixed?: boolean;
+}
+
+export interface ForceGraphEdge {
+  source: string;
+  target: string;
+  weight?: number; // default 1
+}
+
+/**
+ * Force layout of nodes identified by string ids. Duplicate edges are merged, self-loops
+ * are ignored.
+ */
+export interface ForceGraphLayoutOptions
+  extends Omit<ForceLayoutOptions, 'nodes' | 'edges' | 'masses' | 'weights'>,
+    Partial<
+      Omit<
+        Forceatlas2LayoutOptions & Force2LayoutOptions & FruchtermanLayoutOptions,
+        keyof ForceLayoutOptions
+      >
+    > {
+  name?: 0 | 1 | 2; // forceatlas2, force2, fruchterman // default forceatlas2
+  nodes: ForceGraphNode[];
+  edges: ForceGraphEdge[];
+}
+
+export interface ForceGraphNodeResult {
+  id: string;
+  position: number[];
+}
+
+/**
+ * Force-directed edge bundling of a finished layout.
+ */
+export interface EdgeBundlingOptions {
+  nodes: number[];
+  edges: number[][];
+  dimensions?: number; // default 2
+  stiffness?: number; // default 0.1
+  cycles?: number; // default 6
+  initial_subdivisions?: number; // default 1
+  subdivision_rate?: number; // default 2
+  initial_step?: number; // default 0.001, relative to the mean edge length
+  initial_iterations?: number; // default 50
+  iteration_rate?: number; // default 2 / 3
+  compatibility_threshold?: number; // default 0.6
+}
+
+/**
+ * Tree layouts of a rooted tree.
+ */
+export interface TreeLayoutOptions {
+  parents: (number | null)[]; // null for the root
+  sizes?: number[]; // [width1, height1, width2, height2, ...]
+  layout?: 'tidy' | 'dendrogram' | 'indented' | 'mindmap'; // default tidy
+  direction?: 'tb' | 'bt' | 'lr' | 'rl' | 'h' | 'v'; // default tb
+  node_width?: number; // default 10
+  node_height?: number; // default 10
+  sibling_gap?: number; // default 10
+  subtree_gap?: number; // default 20
+  level_gap?: number; // default 40
+  level_gaps?: number[]; // gap between each level and the next, instead of level_gap
+}
+
 export interface AntVDagreLayoutOptions {
   nodes: number[];
   edges: number[][];
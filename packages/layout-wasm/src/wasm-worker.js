@@ -27,35 +27,45 @@ const DEFAULT_LAYOUT_OPTIONS = {
   max_distance: 100.0,
 };
 
-const wrapTransfer = (name, force) => {
-  return (options) => {
-    const layoutOptions = {
-      name,
-      ...DEFAULT_LAYOUT_OPTIONS,
-      ...options,
-    };
+const withDefaults = (name, options) => {
+  const layoutOptions = {
+    name,
+    ...DEFAULT_LAYOUT_OPTIONS,
+    ...options,
+  };
 
-    // calculate fruchterman layout options
-    if (name === 2) {
-      // @ts-ignore
-      const area = layoutOptions.width * layoutOptions.height;
-      const maxDisplace = Math.sqrt(area) / 10;
-      const k2 = area / (layoutOptions.nodes.length + 1);
-      const k = Math.sqrt(k2);
-      layoutOptions.ka = k;
-      layoutOptions.interval = 0.99;
-      layoutOptions.damping = maxDisplace;
-    }
+  // calculate fruchterman layout options
+  if (name === 2) {
+    // @ts-ignore
+    const area = layoutOptions.width * layoutOptions.height;
+    const maxDisplace = Math.sqrt(area) / 10;
+    const k2 = area / (layoutOptions.nodes.length + 1);
+    const k = Math.sqrt(k2);
+    layoutOptions.ka = k;
+    layoutOptions.interval = 0.99;
+    layoutOptions.damping = maxDisplace;
+  }
+  return layoutOptions;
+};
 
-    const positions = force(layoutOptions);
+const wrapTransfer = (name, force) => {
+  return (options) => {
+    const { nodes, report } = force(withDefaults(name, options));
 
     return {
       // Little perf boost to transfer data to the main thread w/o copying.
-      nodes: Comlink.transfer(positions, [positions]),
+      nodes: Comlink.transfer(nodes, [nodes]),
+      report,
     };
   };
 };
 
+const wrapForceGraph = (forceGraph) => {
+  return ({ name = 0, ...options }) => {
+    return { nodes: forceGraph(withDefaults(name, options)) };
+  };
+};
+
 const wrapDagre = (dagre) => {
   return (options) => {
     const { nodes, edges } = dagre(options);
@@ -67,12 +77,31 @@ const wrapDagre = (dagre) => {
   };
 };
 
+const wrapEdgeBundling = (edgeBundling) => {
+  return (options) => {
+    return { edges: edgeBundling(options) };
+  };
+};
+
+const wrapTree = (tree) => {
+  return (options) => {
+    const { nodes } = tree(options);
+    return {
+      // Little perf boost to transfer data to the main thread w/o copying.
+      nodes: Comlink.transfer(nodes, [nodes]),
+    };
+  };
+};
+
 // Wrap wasm-bindgen exports (the `generate` function) to add time measurement.
-function wrapExports({ force, dagre }) {
+function wrapExports({ force, forceGraph, edgeBundling, tree, dagre }) {
   return {
     forceatlas2: wrapTransfer(0, force),
     force2: wrapTransfer(1, force),
     fruchterman: wrapTransfer(2, force),
+    forceGraph: wrapForceGraph(forceGraph),
+    edgeBundling: wrapEdgeBundling(edgeBundling),
+    tree: wrapTree(tree),
     dagre: wrapDagre(dagre),
   };
 }